@@ -1,8 +1,57 @@
 // src/ai/connector.rs
-use anyhow::Result;
+use anyhow::{Result, anyhow};
+use std::fmt;
+
+/// Which AI backend analyzes the captures
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum AiBackend {
+    /// Local Ollama server
+    Ollama,
+    /// Deterministic canned response, no model needed (for testing and demos)
+    Mock,
+    /// External program given by --cmd (e.g. a llama.cpp multimodal binary)
+    Command,
+}
 
 /// Trait defining the interface for AI processing
 pub trait AiConnector: Send + Sync {
     /// Process an image and return the AI's response
     fn process_image(&mut self, image_data: &[u8]) -> Result<String>;
+    
+    /// Process several images sent together in one request. Backends that take a single image only get the first
+    fn process_images(&mut self, images: &[&[u8]]) -> Result<String> {
+        match images.first() {
+            Some(image_data) => self.process_image(image_data),
+            None => Err(anyhow!("No image to process")),
+        }
+    }
+    
+    /// Short name for logs, e.g. "ollama (llava:latest)"
+    fn name(&self) -> String;
+    
+    /// Model that answers, e.g. "llava:latest", for usage stats and --rerun-with; backends without one give their name
+    fn model(&self) -> String {
+        self.name()
+    }
+}
+
+/// Error marking a backend that couldn't be reached at all (as opposed to one that failed the analysis),
+/// so a fallback chain knows to try the next backend
+#[derive(Debug)]
+pub struct BackendUnavailable(pub String);
+
+impl fmt::Display for BackendUnavailable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for BackendUnavailable {}
+
+/// Whether an error means the backend couldn't be reached (connection refused, timeout, missing binary)
+pub fn is_unavailable(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        cause.downcast_ref::<BackendUnavailable>().is_some()
+            || cause.downcast_ref::<reqwest::Error>().map_or(false, |e| e.is_connect() || e.is_timeout())
+    })
 }
\ No newline at end of file
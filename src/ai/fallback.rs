@@ -0,0 +1,77 @@
+// src/ai/fallback.rs
+use anyhow::{Result, anyhow};
+use log::{info, warn};
+
+use super::connector::{self, AiConnector};
+
+//Tries each backend in order, moving on only when one can't be reached.
+//A backend that answers with an error (bad model, invalid JSON, ...) ends the chain with that error
+pub struct FallbackConnector {
+    backends: Vec<Box<dyn AiConnector>>,
+    last_served_by: Option<usize>,
+}
+
+impl FallbackConnector {
+    pub fn new(backends: Vec<Box<dyn AiConnector>>) -> Self {
+        Self {
+            backends,
+            last_served_by: None,
+        }
+    }
+    
+    //Name of the backend that answered the last successful request
+    pub fn last_served_by(&self) -> Option<String> {
+        self.last_served_by.map(|index| self.backends[index].name())
+    }
+    
+    //Run the request on each backend in turn until one can be reached
+    fn serve(&mut self, mut request: impl FnMut(&mut dyn AiConnector) -> Result<String>) -> Result<String> {
+        self.last_served_by = None;
+        let mut unavailable = Vec::new();
+        
+        for (index, backend) in self.backends.iter_mut().enumerate() {
+            match request(backend.as_mut()) {
+                Ok(response) => {
+                    info!("Request served by {}", backend.name());
+                    self.last_served_by = Some(index);
+                    return Ok(response);
+                }
+                Err(e) if connector::is_unavailable(&e) => {
+                    warn!("{} is unavailable ({}), trying the next backend", backend.name(), e);
+                    unavailable.push((backend.name(), e));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        
+        //A lone backend's own error says more than the summary
+        if unavailable.len() == 1 {
+            return Err(unavailable.remove(0).1);
+        }
+        let reasons: Vec<String> = unavailable.iter().map(|(name, e)| format!("{}: {}", name, e)).collect();
+        Err(anyhow!("No backend could be reached:\n  {}", reasons.join("\n  ")))
+    }
+}
+
+impl AiConnector for FallbackConnector {
+    fn process_image(&mut self, image_data: &[u8]) -> Result<String> {
+        self.serve(|backend| backend.process_image(image_data))
+    }
+    
+    fn process_images(&mut self, images: &[&[u8]]) -> Result<String> {
+        self.serve(|backend| backend.process_images(images))
+    }
+    
+    fn name(&self) -> String {
+        if let [backend] = self.backends.as_slice() {
+            return backend.name();
+        }
+        let names: Vec<String> = self.backends.iter().map(|b| b.name()).collect();
+        format!("fallback [{}]", names.join(", "))
+    }
+    
+    fn model(&self) -> String {
+        //Before any request, the backend that will be tried first
+        self.backends.get(self.last_served_by.unwrap_or(0)).map_or_else(|| self.name(), |backend| backend.model())
+    }
+}
\ No newline at end of file
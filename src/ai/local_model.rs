@@ -3,30 +3,264 @@ use anyhow::{Result, anyhow};
 use log::{info, warn};
 use serde::{Serialize, Deserialize};
 use reqwest::blocking::Client;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
 use base64::{Engine as _, engine::general_purpose};
+use std::borrow::Cow;
+use std::io::{BufRead, BufReader};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 
 use super::connector::AiConnector;
+use super::preprocess::{self, PreprocessOp};
+use crate::capture::screenshot;
+
+/// How long a single request to Ollama may take
+pub const REQUEST_TIMEOUT_SECS: u64 = 300;
+
+/// Ports probed on localhost by `discover_ollama`
+const DISCOVERY_PORTS: [u16; 2] = [11434, 11435];
+
+/// How long a discovery probe waits for a server to answer
+const DISCOVERY_TIMEOUT: Duration = Duration::from_millis(800);
+
+/// Vision model suggested when none is installed
+pub const RECOMMENDED_VISION_MODEL: &str = "llava:latest";
+
+/// Longer side, in pixels, of the thumbnail sent for a quick look
+pub const QUICK_LOOK_DIMENSION: u32 = 384;
+
+/// Prompt used when the user doesn't give one
+pub const DEFAULT_PROMPT: &str = "Describe what you see in this image in detail, focusing on any text, UI elements, and visual content.";
+
+//Addresses discover_ollama tries, in order
+//OLLAMA_HOST isn't among them: when it is set it is used directly and discovery never runs
+pub fn discovery_candidates() -> Vec<String> {
+    DISCOVERY_PORTS.iter().map(|port| format!("http://localhost:{}", port)).collect()
+}
+
+//Find a running Ollama server: the first candidate whose /api/tags answers
+pub fn discover_ollama() -> Option<String> {
+    let client = Client::builder().timeout(DISCOVERY_TIMEOUT).build().ok()?;
+    discovery_candidates().into_iter().find(|url| {
+        match client.get(format!("{}/api/tags", url)).send() {
+            Ok(response) => response.status().is_success(),
+            Err(_) => false,
+        }
+    })
+}
+
+//Names in an /api/tags reply
+fn model_names(tags: &serde_json::Value) -> Vec<String> {
+    tags["models"].as_array()
+        .map(|models| models.iter().filter_map(|model| model["name"].as_str().map(String::from)).collect())
+        .unwrap_or_default()
+}
+
+//Turn what the user typed into an installed model's exact name: trims it, fixes its case and, when it has
+//no tag, adds the tag of an installed model with that base name (":latest" first). A name that still isn't
+//installed is returned as typed so it can be pulled; see similar_installed_model for a suggestion.
+//With no installed models to compare against, only the spaces are trimmed
+pub fn normalize_model_name(name: &str, installed: &[String]) -> Result<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(anyhow!("The model name is empty"));
+    }
+    if let Some(exact) = installed.iter().find(|model| model.eq_ignore_ascii_case(name)) {
+        return Ok(exact.clone());
+    }
+    let base_of = |model: &str| model.split(':').next().unwrap_or("").to_lowercase();
+    let base = base_of(name);
+    if !name.contains(':') {
+        let mut tagged: Vec<&String> = installed.iter().filter(|model| base_of(model) == base).collect();
+        tagged.sort_by_key(|model| !model.ends_with(":latest"));
+        if let Some(model) = tagged.first() {
+            return Ok(model.to_string());
+        }
+    }
+    Ok(name.to_string())
+}
+
+//An installed model whose name is a likely typo of a model that isn't installed, to suggest alongside
+//pulling it. None when the model is installed or nothing is close enough
+pub fn similar_installed_model(name: &str, installed: &[String]) -> Option<String> {
+    if installed.iter().any(|model| model.eq_ignore_ascii_case(name)) {
+        return None;
+    }
+    let base_of = |model: &str| model.split(':').next().unwrap_or("").to_lowercase();
+    let base = base_of(name);
+    installed.iter()
+        .map(|model| (edit_distance(&base, &base_of(model)), model))
+        .filter(|(distance, _)| (1..=2).contains(distance))
+        .min_by_key(|(distance, model)| (*distance, !model.ends_with(":latest")))
+        .map(|(_, model)| model.clone())
+}
+
+//Levenshtein distance between two strings, by characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
 
 //Implementation for Ollama local LLM processing
+#[derive(Clone)]
 pub struct LocalModel {
     ollama_url: String,
     model_name: String,
     client: Client,
+    headers: HeaderMap,
     prompt: String,
+    system_prompt: Option<String>,
+    preprocess: Vec<PreprocessOp>,
+    response_format: ResponseFormat,
+    last_stats: Option<GenerationStats>,
+    stream_sink: Option<Arc<Mutex<String>>>,
+    keep_alive: Option<String>,
+    options: GenerationOptions,
+    max_dimension: Option<u32>,
+    request_timeout: Duration,
+    //Earlier (question, answer) turns about the same image, replayed ahead of the prompt
+    history: Vec<(String, String)>,
+}
+
+/// Sampling options passed through to Ollama; unset ones use the model's defaults
+#[derive(Serialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct GenerationOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    /// Most tokens the model may generate
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_predict: Option<u32>,
+}
+
+/// Named bundle of resolution, token limit, sampling and timeout settings
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum Quality {
+    /// Downscale to 1024 px and cap the reply at 256 tokens, with a 2 minute timeout
+    Fast,
+    /// Full resolution and the model's own limits (the defaults)
+    Balanced,
+    /// Full resolution, up to 2048 tokens at a low temperature, with a 10 minute timeout
+    Accurate,
+}
+
+impl Quality {
+    pub const ALL: [Quality; 3] = [Quality::Fast, Quality::Balanced, Quality::Accurate];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Quality::Fast => "Fast",
+            Quality::Balanced => "Balanced",
+            Quality::Accurate => "Accurate",
+        }
+    }
+
+    //Set the model's resolution cap, sampling options and timeout for this preset
+    pub fn apply(self, model: &mut LocalModel) {
+        let (max_dimension, options, timeout_secs) = match self {
+            Quality::Fast => (Some(1024), GenerationOptions { temperature: None, num_predict: Some(256) }, 120),
+            Quality::Balanced => (None, GenerationOptions::default(), REQUEST_TIMEOUT_SECS),
+            Quality::Accurate => (None, GenerationOptions { temperature: Some(0.2), num_predict: Some(2048) }, 600),
+        };
+        model.set_max_dimension(max_dimension);
+        model.set_options(options);
+        model.set_timeout(Duration::from_secs(timeout_secs));
+    }
+}
+
+/// Timing reported by Ollama for the last generation
+#[derive(Clone, Copy, Debug)]
+pub struct GenerationStats {
+    pub eval_count: u64,
+    pub eval_duration: Duration,
+}
+
+impl GenerationStats {
+    pub fn tokens_per_second(&self) -> f64 {
+        let secs = self.eval_duration.as_secs_f64();
+        if secs > 0.0 { self.eval_count as f64 / secs } else { 0.0 }
+    }
+}
+
+/// Output format requested from the model
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum ResponseFormat {
+    /// Free-form text
+    Text,
+    /// Constrain generation to valid JSON
+    Json,
 }
 
 #[derive(Serialize)]
 struct OllamaRequest {
     model: String,
     prompt: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<String>,
     images: Option<Vec<String>>,
     stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keep_alive: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    options: Option<GenerationOptions>,
+}
+
+#[derive(Serialize)]
+struct EmbeddingRequest {
+    model: String,
+    prompt: String,
+    images: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingResponse {
+    #[serde(default)]
+    embedding: Vec<f32>,
+}
+
+//One line of /api/pull's progress stream; the byte counts cover the layer now downloading
+#[derive(Deserialize, Clone, Default, Debug)]
+pub struct PullProgress {
+    #[serde(default)]
+    pub status: String,
+    pub total: Option<u64>,
+    pub completed: Option<u64>,
+    pub error: Option<String>,
+}
+
+impl PullProgress {
+    //Fraction of the current layer downloaded, when Ollama knows its size
+    pub fn fraction(&self) -> Option<f32> {
+        match (self.completed, self.total) {
+            (Some(completed), Some(total)) if total > 0 => Some(completed as f32 / total as f32),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Deserialize)]
 struct OllamaResponse {
+    #[serde(default)]
     response: String,
+    //Streamed chunks set this on the last one; whole responses always do
+    #[serde(default)]
+    done: bool,
+    error: Option<String>,
+    eval_count: Option<u64>,
+    //Nanoseconds spent generating the response
+    eval_duration: Option<u64>,
 }
 
 impl LocalModel {
@@ -38,16 +272,31 @@ impl LocalModel {
         info!("Initializing Ollama model: {} at {}", model_path, ollama_url);
         
         let client = Client::builder()
-            .timeout(Duration::from_secs(300)) // 5 minutes
+            .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS)) // 5 minutes
             .connect_timeout(Duration::from_secs(10))
             .build()?;
         
-        //check if Ollama is running
+        //send OLLAMA_AUTH_TOKEN as a bearer token for servers behind an authenticating proxy
+        let mut headers = HeaderMap::new();
+        if let Ok(token) = std::env::var("OLLAMA_AUTH_TOKEN") {
+            match HeaderValue::from_str(&format!("Bearer {}", token)) {
+                Ok(mut value) => {
+                    value.set_sensitive(true);
+                    headers.insert(AUTHORIZATION, value);
+                }
+                Err(e) => warn!("Ignoring invalid OLLAMA_AUTH_TOKEN: {}", e),
+            }
+        }
+        
+        //check if Ollama is running, and which models it has for normalize_model_name
         let check_url = format!("{}/api/tags", ollama_url);
-        match client.get(&check_url).send() {
+        let mut installed = Vec::new();
+        match client.get(&check_url).headers(headers.clone()).send() {
             Ok(response) => {
                 if !response.status().is_success() {
                     warn!("Ollama server responded with status: {}", response.status());
+                } else if let Ok(tags) = response.json::<serde_json::Value>() {
+                    installed = model_names(&tags);
                 }
             }
             Err(e) => {
@@ -55,14 +304,31 @@ impl LocalModel {
                 warn!("Make sure Ollama is running: 'ollama serve'");
             }
         }
-        
-        let default_prompt = "Describe what you see in this image in detail, focusing on any text, UI elements, and visual content.".to_string();
+        let model_name = normalize_model_name(model_path, &installed)?;
+        if model_name != model_path {
+            info!("Using installed model {} for '{}'", model_name, model_path);
+        }
+        //Keep the name as typed even when it looks like a typo: the caller may offer to pull it
+        if let Some(similar) = similar_installed_model(&model_name, &installed) {
+            warn!("Model '{}' isn't installed. Did you mean {}?", model_name, similar);
+        }
         
         Ok(Self {
             ollama_url,
-            model_name: model_path.to_string(),
+            model_name,
             client,
-            prompt: default_prompt,
+            headers,
+            prompt: DEFAULT_PROMPT.to_string(),
+            system_prompt: None,
+            preprocess: Vec::new(),
+            response_format: ResponseFormat::Text,
+            last_stats: None,
+            stream_sink: None,
+            keep_alive: None,
+            options: GenerationOptions::default(),
+            max_dimension: None,
+            request_timeout: Duration::from_secs(REQUEST_TIMEOUT_SECS),
+            history: Vec::new(),
         })
     }
     
@@ -73,71 +339,144 @@ impl LocalModel {
     
     //Reset to the default prompt
     pub fn reset_prompt(&mut self) {
-        self.prompt = "Describe what you see in this image in detail, focusing on any text, UI elements, and visual content.".to_string();
+        self.prompt = DEFAULT_PROMPT.to_string();
     }
     
-    //Check if the specified model is available
-    fn check_model_available(&self) -> Result<bool> {
-        let url = format!("{}/api/tags", self.ollama_url);
-        let response = self.client.get(&url).send()?;
-        
-        if !response.status().is_success() {
-            return Ok(false);
-        }
-        
-        let tags: serde_json::Value = response.json()?;
-        
-        //Check if our model is in the list
-        if let Some(models) = tags["models"].as_array() {
-            for model in models {
-                if let Some(name) = model["name"].as_str() {
-                    if name == self.model_name {
-                        return Ok(true);
-                    }
+    //The prompt the next request will send
+    pub fn prompt(&self) -> &str {
+        &self.prompt
+    }
+    
+    //The system prompt the next request will send, if any
+    pub fn system_prompt(&self) -> Option<&str> {
+        self.system_prompt.as_deref()
+    }
+    
+    //Set the system prompt sent alongside every request (None = the model's default)
+    pub fn set_system_prompt(&mut self, system_prompt: Option<&str>) {
+        self.system_prompt = system_prompt.map(|s| s.to_string());
+    }
+    
+    //Set filters applied to each image before it is sent (empty = send as captured)
+    pub fn set_preprocess(&mut self, ops: Vec<PreprocessOp>) {
+        self.preprocess = ops;
+    }
+    
+    //Add extra HTTP headers sent with every request (e.g. for a reverse proxy)
+    pub fn set_headers(&mut self, headers: Vec<(String, String)>) {
+        for (key, value) in headers {
+            match (HeaderName::from_bytes(key.trim().as_bytes()), HeaderValue::from_str(value.trim())) {
+                (Ok(name), Ok(value)) => {
+                    self.headers.insert(name, value);
                 }
+                _ => warn!("Ignoring invalid header '{}'", key),
             }
         }
-        
-        Ok(false)
     }
-}
-
-impl AiConnector for LocalModel {
-    fn process_image(&mut self, image_data: &[u8]) -> Result<String> {
+    
+    //Ask Ollama to constrain the response format (JSON responses are validated)
+    pub fn set_response_format(&mut self, fmt: ResponseFormat) {
+        self.response_format = fmt;
+    }
+    
+    //Stream responses, appending each chunk to `sink` as it arrives (e.g. to show partial output on Ctrl+C).
+    //The sink is cleared at the start of every request
+    pub fn set_stream_sink(&mut self, sink: Option<Arc<Mutex<String>>>) {
+        self.stream_sink = sink;
+    }
+    
+    //How long Ollama keeps the model loaded after a request (e.g. "30m"); None uses the server default
+    pub fn set_keep_alive(&mut self, keep_alive: Option<&str>) {
+        self.keep_alive = keep_alive.map(|k| k.to_string());
+    }
+    
+    //Sampling options sent with every generation request
+    pub fn set_options(&mut self, options: GenerationOptions) {
+        self.options = options;
+    }
+    
+    //Downscale images whose longer side is above this before sending them; None sends them as they are
+    pub fn set_max_dimension(&mut self, max_dimension: Option<u32>) {
+        self.max_dimension = max_dimension;
+    }
+    
+    //Continue a conversation: the next request replays these (question, answer) turns before the prompt
+    pub fn set_history(&mut self, history: Vec<(String, String)>) {
+        self.history = history;
+    }
+    
+    //How long a generation request may take
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.request_timeout = timeout;
+    }
+    
+    //Token statistics from the last successful request, if Ollama reported them
+    pub fn last_stats(&self) -> Option<GenerationStats> {
+        self.last_stats
+    }
+    
+    //Send several images with the prompt in a single request (e.g. to compare captures)
+    pub fn process_images(&mut self, images: &[&[u8]]) -> Result<String> {
         //Check if Ollama is running and model is available
         if !self.check_model_available()? {
             return Err(anyhow!("Model '{}' not found. Pull it with: ollama pull {}", self.model_name, self.model_name));
         }
         
-        info!("Processing image with Ollama model: {}", self.model_name);
+        info!("Processing {} image(s) with Ollama model: {}", images.len(), self.model_name);
         info!("This may take a while on first run as the model loads into memory...");
         
-        // Convert image to base64
-        let base64_image = general_purpose::STANDARD.encode(image_data);
+        // Apply any preprocessing filters and the size cap, and convert the images to base64
+        let mut base64_images = Vec::with_capacity(images.len());
+        for image_data in images {
+            let mut image_data = Cow::Borrowed(*image_data);
+            if !self.preprocess.is_empty() {
+                info!("Preprocessing image with {:?}", self.preprocess);
+                image_data = Cow::Owned(preprocess::apply(&image_data, &self.preprocess)?);
+            }
+            if let Some(max_dimension) = self.max_dimension {
+                let (smaller, scale) = screenshot::downscale_to_fit(&image_data, usize::MAX, Some(max_dimension))?;
+                if scale < 1.0 {
+                    image_data = Cow::Owned(smaller);
+                }
+            }
+            base64_images.push(general_purpose::STANDARD.encode(&image_data));
+        }
         
         //Construct the request
         let request = OllamaRequest {
             model: self.model_name.clone(),
-            prompt: self.prompt.clone(),
-            images: Some(vec![base64_image]),
-            stream: false,
+            prompt: self.request_prompt(),
+            system: self.system_prompt.clone(),
+            images: Some(base64_images),
+            stream: self.stream_sink.is_some(),
+            format: match self.response_format {
+                ResponseFormat::Text => None,
+                ResponseFormat::Json => Some("json".to_string()),
+            },
+            keep_alive: self.keep_alive.clone(),
+            options: (self.options != GenerationOptions::default()).then_some(self.options),
         };
         
         //send the request to Ollama
         let url = format!("{}/api/generate", self.ollama_url);
+        let timeout_secs = self.request_timeout.as_secs();
         
-        info!("Sending request to Ollama... (this may take up to 5 minutes)");
+        info!("Sending request to Ollama... (this may take up to {}s)", timeout_secs);
         
         let response = self.client
             .post(&url)
+            .headers(self.headers.clone())
+            .timeout(self.request_timeout)
             .json(&request)
             .send()
             .map_err(|e| {
-                if e.is_timeout() {
-                    anyhow!("Request timed out after 5 minutes. The model might be too large or your system may need more resources.")
+                //Keep the reqwest error in the chain so callers can tell an unreachable server from a failed analysis
+                let message = if e.is_timeout() {
+                    format!("Request timed out after {}s. The model might be too large or your system may need more resources.", timeout_secs)
                 } else {
-                    anyhow!("Ollama API error: {}", e)
-                }
+                    format!("Ollama API error: {}", e)
+                };
+                anyhow::Error::new(e).context(message)
             })?;
         
         if !response.status().is_success() {
@@ -146,8 +485,255 @@ impl AiConnector for LocalModel {
         }
         
         //parse the response
-        let response_data: OllamaResponse = response.json()?;
+        let response_data: OllamaResponse = match &self.stream_sink {
+            Some(sink) => {
+                if let Ok(mut sink) = sink.lock() {
+                    sink.clear();
+                }
+                read_stream(response, sink)?
+            }
+            None => response.json()?,
+        };
+        self.last_stats = match (response_data.eval_count, response_data.eval_duration) {
+            (Some(eval_count), Some(nanos)) => Some(GenerationStats {
+                eval_count,
+                eval_duration: Duration::from_nanos(nanos),
+            }),
+            _ => None,
+        };
+        
+        //The model was constrained to JSON, so anything else is an error
+        if self.response_format == ResponseFormat::Json {
+            if let Err(e) = serde_json::from_str::<serde_json::Value>(&response_data.response) {
+                return Err(anyhow!("Model returned invalid JSON: {}\n\n{}", e, response_data.response));
+            }
+        }
         
         Ok(response_data.response)
     }
+    
+    //Get an embedding vector for an image (with the prompt) from /api/embeddings, for similarity search.
+    //Models without image embedding support return an empty vector, which is reported as an error
+    pub fn embed_image(&mut self, image_data: &[u8]) -> Result<Vec<f32>> {
+        let image_data = if self.preprocess.is_empty() {
+            image_data.to_vec()
+        } else {
+            preprocess::apply(image_data, &self.preprocess)?
+        };
+        let request = EmbeddingRequest {
+            model: self.model_name.clone(),
+            prompt: self.prompt.clone(),
+            images: vec![general_purpose::STANDARD.encode(image_data)],
+        };
+        
+        let url = format!("{}/api/embeddings", self.ollama_url);
+        info!("Requesting an image embedding from Ollama model: {}", self.model_name);
+        let response = self.client
+            .post(&url)
+            .headers(self.headers.clone())
+            .json(&request)
+            .send()
+            .map_err(|e| {
+                let message = format!("Ollama API error: {}", e);
+                anyhow::Error::new(e).context(message)
+            })?;
+        
+        if !response.status().is_success() {
+            let error_text = response.text()?;
+            return Err(anyhow!("Ollama API error: {}", error_text));
+        }
+        
+        let data: EmbeddingResponse = response.json()?;
+        if data.embedding.is_empty() {
+            return Err(anyhow!("Model '{}' returned no embedding; it may not support embeddings", self.model_name));
+        }
+        Ok(data.embedding)
+    }
+    
+    //Ask Ollama whether the model can take images
+    pub fn is_vision_model(&self) -> Result<bool> {
+        self.supports_images(&self.model_name)
+    }
+    
+    //Whether the model is installed on the server
+    pub fn is_installed(&self) -> Result<bool> {
+        self.check_model_available()
+    }
+    
+    //Installed models that can take images, for steering first-run users to a usable model
+    pub fn installed_vision_models(&self) -> Result<Vec<String>> {
+        let mut vision_models = Vec::new();
+        for name in self.installed_models()? {
+            match self.supports_images(&name) {
+                Ok(true) => vision_models.push(name),
+                Ok(false) => {}
+                Err(e) => warn!("Could not check whether {} supports images: {}", name, e),
+            }
+        }
+        Ok(vision_models)
+    }
+    
+    //Download the model, reporting each status line. Setting `cancel` drops the connection, which stops the download
+    pub fn pull(&self, cancel: &AtomicBool, mut progress: impl FnMut(&PullProgress)) -> Result<()> {
+        info!("Pulling model {} from {}", self.model_name, self.ollama_url);
+        //Large models take far longer than the request timeout to download
+        let client = Client::builder().connect_timeout(Duration::from_secs(10)).timeout(None).build()?;
+        let response = client
+            .post(format!("{}/api/pull", self.ollama_url))
+            .headers(self.headers.clone())
+            .json(&serde_json::json!({ "model": self.model_name, "name": self.model_name, "stream": true }))
+            .send()?;
+        
+        if !response.status().is_success() {
+            return Err(anyhow!("Ollama could not pull '{}': {}", self.model_name, response.text()?));
+        }
+        
+        let mut succeeded = false;
+        for line in BufReader::new(response).lines() {
+            if cancel.load(Ordering::Relaxed) {
+                info!("Cancelled pulling model {}", self.model_name);
+                return Err(anyhow!("Download of '{}' cancelled", self.model_name));
+            }
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let update: PullProgress = serde_json::from_str(&line)?;
+            if let Some(error) = &update.error {
+                return Err(anyhow!("Ollama could not pull '{}': {}", self.model_name, error));
+            }
+            progress(&update);
+            succeeded = update.status == "success";
+        }
+        if !succeeded {
+            return Err(anyhow!("Ollama stopped before '{}' finished downloading", self.model_name));
+        }
+        info!("Pulled model {}", self.model_name);
+        Ok(())
+    }
+    
+    //The prompt as sent: with a history, a transcript of the earlier turns that ends on the new question
+    fn request_prompt(&self) -> String {
+        if self.history.is_empty() {
+            return self.prompt.clone();
+        }
+        let mut prompt = String::from("Earlier in this conversation about the same image:\n\n");
+        for (question, answer) in &self.history {
+            prompt.push_str(&format!("User: {}\nAssistant: {}\n\n", question, answer));
+        }
+        prompt.push_str(&format!("User: {}\nAssistant:", self.prompt));
+        prompt
+    }
+    
+    //Names of every model installed on the server
+    fn installed_models(&self) -> Result<Vec<String>> {
+        let url = format!("{}/api/tags", self.ollama_url);
+        let response = self.client.get(&url).headers(self.headers.clone()).send()?;
+        if !response.status().is_success() {
+            return Err(anyhow!("Ollama server responded with status: {}", response.status()));
+        }
+        let tags: serde_json::Value = response.json()?;
+        Ok(model_names(&tags))
+    }
+    
+    //Newer servers list a "vision" capability; older ones only reveal it through a clip/mllama family or a projector
+    fn supports_images(&self, model_name: &str) -> Result<bool> {
+        let url = format!("{}/api/show", self.ollama_url);
+        let response = self.client
+            .post(&url)
+            .headers(self.headers.clone())
+            .json(&serde_json::json!({ "model": model_name, "name": model_name }))
+            .send()?;
+        
+        if !response.status().is_success() {
+            return Err(anyhow!("Ollama could not describe model '{}': {}", model_name, response.text()?));
+        }
+        
+        let info: serde_json::Value = response.json()?;
+        let has_vision_capability = info["capabilities"].as_array()
+            .map_or(false, |caps| caps.iter().any(|c| c.as_str() == Some("vision")));
+        let has_vision_family = info["details"]["families"].as_array()
+            .map_or(false, |families| families.iter().any(|f| matches!(f.as_str(), Some("clip") | Some("mllama"))));
+        let has_projector = info.get("projector_info").map_or(false, |p| !p.is_null());
+        
+        Ok(has_vision_capability || has_vision_family || has_projector)
+    }
+    
+    //Check if the specified model is available
+    fn check_model_available(&self) -> Result<bool> {
+        let url = format!("{}/api/tags", self.ollama_url);
+        let response = self.client.get(&url).headers(self.headers.clone()).send()?;
+        
+        if !response.status().is_success() {
+            return Ok(false);
+        }
+        
+        let tags: serde_json::Value = response.json()?;
+        
+        //Check if our model is in the list
+        Ok(model_names(&tags).contains(&self.model_name))
+    }
+}
+
+//Read Ollama's newline-delimited stream into one response, mirroring each chunk into the sink
+fn read_stream(response: reqwest::blocking::Response, sink: &Mutex<String>) -> Result<OllamaResponse> {
+    let mut text = String::new();
+    for line in BufReader::new(response).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let chunk: OllamaResponse = serde_json::from_str(&line)?;
+        if let Some(error) = &chunk.error {
+            return Err(anyhow!("Ollama API error: {}", error));
+        }
+        text.push_str(&chunk.response);
+        if let Ok(mut sink) = sink.lock() {
+            sink.push_str(&chunk.response);
+        }
+        if chunk.done {
+            return Ok(OllamaResponse { response: text, ..chunk });
+        }
+    }
+    Err(anyhow!("Ollama closed the stream before the response was complete"))
+}
+
+impl AiConnector for LocalModel {
+    fn process_image(&mut self, image_data: &[u8]) -> Result<String> {
+        self.process_images(&[image_data])
+    }
+    
+    fn process_images(&mut self, images: &[&[u8]]) -> Result<String> {
+        LocalModel::process_images(self, images)
+    }
+    
+    fn name(&self) -> String {
+        format!("ollama ({})", self.model_name)
+    }
+    
+    fn model(&self) -> String {
+        self.model_name.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uninstalled_model_is_kept_with_a_suggestion() {
+        let installed = vec!["llama3:latest".to_string()];
+        //Close enough to be suggested, but still kept so it can be pulled
+        assert_eq!(normalize_model_name("llava", &installed).unwrap(), "llava");
+        assert_eq!(similar_installed_model("llava", &installed).as_deref(), Some("llama3:latest"));
+        assert_eq!(similar_installed_model("moondream", &installed), None);
+    }
+
+    #[test]
+    fn installed_model_gets_its_tag_and_case() {
+        let installed = vec!["llava:13b".to_string(), "llava:latest".to_string()];
+        assert_eq!(normalize_model_name(" LLaVA ", &installed).unwrap(), "llava:latest");
+        assert_eq!(similar_installed_model("llava:latest", &installed), None);
+        assert!(normalize_model_name("  ", &installed).is_err());
+    }
 }
\ No newline at end of file
@@ -0,0 +1,42 @@
+// src/ai/mock_model.rs
+use anyhow::Result;
+use log::info;
+
+use super::connector::AiConnector;
+
+//Offline stand-in for the real model: answers deterministically without Ollama
+#[derive(Default)]
+pub struct MockModel {
+    prompt: Option<String>,
+}
+
+impl MockModel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    //Set the prompt echoed back in the response
+    pub fn set_prompt(&mut self, prompt: &str) {
+        self.prompt = Some(prompt.to_string());
+    }
+}
+
+impl AiConnector for MockModel {
+    fn process_image(&mut self, image_data: &[u8]) -> Result<String> {
+        let image = image::load_from_memory(image_data)?;
+        info!("Mock backend received a {}x{} image", image.width(), image.height());
+        
+        let mut response = format!(
+            "Mock analysis: received a {}x{} image ({} bytes).",
+            image.width(), image.height(), image_data.len()
+        );
+        if let Some(prompt) = &self.prompt {
+            response.push_str(&format!("\nPrompt: {}", prompt));
+        }
+        Ok(response)
+    }
+    
+    fn name(&self) -> String {
+        "mock".to_string()
+    }
+}
\ No newline at end of file
@@ -1,2 +1,7 @@
 pub mod connector;
-pub mod local_model;
\ No newline at end of file
+pub mod fallback;
+pub mod local_model;
+pub mod mock_model;
+pub mod postprocess;
+pub mod preprocess;
+pub mod subprocess_model;
\ No newline at end of file
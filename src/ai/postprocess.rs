@@ -0,0 +1,73 @@
+// src/ai/postprocess.rs
+use log::warn;
+use regex::Regex;
+use serde::{Serialize, Deserialize};
+
+/// Opening words like "Sure!" or "Certainly," that lead into the answer
+const PREAMBLE_WORD: &str = r"(?i)\A\s*(?:sure|certainly|of course|absolutely|okay)[!,.][ \t]*\n*";
+
+/// A lead-in line ending in a colon, like "Here's what I see in the image:"
+const PREAMBLE_LINE: &str = r"(?i)\A\s*here(?:'s| is| are)\b[^\n]*?:[ \t]*\n+";
+
+/// A regex replacement applied to model responses, e.g. `{"pattern": "(?m)^#+ ", "replacement": ""}`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Rule {
+    pub pattern: String,
+    /// Replacement text; `$1` / `${name}` refer to capture groups
+    #[serde(default)]
+    pub replacement: String,
+}
+
+impl Rule {
+    pub fn new(pattern: &str, replacement: &str) -> Self {
+        Self { pattern: pattern.to_string(), replacement: replacement.to_string() }
+    }
+}
+
+/// Rules that strip a chatty preamble ("Sure, here's what I see in the image:") from the start of a response
+pub fn trim_preamble_rules() -> Vec<Rule> {
+    vec![Rule::new(PREAMBLE_WORD, ""), Rule::new(PREAMBLE_LINE, "")]
+}
+
+/// Apply the rules to a response in order, each to every match. Rules whose pattern doesn't compile are
+/// skipped with a warning, so a bad rule in the config can't hide the answer
+pub fn post_process(text: &str, rules: &[Rule]) -> String {
+    let mut text = text.to_string();
+    for rule in rules {
+        match Regex::new(&rule.pattern) {
+            Ok(regex) => text = regex.replace_all(&text, rule.replacement.as_str()).into_owned(),
+            Err(e) => warn!("Skipping response rule '{}': {}", rule.pattern, e),
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_a_chatty_preamble() {
+        let reply = "Sure, here's what I see:\nA settings dialog with two buttons.";
+        assert_eq!(post_process(reply, &trim_preamble_rules()), "A settings dialog with two buttons.");
+        assert_eq!(post_process("Certainly!\nA login form.", &trim_preamble_rules()), "A login form.");
+    }
+
+    #[test]
+    fn reply_without_a_preamble_is_unchanged() {
+        let reply = "A terminal showing a failed build.\nThe error is on line 12:\nmissing semicolon.";
+        assert_eq!(post_process(reply, &trim_preamble_rules()), reply);
+    }
+
+    #[test]
+    fn sure_in_the_middle_is_kept() {
+        let reply = "The dialog asks \"Are you sure, really?\" Sure, the Cancel button is focused.";
+        assert_eq!(post_process(reply, &trim_preamble_rules()), reply);
+    }
+
+    #[test]
+    fn invalid_pattern_is_skipped() {
+        let rules = vec![Rule::new("(unclosed", ""), Rule::new("(?m)^#+ ", "")];
+        assert_eq!(post_process("# Title\nBody", &rules), "Title\nBody");
+    }
+}
\ No newline at end of file
@@ -0,0 +1,112 @@
+// src/ai/preprocess.rs
+use anyhow::Result;
+use image::DynamicImage;
+use std::io::Cursor;
+
+/// Image filters that can be applied before an image is sent to the model
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum PreprocessOp {
+    /// Drop color information
+    Grayscale,
+    /// Stretch each channel to use the full 0-255 range
+    Contrast,
+    /// Unsharp mask to crisp up small text
+    Sharpen,
+}
+
+/// MIME type of encoded image bytes, from their magic bytes rather than assuming PNG.
+/// Unknown data is reported as image/png, what captures are encoded as by default
+pub fn detect_image_format(bytes: &[u8]) -> &'static str {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        "image/png"
+    } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        "image/jpeg"
+    } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        "image/webp"
+    } else {
+        "image/png"
+    }
+}
+
+/// Decode the image, apply the filters in order, and re-encode it as PNG
+pub fn apply(image_data: &[u8], ops: &[PreprocessOp]) -> Result<Vec<u8>> {
+    let mut image = image::load_from_memory(image_data)?;
+    for op in ops {
+        image = match op {
+            PreprocessOp::Grayscale => image.grayscale(),
+            PreprocessOp::Contrast => stretch_contrast(&image),
+            PreprocessOp::Sharpen => image.unsharpen(1.5, 4),
+        };
+    }
+
+    let mut buffer = Vec::new();
+    image.write_to(&mut Cursor::new(&mut buffer), image::ImageOutputFormat::Png)?;
+    Ok(buffer)
+}
+
+/// Linearly rescale each color channel so its darkest value becomes 0 and its brightest 255
+fn stretch_contrast(image: &DynamicImage) -> DynamicImage {
+    let mut rgba = image.to_rgba8();
+
+    let mut min = [u8::MAX; 3];
+    let mut max = [u8::MIN; 3];
+    for pixel in rgba.pixels() {
+        for (channel, &value) in pixel.0.iter().take(3).enumerate() {
+            min[channel] = min[channel].min(value);
+            max[channel] = max[channel].max(value);
+        }
+    }
+
+    for pixel in rgba.pixels_mut() {
+        for (channel, value) in pixel.0.iter_mut().take(3).enumerate() {
+            let range = max[channel] - min[channel];
+            if range > 0 {
+                *value = ((*value - min[channel]) as u32 * 255 / range as u32) as u8;
+            }
+        }
+    }
+
+    // Keep grayscale images grayscale
+    if image.color().has_color() {
+        DynamicImage::ImageRgba8(rgba)
+    } else {
+        DynamicImage::ImageRgba8(rgba).grayscale()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encoded(format: image::ImageOutputFormat) -> Vec<u8> {
+        let image = DynamicImage::ImageRgb8(image::RgbImage::from_pixel(8, 8, image::Rgb([40, 80, 120])));
+        let mut buffer = Vec::new();
+        image.write_to(&mut Cursor::new(&mut buffer), format).unwrap();
+        buffer
+    }
+
+    #[test]
+    fn detects_png_and_jpeg() {
+        assert_eq!(detect_image_format(&encoded(image::ImageOutputFormat::Png)), "image/png");
+        assert_eq!(detect_image_format(&encoded(image::ImageOutputFormat::Jpeg(80))), "image/jpeg");
+    }
+
+    #[test]
+    fn detects_webp_from_its_riff_header() {
+        let mut webp = b"RIFF".to_vec();
+        webp.extend_from_slice(&26u32.to_le_bytes());
+        webp.extend_from_slice(b"WEBPVP8L");
+        assert_eq!(detect_image_format(&webp), "image/webp");
+    }
+
+    #[test]
+    fn truncated_or_other_riff_falls_back_to_png() {
+        assert_eq!(detect_image_format(b"RIFF\x1a\x00\x00\x00WE"), "image/png");
+        assert_eq!(detect_image_format(b"RIFF\x1a\x00\x00\x00WAVE"), "image/png");
+    }
+
+    #[test]
+    fn empty_input_falls_back_to_png() {
+        assert_eq!(detect_image_format(&[]), "image/png");
+    }
+}
\ No newline at end of file
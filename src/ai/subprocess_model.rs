@@ -0,0 +1,180 @@
+// src/ai/subprocess_model.rs
+use anyhow::{Result, anyhow};
+use log::{info, warn};
+use std::io::Read;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use super::connector::{AiConnector, BackendUnavailable};
+use super::local_model::DEFAULT_PROMPT;
+use super::preprocess::detect_image_format;
+
+/// Seconds to wait for the command before killing it, unless --cmd-timeout says otherwise
+pub const DEFAULT_TIMEOUT_SECS: u64 = 300;
+
+//Runs an external program (e.g. a llama.cpp multimodal binary) and uses its stdout as the response.
+//The command template is split like a shell would (quotes, no expansion); {image} is replaced with
+//the path of a temporary image file and {prompt} with the prompt.
+pub struct SubprocessModel {
+    template: Vec<String>,
+    prompt: String,
+    timeout: Duration,
+}
+
+impl SubprocessModel {
+    pub fn new(command: &str, timeout: Duration) -> Result<Self> {
+        let template = split_command(command)?;
+        if template.is_empty() {
+            return Err(anyhow!("--cmd is empty"));
+        }
+        if !template.iter().any(|arg| arg.contains("{image}")) {
+            return Err(anyhow!("--cmd must contain an {{image}} placeholder for the screenshot path"));
+        }
+        Ok(Self {
+            template,
+            prompt: DEFAULT_PROMPT.to_string(),
+            timeout,
+        })
+    }
+    
+    //Set a custom prompt for image analysis
+    pub fn set_prompt(&mut self, prompt: &str) {
+        self.prompt = prompt.to_string();
+    }
+    
+    //The prompt substituted for {prompt}
+    pub fn prompt(&self) -> &str {
+        &self.prompt
+    }
+    
+    fn build_command(&self, image_path: &Path) -> Command {
+        let image_path = image_path.to_string_lossy();
+        let mut args = self.template.iter()
+            .map(|arg| arg.replace("{image}", &image_path).replace("{prompt}", &self.prompt));
+        let mut command = Command::new(args.next().unwrap_or_default());
+        command.args(args);
+        command
+    }
+}
+
+impl AiConnector for SubprocessModel {
+    fn process_image(&mut self, image_data: &[u8]) -> Result<String> {
+        let image_path = temp_image_path(detect_image_format(image_data));
+        std::fs::write(&image_path, image_data)?;
+        let result = self.run(&image_path);
+        let _ = std::fs::remove_file(&image_path);
+        result
+    }
+    
+    fn name(&self) -> String {
+        format!("command ({})", self.template[0])
+    }
+}
+
+impl SubprocessModel {
+    fn run(&self, image_path: &Path) -> Result<String> {
+        let program = &self.template[0];
+        info!("Running {} on {}", program, image_path.display());
+        
+        let mut child = self.build_command(image_path)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| BackendUnavailable(format!("Failed to run {}: {}", program, e)))?;
+        
+        //Drain both pipes on their own threads so a chatty process can't block on a full pipe
+        let stdout = drain(child.stdout.take());
+        let stderr = drain(child.stderr.take());
+        
+        let deadline = Instant::now() + self.timeout;
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if Instant::now() >= deadline {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(BackendUnavailable(format!("{} timed out after {}s", program, self.timeout.as_secs())).into());
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        };
+        
+        let stdout = stdout.join().unwrap_or_default();
+        let stderr = stderr.join().unwrap_or_default();
+        if !status.success() {
+            return Err(anyhow!("{} exited with {}: {}", program, status, stderr.trim()));
+        }
+        if !stderr.trim().is_empty() {
+            warn!("{} wrote to stderr: {}", program, stderr.trim());
+        }
+        
+        let response = stdout.trim().to_string();
+        if response.is_empty() {
+            return Err(anyhow!("{} produced no output", program));
+        }
+        Ok(response)
+    }
+}
+
+fn drain<R: Read + Send + 'static>(pipe: Option<R>) -> std::thread::JoinHandle<String> {
+    std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        String::from_utf8_lossy(&buf).into_owned()
+    })
+}
+
+fn temp_image_path(mime_type: &str) -> std::path::PathBuf {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    //Name the file after its real format so tools that go by the extension don't misread a JPEG as PNG
+    let extension = mime_type.trim_start_matches("image/");
+    std::env::temp_dir().join(format!("screensnap-analyze-{}-{}.{}", std::process::id(), nanos, extension))
+}
+
+//Split a command line into arguments, honouring single and double quotes and backslash escapes
+fn split_command(command: &str) -> Result<Vec<String>> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quote: Option<char> = None;
+    let mut chars = command.chars();
+    
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"'), '\\') | (None, '\\') => match chars.next() {
+                Some(escaped) => current.push(escaped),
+                None => return Err(anyhow!("--cmd ends with a dangling backslash")),
+            },
+            (Some(_), c) => current.push(c),
+            (None, '"') | (None, '\'') => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if quote.is_some() {
+        return Err(anyhow!("--cmd has an unterminated quote"));
+    }
+    if in_arg {
+        args.push(current);
+    }
+    Ok(args)
+}
\ No newline at end of file
@@ -0,0 +1,217 @@
+// src/batch.rs
+use anyhow::{Result, anyhow};
+use image::ImageFormat;
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Match a file name against a pattern where `*` is any run of characters and `?` is one character
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Where to resume after the last `*` if the rest stops matching
+    let mut backtrack: Option<(usize, usize)> = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, n));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            n = matched + 1;
+            backtrack = Some((star, n));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Image files directly inside `dir` (optionally filtered by a file name glob), sorted by name.
+/// Files the image crate doesn't recognize by extension are skipped
+pub fn collect_images(dir: &Path, glob: Option<&str>) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir).map_err(|e| anyhow!("Could not read {}: {}", dir.display(), e))? {
+        let path = entry?.path();
+        if !path.is_file() || ImageFormat::from_path(&path).is_err() {
+            continue;
+        }
+        let name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+        if glob.map_or(true, |pattern| glob_match(pattern, &name)) {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// File format for analysis results
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutFormat {
+    /// One JSON object per line
+    Jsonl,
+    /// Comma-separated values with a header row, for spreadsheets
+    Csv,
+}
+
+impl OutFormat {
+    /// CSV for a .csv file name, JSON lines for anything else
+    pub fn from_path(path: &Path) -> Self {
+        if path.extension().map_or(false, |ext| ext.eq_ignore_ascii_case("csv")) {
+            OutFormat::Csv
+        } else {
+            OutFormat::Jsonl
+        }
+    }
+}
+
+/// One image and its analysis, as a JSON line or a CSV row. An analyzed image has either `response`
+/// or `error`; one that wasn't analyzed has neither, and no `model` or `prompt`
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct AnalysisRecord {
+    pub filename: String,
+    /// RFC 3339 local time the result was written
+    pub timestamp: String,
+    pub model: Option<String>,
+    pub prompt: Option<String>,
+    pub response: Option<String>,
+    pub error: Option<String>,
+}
+
+impl AnalysisRecord {
+    /// A record stamped with the current time; `result` is None when the image wasn't analyzed
+    pub fn new(filename: String, model: Option<&str>, prompt: Option<&str>, result: Option<Result<String, String>>) -> Self {
+        let (response, error) = match result {
+            Some(Ok(response)) => (Some(response), None),
+            Some(Err(error)) => (None, Some(error)),
+            None => (None, None),
+        };
+        Self {
+            filename,
+            timestamp: chrono::Local::now().to_rfc3339(),
+            model: model.map(str::to_string),
+            prompt: prompt.map(str::to_string),
+            response,
+            error,
+        }
+    }
+}
+
+/// `record` as a JSON object with the file name under `file_key` instead of `filename`
+struct KeyedRecord<'a> {
+    record: &'a AnalysisRecord,
+    file_key: &'static str,
+}
+
+impl Serialize for KeyedRecord<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let record = self.record;
+        let mut map = serializer.serialize_map(Some(6))?;
+        map.serialize_entry(self.file_key, &record.filename)?;
+        map.serialize_entry("timestamp", &record.timestamp)?;
+        map.serialize_entry("model", &record.model)?;
+        map.serialize_entry("prompt", &record.prompt)?;
+        map.serialize_entry("response", &record.response)?;
+        map.serialize_entry("error", &record.error)?;
+        map.end()
+    }
+}
+
+enum Sink<W: Write> {
+    Jsonl(W),
+    /// The csv crate quotes fields with commas, quotes or line breaks and writes the header before the first row
+    Csv(csv::Writer<W>),
+}
+
+/// Writes analysis records as JSON lines or CSV
+pub struct AnalysisWriter<W: Write> {
+    sink: Sink<W>,
+    /// JSON key for the file name, so existing consumers keep working: batch has always written
+    /// `file` and watch `image`. CSV always names the column `filename`
+    file_key: &'static str,
+}
+
+impl AnalysisWriter<BufWriter<File>> {
+    pub fn create(path: &Path, format: OutFormat, file_key: &'static str) -> Result<Self> {
+        let file = File::create(path).map_err(|e| anyhow!("Could not create {}: {}", path.display(), e))?;
+        Ok(Self::new(BufWriter::new(file), format, file_key))
+    }
+}
+
+impl<W: Write> AnalysisWriter<W> {
+    pub fn new(writer: W, format: OutFormat, file_key: &'static str) -> Self {
+        let sink = match format {
+            OutFormat::Jsonl => Sink::Jsonl(writer),
+            OutFormat::Csv => Sink::Csv(csv::Writer::from_writer(writer)),
+        };
+        Self { sink, file_key }
+    }
+
+    /// Append a record, flushing so finished files survive an interrupted run and a reader sees it right away
+    pub fn write(&mut self, record: &AnalysisRecord) -> Result<()> {
+        match &mut self.sink {
+            Sink::Jsonl(writer) => {
+                serde_json::to_writer(&mut *writer, &KeyedRecord { record, file_key: self.file_key })?;
+                writeln!(writer)?;
+                writer.flush()?;
+            }
+            Sink::Csv(writer) => {
+                writer.serialize(record)?;
+                writer.flush()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tricky_record() -> AnalysisRecord {
+        AnalysisRecord::new(
+            "shot, final.png".to_string(),
+            Some("llava"),
+            Some("What does the \"Save\" button do?"),
+            Some(Ok("It says \"Saved, 3 files\"\nand closes the dialog.".to_string())),
+        )
+    }
+
+    #[test]
+    fn csv_round_trips_quotes_commas_and_newlines() {
+        let mut out = Vec::new();
+        let mut writer = AnalysisWriter::new(&mut out, OutFormat::Csv, "file");
+        writer.write(&tricky_record()).unwrap();
+        writer.write(&AnalysisRecord::new("b.png".to_string(), Some("llava"), None, Some(Err("timed out".to_string())))).unwrap();
+        drop(writer);
+
+        let mut reader = csv::Reader::from_reader(out.as_slice());
+        let headers = reader.headers().unwrap().clone();
+        assert_eq!(headers.iter().collect::<Vec<_>>(), ["filename", "timestamp", "model", "prompt", "response", "error"]);
+        let rows: Vec<csv::StringRecord> = reader.records().map(|row| row.unwrap()).collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(&rows[0][0], "shot, final.png");
+        assert_eq!(&rows[0][3], "What does the \"Save\" button do?");
+        assert_eq!(&rows[0][4], "It says \"Saved, 3 files\"\nand closes the dialog.");
+        assert_eq!(&rows[0][5], "");
+        assert_eq!(&rows[1][5], "timed out");
+    }
+
+    #[test]
+    fn json_lines_keep_the_file_key() {
+        let mut out = Vec::new();
+        AnalysisWriter::new(&mut out, OutFormat::Jsonl, "image").write(&tricky_record()).unwrap();
+
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(text.lines().count(), 1);
+        let value: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(value["image"], "shot, final.png");
+        assert!(value.get("filename").is_none());
+        assert_eq!(value["response"], "It says \"Saved, 3 files\"\nand closes the dialog.");
+        assert!(value["error"].is_null());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,67 @@
+// src/bundle.rs
+use anyhow::{Result, anyhow};
+use image::{DynamicImage, GenericImageView};
+use log::info;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::capture::screenshot::{self, CaptureSource, PngCompression};
+
+/// Describes how a bundled response was produced, written as meta.json
+#[derive(Serialize)]
+pub struct BundleMeta {
+    pub model: String,
+    /// None when the backend's default prompt was used
+    pub prompt: Option<String>,
+    pub timestamp: String,
+    pub width: u32,
+    pub height: u32,
+    /// What was captured; None for pasted images
+    pub source: Option<CaptureSource>,
+}
+
+impl BundleMeta {
+    pub fn new(model: &str, prompt: Option<&str>, image: &DynamicImage, source: Option<&CaptureSource>) -> Self {
+        let (width, height) = image.dimensions();
+        Self {
+            model: model.to_string(),
+            prompt: prompt.map(|p| p.to_string()),
+            timestamp: chrono::Local::now().to_rfc3339(),
+            width,
+            height,
+            source: source.cloned(),
+        }
+    }
+}
+
+/// Write image.png, response.txt and meta.json into a new timestamped folder inside `dir`.
+/// A numeric suffix is added when that folder already exists. Returns the folder's path
+pub fn save_bundle(dir: &Path, image: &DynamicImage, response: &str, meta: &BundleMeta) -> Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S").to_string();
+    let bundle_dir = create_unique_dir(dir, &stamp)?;
+    
+    screenshot::save_png(image, &bundle_dir.join("image.png"), PngCompression::Default)?;
+    std::fs::write(bundle_dir.join("response.txt"), response)?;
+    std::fs::write(bundle_dir.join("meta.json"), serde_json::to_string_pretty(meta)?)?;
+    
+    info!("Bundle saved to {}", bundle_dir.display());
+    Ok(bundle_dir)
+}
+
+fn create_unique_dir(dir: &Path, name: &str) -> Result<PathBuf> {
+    for n in 1..1000 {
+        let candidate = if n == 1 {
+            dir.join(name)
+        } else {
+            dir.join(format!("{}-{}", name, n))
+        };
+        // create_dir fails on an existing folder, so two saves can't claim the same one
+        match std::fs::create_dir(&candidate) {
+            Ok(()) => return Ok(candidate),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Err(anyhow!("Too many bundles named {} in {}", name, dir.display()))
+}
\ No newline at end of file
@@ -0,0 +1,119 @@
+// src/capture/annotate.rs
+use image::{DynamicImage, Rgba};
+use imageproc::drawing::{draw_filled_rect_mut, draw_hollow_rect_mut, draw_text_mut, text_size};
+use imageproc::rect::Rect;
+use log::{info, warn};
+use rusttype::{Font, Scale};
+
+/// Prompt asking the model to locate UI elements as normalized bounding boxes
+pub const ANNOTATION_PROMPT: &str = "Find the important UI elements in this screenshot (buttons, text fields, menus, dialogs, error messages). \
+Respond with ONLY a JSON array, no other text. Each entry must look like \
+{\"label\": \"short name\", \"x\": 0.1, \"y\": 0.2, \"width\": 0.3, \"height\": 0.05} \
+where x, y, width and height are fractions of the image size between 0 and 1, measured from the top-left corner.";
+
+const BOX_COLOR: Rgba<u8> = Rgba([255, 64, 64, 255]);
+const LABEL_TEXT_COLOR: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const BOX_THICKNESS: i32 = 3;
+const LABEL_SCALE: f32 = 18.0;
+
+/// A labelled box in normalized (0..1) image coordinates
+#[derive(Clone, Debug)]
+pub struct BoundingBox {
+    pub label: String,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// Extract bounding boxes from a model response, skipping entries with invalid coordinates
+pub fn parse_bounding_boxes(response: &str) -> Vec<BoundingBox> {
+    // Models often wrap the array in prose or a ```json fence
+    let (start, end) = match (response.find('['), response.rfind(']')) {
+        (Some(start), Some(end)) if start < end => (start, end),
+        _ => {
+            warn!("No JSON array found in annotation response");
+            return Vec::new();
+        }
+    };
+
+    let entries: Vec<serde_json::Value> = match serde_json::from_str(&response[start..=end]) {
+        Ok(entries) => entries,
+        Err(e) => {
+            warn!("Failed to parse annotation JSON: {}", e);
+            return Vec::new();
+        }
+    };
+
+    let boxes: Vec<BoundingBox> = entries.iter().filter_map(|entry| {
+        let coord = |key: &str| entry[key].as_f64().map(|v| v as f32).filter(|v| v.is_finite());
+        let (x, y, width, height) = (coord("x")?, coord("y")?, coord("width")?, coord("height")?);
+        if x < 0.0 || y < 0.0 || x >= 1.0 || y >= 1.0 || width <= 0.0 || height <= 0.0 {
+            warn!("Skipping bounding box with invalid coordinates: {}", entry);
+            return None;
+        }
+        Some(BoundingBox {
+            label: entry["label"].as_str().unwrap_or("").to_string(),
+            x,
+            y,
+            // Clip boxes that run past the right/bottom edge
+            width: width.min(1.0 - x),
+            height: height.min(1.0 - y),
+        })
+    }).collect();
+
+    info!("Parsed {} bounding boxes from {} entries", boxes.len(), entries.len());
+    boxes
+}
+
+/// Draw the boxes and their labels onto a copy of the image
+pub fn draw_bounding_boxes(image: &DynamicImage, boxes: &[BoundingBox]) -> DynamicImage {
+    let mut canvas = image.to_rgba8();
+    let (img_width, img_height) = (canvas.width() as f32, canvas.height() as f32);
+
+    // Reuse egui's bundled font so labels don't need a font on disk
+    let font = egui::FontDefinitions::default()
+        .font_data
+        .get("Ubuntu-Light")
+        .and_then(|data| Font::try_from_vec(data.font.to_vec()));
+    if font.is_none() {
+        warn!("Annotation font unavailable, drawing boxes without labels");
+    }
+    let scale = Scale::uniform(LABEL_SCALE);
+
+    for bbox in boxes {
+        let left = (bbox.x * img_width) as i32;
+        let top = (bbox.y * img_height) as i32;
+        let width = ((bbox.width * img_width) as u32).max(1);
+        let height = ((bbox.height * img_height) as u32).max(1);
+
+        for inset in 0..BOX_THICKNESS {
+            let inset_width = width.saturating_sub(2 * inset as u32);
+            let inset_height = height.saturating_sub(2 * inset as u32);
+            if inset_width == 0 || inset_height == 0 {
+                break;
+            }
+            draw_hollow_rect_mut(
+                &mut canvas,
+                Rect::at(left + inset, top + inset).of_size(inset_width, inset_height),
+                BOX_COLOR,
+            );
+        }
+
+        if let Some(font) = &font {
+            if !bbox.label.is_empty() {
+                let (text_width, text_height) = text_size(scale, font, &bbox.label);
+                // Put the label above the box, or inside it when the box touches the top edge
+                let label_top = if top >= text_height + 4 { top - text_height - 4 } else { top };
+                draw_filled_rect_mut(
+                    &mut canvas,
+                    Rect::at(left, label_top).of_size((text_width + 6).max(1) as u32, (text_height + 4).max(1) as u32),
+                    BOX_COLOR,
+                );
+                draw_text_mut(&mut canvas, LABEL_TEXT_COLOR, left + 3, label_top + 2, scale, font, &bbox.label);
+            }
+        }
+    }
+
+    DynamicImage::ImageRgba8(canvas)
+}
\ No newline at end of file
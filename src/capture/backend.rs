@@ -0,0 +1,410 @@
+// src/capture/backend.rs
+use anyhow::{Result, anyhow};
+use image::{DynamicImage, RgbaImage};
+use log::{info, warn};
+use screenshots::Screen;
+use std::fmt;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Buffers at least this large (a 4K frame) are converted on the rayon pool;
+/// below it the thread hand-off costs more than the serial swap
+#[cfg(feature = "parallel")]
+const PARALLEL_CONVERSION_THRESHOLD: usize = 3840 * 2160 * 4;
+
+/// Error shown when every screen reports a 0x0 size, as happens on some headless/RDP sessions
+pub const NO_USABLE_DISPLAY: &str = "no usable display found (running headless?)";
+
+/// Error for a capture the user called off, e.g. by closing the portal's dialog. Not a failure:
+/// callers report it and stop instead of falling back to another capture
+#[derive(Debug)]
+pub struct CaptureCancelled;
+
+impl fmt::Display for CaptureCancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "capture cancelled")
+    }
+}
+
+impl std::error::Error for CaptureCancelled {}
+
+/// Whether an error means the user cancelled the capture
+pub fn is_cancelled(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| cause.downcast_ref::<CaptureCancelled>().is_some())
+}
+
+/// Whether a screen reports a real size
+pub fn is_usable_screen(screen: &Screen) -> bool {
+    screen.display_info.width > 0 && screen.display_info.height > 0
+}
+
+/// Index of the primary screen, or of the first usable one, skipping screens with zero dimensions
+pub fn select_usable_screen(screens: &[Screen]) -> Option<usize> {
+    let sizes: Vec<ScreenSize> = screens.iter()
+        .map(|s| ScreenSize { width: s.display_info.width, height: s.display_info.height, is_primary: s.display_info.is_primary })
+        .collect();
+    select_usable_size(&sizes)
+}
+
+/// What `select_usable_screen` needs to know about a screen
+#[derive(Clone, Copy, Debug)]
+struct ScreenSize {
+    width: u32,
+    height: u32,
+    is_primary: bool,
+}
+
+fn select_usable_size(screens: &[ScreenSize]) -> Option<usize> {
+    let usable = |s: &ScreenSize| s.width > 0 && s.height > 0;
+    screens.iter()
+        .position(|s| s.is_primary && usable(s))
+        .or_else(|| screens.iter().position(usable))
+}
+
+/// Which capture backend to use
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum CaptureBackendKind {
+    /// Use the native backend, or a command-line tool if it can't see any displays
+    Auto,
+    /// Capture through the screenshots crate
+    Native,
+    /// Shell out to grim/scrot (Linux) or screencapture (macOS)
+    Command,
+    /// Ask xdg-desktop-portal, for GNOME on Wayland; needs the `portal` build feature
+    Portal,
+}
+
+/// Pixels exactly as the OS hands them over: 4 bytes per pixel in blue, green, red, alpha order,
+/// rows top to bottom with no padding. Everything else in the app works on RGBA
+pub struct BgraFrame {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+/// Interface for grabbing pixels from a monitor
+pub trait CaptureBackend: Send + Sync {
+    /// Short name used in logs
+    fn name(&self) -> &'static str;
+
+    /// Capture an entire monitor
+    fn capture_screen(&self, monitor: usize) -> Result<DynamicImage>;
+
+    /// Capture a region in coordinates relative to the monitor's top-left corner
+    fn capture_area(&self, monitor: usize, x: i32, y: i32, width: u32, height: u32) -> Result<DynamicImage>;
+
+    /// Capture an entire monitor into `buffer`, reallocating it only when the monitor's size differs
+    fn capture_screen_into(&self, monitor: usize, buffer: &mut RgbaImage) -> Result<()> {
+        *buffer = self.capture_screen(monitor)?.into_rgba8();
+        Ok(())
+    }
+
+    /// Capture an entire monitor as BGRA. Backends that don't get BGRA from the OS swap the channels to match
+    fn capture_screen_bgra(&self, monitor: usize) -> Result<BgraFrame> {
+        let rgba = self.capture_screen(monitor)?.into_rgba8();
+        let (width, height) = rgba.dimensions();
+        let mut data = rgba.into_raw();
+        // Swapping red and blue goes both ways
+        convert_bgra_in_place(&mut data, width);
+        Ok(BgraFrame { width, height, data })
+    }
+
+    /// Whether windows are picked in the backend's own dialog because they can't be looked up by title
+    fn picks_windows(&self) -> bool {
+        false
+    }
+
+    /// Capture whatever the user picks in the backend's dialog (see `picks_windows`)
+    fn capture_picked(&self) -> Result<DynamicImage> {
+        Err(anyhow!("The {} capture backend has no picker", self.name()))
+    }
+}
+
+/// Create the backend for the requested kind
+pub fn create_backend(kind: CaptureBackendKind) -> Result<Box<dyn CaptureBackend>> {
+    let backend: Box<dyn CaptureBackend> = match kind {
+        CaptureBackendKind::Native => Box::new(NativeBackend),
+        CaptureBackendKind::Command => Box::new(CommandBackend::detect()?),
+        CaptureBackendKind::Portal => portal_backend()?,
+        // GNOME Wayland hands out screenshots only through the portal
+        #[cfg(all(target_os = "linux", feature = "portal"))]
+        CaptureBackendKind::Auto if super::portal::is_gnome_wayland() => Box::new(super::portal::PortalBackend),
+        CaptureBackendKind::Auto => {
+            match Screen::all() {
+                Ok(screens) if screens.iter().any(is_usable_screen) => Box::new(NativeBackend),
+                probe => {
+                    if let Err(e) = probe {
+                        warn!("Native capture backend unavailable: {}", e);
+                    } else {
+                        warn!("Native capture backend found no screens with a non-zero size");
+                    }
+                    match CommandBackend::detect() {
+                        Ok(backend) => Box::new(backend),
+                        Err(e) => {
+                            warn!("No command-line capture tool available either: {}", e);
+                            Box::new(NativeBackend)
+                        }
+                    }
+                }
+            }
+        }
+    };
+    info!("Using {} capture backend", backend.name());
+    Ok(backend)
+}
+
+#[cfg(all(target_os = "linux", feature = "portal"))]
+fn portal_backend() -> Result<Box<dyn CaptureBackend>> {
+    Ok(Box::new(super::portal::PortalBackend))
+}
+
+#[cfg(not(all(target_os = "linux", feature = "portal")))]
+fn portal_backend() -> Result<Box<dyn CaptureBackend>> {
+    Err(anyhow!("This build has no portal capture backend; it is Linux-only and needs --features portal"))
+}
+
+/// Capture through the screenshots crate
+pub struct NativeBackend;
+
+impl NativeBackend {
+    fn screen(monitor: usize) -> Result<Screen> {
+        let screens = Screen::all()?;
+        let count = screens.len();
+        let screen = screens.into_iter().nth(monitor).ok_or_else(|| {
+            anyhow!("Monitor {} not found ({} available)", monitor, count)
+        })?;
+        if !is_usable_screen(&screen) {
+            return Err(anyhow!("Monitor {} reports a size of 0x0: {}", monitor, NO_USABLE_DISPLAY));
+        }
+        Ok(screen)
+    }
+}
+
+impl CaptureBackend for NativeBackend {
+    fn name(&self) -> &'static str {
+        "native"
+    }
+
+    fn capture_screen(&self, monitor: usize) -> Result<DynamicImage> {
+        let image = Self::screen(monitor)?.capture()?;
+        bgra_to_dynamic_image(image.width(), image.height(), image.as_raw())
+    }
+
+    fn capture_area(&self, monitor: usize, x: i32, y: i32, width: u32, height: u32) -> Result<DynamicImage> {
+        let image = Self::screen(monitor)?.capture_area(x, y, width, height)?;
+        bgra_to_dynamic_image(image.width(), image.height(), image.as_raw())
+    }
+
+    fn capture_screen_into(&self, monitor: usize, buffer: &mut RgbaImage) -> Result<()> {
+        // The screenshots crate has no way to capture into a caller's buffer, so its own frame is still
+        // allocated each time; what's reused is the converted image handed back to the caller
+        let image = Self::screen(monitor)?.capture()?;
+        bgra_into_rgba_image(image.width(), image.height(), image.as_raw(), buffer)
+    }
+
+    fn capture_screen_bgra(&self, monitor: usize) -> Result<BgraFrame> {
+        let image = Self::screen(monitor)?.capture()?;
+        Ok(BgraFrame { width: image.width(), height: image.height(), data: image.into_raw() })
+    }
+}
+
+/// Capture by running an external screenshot tool and reading back its PNG
+pub struct CommandBackend {
+    tool: &'static str,
+}
+
+impl CommandBackend {
+    /// Find a usable screenshot tool for this platform
+    pub fn detect() -> Result<Self> {
+        let candidates: &[&'static str] = if cfg!(target_os = "macos") {
+            &["screencapture"]
+        } else if cfg!(target_os = "linux") {
+            if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+                &["grim", "scrot"]
+            } else {
+                &["scrot", "grim"]
+            }
+        } else {
+            &[]
+        };
+
+        candidates.iter()
+            .copied()
+            .find(|tool| command_exists(tool))
+            .map(|tool| Self { tool })
+            .ok_or_else(|| anyhow!("No command-line screenshot tool found (tried: {})", candidates.join(", ")))
+    }
+
+    /// Run the tool for an absolute rectangle, or the whole desktop when `rect` is None
+    fn run(&self, rect: Option<(i32, i32, u32, u32)>) -> Result<DynamicImage> {
+        let output_path = temp_capture_path();
+        let mut command = Command::new(self.tool);
+
+        match (self.tool, rect) {
+            ("grim", Some((x, y, w, h))) => { command.arg("-g").arg(format!("{},{} {}x{}", x, y, w, h)); }
+            ("scrot", Some((x, y, w, h))) => { command.arg("-o").arg("-a").arg(format!("{},{},{},{}", x, y, w, h)); }
+            ("scrot", None) => { command.arg("-o"); }
+            ("screencapture", Some((x, y, w, h))) => { command.arg("-x").arg("-R").arg(format!("{},{},{},{}", x, y, w, h)); }
+            ("screencapture", None) => { command.arg("-x"); }
+            _ => {}
+        }
+        command.arg(&output_path);
+
+        let output = command.output()
+            .map_err(|e| anyhow!("Failed to run {}: {}", self.tool, e))?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "{} exited with {}: {}",
+                self.tool, output.status, String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        let image = image::open(&output_path)
+            .map_err(|e| anyhow!("Failed to read capture from {}: {}", self.tool, e));
+        let _ = std::fs::remove_file(&output_path);
+        image
+    }
+}
+
+impl CaptureBackend for CommandBackend {
+    fn name(&self) -> &'static str {
+        self.tool
+    }
+
+    fn capture_screen(&self, monitor: usize) -> Result<DynamicImage> {
+        match Screen::all() {
+            Ok(screens) if monitor < screens.len() && is_usable_screen(&screens[monitor]) => {
+                let info = screens[monitor].display_info;
+                self.run(Some((info.x, info.y, info.width, info.height)))
+            }
+            // Without display geometry, the best we can do is the whole desktop
+            _ if monitor == 0 => self.run(None),
+            _ => Err(anyhow!("Monitor {} not found", monitor)),
+        }
+    }
+
+    fn capture_area(&self, monitor: usize, x: i32, y: i32, width: u32, height: u32) -> Result<DynamicImage> {
+        let (origin_x, origin_y) = match Screen::all() {
+            Ok(screens) if monitor < screens.len() => (screens[monitor].display_info.x, screens[monitor].display_info.y),
+            _ if monitor == 0 => (0, 0),
+            _ => return Err(anyhow!("Monitor {} not found", monitor)),
+        };
+        self.run(Some((origin_x + x, origin_y + y, width, height)))
+    }
+}
+
+/// Check whether an executable with this name is on the PATH
+fn command_exists(name: &str) -> bool {
+    std::env::var_os("PATH").map_or(false, |paths| {
+        std::env::split_paths(&paths).any(|dir| dir.join(name).is_file())
+    })
+}
+
+fn temp_capture_path() -> PathBuf {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    std::env::temp_dir().join(format!("screensnap-{}-{}.png", std::process::id(), nanos))
+}
+
+/// Convert a BGRA buffer from the screenshots crate into an RGBA `DynamicImage`
+fn bgra_to_dynamic_image(width: u32, height: u32, bgra: &[u8]) -> Result<DynamicImage> {
+    let mut rgba_buffer = bgra.to_vec();
+    convert_bgra_in_place(&mut rgba_buffer, width);
+
+    let rgba = image::RgbaImage::from_raw(width, height, rgba_buffer)
+        .ok_or_else(|| anyhow!("Failed to create image from raw data"))?;
+
+    Ok(DynamicImage::ImageRgba8(rgba))
+}
+
+/// Convert a BGRA buffer into `buffer`, reusing its allocation when the dimensions already match
+fn bgra_into_rgba_image(width: u32, height: u32, bgra: &[u8], buffer: &mut RgbaImage) -> Result<()> {
+    if bgra.len() != width as usize * height as usize * 4 {
+        return Err(anyhow!("Capture buffer is {} bytes, expected {} for {}x{}", bgra.len(), width as usize * height as usize * 4, width, height));
+    }
+    if buffer.dimensions() == (width, height) {
+        buffer.copy_from_slice(bgra);
+    } else {
+        *buffer = RgbaImage::from_raw(width, height, bgra.to_vec())
+            .ok_or_else(|| anyhow!("Failed to create image from raw data"))?;
+    }
+    convert_bgra_in_place(buffer, width);
+    Ok(())
+}
+
+/// Turn a BGRA buffer into RGBA in place, on the rayon pool for large frames
+fn convert_bgra_in_place(buffer: &mut [u8], width: u32) {
+    #[cfg(feature = "parallel")]
+    if buffer.len() >= PARALLEL_CONVERSION_THRESHOLD {
+        swap_red_blue_parallel(buffer, width as usize * 4);
+    } else {
+        swap_red_blue(buffer);
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        let _ = width;
+        swap_red_blue(buffer);
+    }
+}
+
+/// Swap the B and R channels of a 4-byte-per-pixel buffer in place
+fn swap_red_blue(buffer: &mut [u8]) {
+    for pixel in buffer.chunks_exact_mut(4) {
+        pixel.swap(0, 2);
+    }
+}
+
+/// Swap the B and R channels in place, one row per rayon task
+#[cfg(feature = "parallel")]
+fn swap_red_blue_parallel(buffer: &mut [u8], row_bytes: usize) {
+    use rayon::prelude::*;
+    buffer.par_chunks_exact_mut(row_bytes).for_each(swap_red_blue);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn screen(width: u32, height: u32, is_primary: bool) -> ScreenSize {
+        ScreenSize { width, height, is_primary }
+    }
+
+    #[test]
+    fn usable_screen_skips_a_zero_sized_primary() {
+        assert_eq!(select_usable_size(&[screen(0, 0, true), screen(1920, 1080, false)]), Some(1));
+    }
+
+    #[test]
+    fn usable_screen_prefers_the_primary() {
+        assert_eq!(select_usable_size(&[screen(1920, 1080, false), screen(2560, 1440, true)]), Some(1));
+        assert_eq!(select_usable_size(&[screen(1920, 1080, false), screen(2560, 0, true)]), Some(0));
+    }
+
+    #[test]
+    fn usable_screen_is_none_when_every_screen_is_zero_sized() {
+        assert_eq!(select_usable_size(&[screen(0, 0, true), screen(0, 0, false)]), None);
+        assert_eq!(select_usable_size(&[]), None);
+    }
+
+    #[test]
+    fn capture_into_reuses_the_buffer_across_frames() {
+        let mut buffer = RgbaImage::new(0, 0);
+        bgra_into_rgba_image(2, 1, &[1, 2, 3, 255, 4, 5, 6, 255], &mut buffer).unwrap();
+        let allocation = buffer.as_ptr();
+
+        bgra_into_rgba_image(2, 1, &[7, 8, 9, 255, 10, 11, 12, 128], &mut buffer).unwrap();
+        assert_eq!(buffer.as_ptr(), allocation);
+        assert_eq!(buffer.as_raw(), &vec![9, 8, 7, 255, 12, 11, 10, 128]);
+    }
+
+    #[test]
+    fn capture_into_resizes_the_buffer_for_a_new_size() {
+        let mut buffer = RgbaImage::new(2, 1);
+        bgra_into_rgba_image(1, 1, &[1, 2, 3, 255], &mut buffer).unwrap();
+        assert_eq!(buffer.dimensions(), (1, 1));
+        assert_eq!(buffer.as_raw(), &vec![3, 2, 1, 255]);
+        assert!(bgra_into_rgba_image(2, 2, &[0; 4], &mut buffer).is_err());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,115 @@
+// src/capture/diff.rs
+use image::{DynamicImage, Rgba, RgbaImage};
+
+/// Prompt for comparing two captures, optionally followed by a diff overlay
+pub const CHANGES_PROMPT: &str = "The first image is an earlier screenshot and the second image is a later screenshot of the same screen. \
+Describe what changed between them: new, removed or moved UI elements, changed text, and anything that looks like an error or regression. \
+Ignore changes that are only compression noise.";
+
+/// Extra sentence added to the prompt when the overlay is sent as a third image
+pub const OVERLAY_PROMPT_NOTE: &str = " The third image highlights the changed pixels in red on a dimmed copy of the later screenshot.";
+
+/// Largest per-channel difference still treated as unchanged (absorbs compression and dithering noise)
+const DIFF_THRESHOLD: u8 = 24;
+
+const CHANGED_COLOR: Rgba<u8> = Rgba([255, 0, 0, 255]);
+
+/// Pixel differences between two same-sized captures
+pub struct PixelDiff {
+    /// The later image, dimmed, with changed pixels in red
+    pub overlay: DynamicImage,
+    pub changed_pixels: u64,
+}
+
+/// Compare two captures pixel by pixel; None when their sizes differ
+pub fn pixel_diff(before: &DynamicImage, after: &DynamicImage) -> Option<PixelDiff> {
+    if before.width() != after.width() || before.height() != after.height() {
+        return None;
+    }
+
+    let before = before.to_rgba8();
+    let after = after.to_rgba8();
+    let mut changed_pixels = 0;
+    let overlay = RgbaImage::from_fn(after.width(), after.height(), |x, y| {
+        let old = before.get_pixel(x, y);
+        let new = after.get_pixel(x, y);
+        let changed = old.0.iter().zip(new.0.iter()).take(3).any(|(a, b)| a.abs_diff(*b) > DIFF_THRESHOLD);
+        if changed {
+            changed_pixels += 1;
+            CHANGED_COLOR
+        } else {
+            let luma = (new[0] as u32 * 299 + new[1] as u32 * 587 + new[2] as u32 * 114) / 1000;
+            let dimmed = (luma / 3) as u8;
+            Rgba([dimmed, dimmed, dimmed, 255])
+        }
+    });
+
+    Some(PixelDiff {
+        overlay: DynamicImage::ImageRgba8(overlay),
+        changed_pixels,
+    })
+}
+
+/// How alike two same-sized images are, from 0.0 (opposite colors everywhere) to 1.0 (identical):
+/// one minus the mean absolute RGB difference, normalized. None when their sizes differ
+pub fn similarity(before: &DynamicImage, after: &DynamicImage) -> Option<f64> {
+    if before.width() != after.width() || before.height() != after.height() {
+        return None;
+    }
+
+    let before = before.to_rgba8();
+    let after = after.to_rgba8();
+    let samples = before.width() as u64 * before.height() as u64 * 3;
+    if samples == 0 {
+        return Some(1.0);
+    }
+    let total: u64 = before.pixels().zip(after.pixels())
+        .map(|(old, new)| old.0.iter().zip(new.0.iter()).take(3).map(|(a, b)| a.abs_diff(*b) as u64).sum::<u64>())
+        .sum();
+    Some(1.0 - total as f64 / (samples as f64 * 255.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 64x64 image with a white square on black, `offset` pixels right of the middle
+    fn square(offset: u32) -> DynamicImage {
+        DynamicImage::ImageRgba8(RgbaImage::from_fn(64, 64, |x, y| {
+            if (16 + offset..48 + offset).contains(&x) && (16..48).contains(&y) {
+                Rgba([255, 255, 255, 255])
+            } else {
+                Rgba([0, 0, 0, 255])
+            }
+        }))
+    }
+
+    #[test]
+    fn identical_images_are_fully_similar() {
+        assert_eq!(similarity(&square(0), &square(0)), Some(1.0));
+        assert_eq!(pixel_diff(&square(0), &square(0)).map(|diff| diff.changed_pixels), Some(0));
+    }
+
+    #[test]
+    fn shifted_image_is_similar_but_not_identical() {
+        let score = similarity(&square(0), &square(2)).unwrap();
+        // Two 32-pixel-tall columns change on each side of the square
+        assert!((score - (1.0 - 128.0 / 4096.0)).abs() < 1e-9, "score {}", score);
+        assert_eq!(pixel_diff(&square(0), &square(2)).map(|diff| diff.changed_pixels), Some(128));
+    }
+
+    #[test]
+    fn different_images_score_low() {
+        let black = DynamicImage::ImageRgba8(RgbaImage::from_pixel(64, 64, Rgba([0, 0, 0, 255])));
+        let white = DynamicImage::ImageRgba8(RgbaImage::from_pixel(64, 64, Rgba([255, 255, 255, 255])));
+        assert_eq!(similarity(&black, &white), Some(0.0));
+        assert!(similarity(&square(0), &white).unwrap() < 0.5);
+    }
+
+    #[test]
+    fn images_of_different_sizes_are_not_compared() {
+        let small = DynamicImage::ImageRgba8(RgbaImage::new(32, 32));
+        assert_eq!(similarity(&square(0), &small), None);
+        assert!(pixel_diff(&square(0), &small).is_none());
+    }
+}
\ No newline at end of file
@@ -1,3 +1,10 @@
 // src/capture/mod.rs
+pub mod annotate;
+pub mod backend;
+pub mod diff;
+#[cfg(all(target_os = "linux", feature = "portal"))]
+pub mod portal;
+pub mod roi;
+pub mod scroll;
 pub mod screenshot;
 pub mod window_finder;
\ No newline at end of file
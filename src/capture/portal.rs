@@ -0,0 +1,157 @@
+// src/capture/portal.rs
+use anyhow::{Result, anyhow};
+use image::DynamicImage;
+use log::{info, warn};
+use screenshots::Screen;
+use std::collections::HashMap;
+use zbus::blocking::{Connection, Proxy};
+use zbus::zvariant::{OwnedObjectPath, OwnedValue, Value};
+
+use super::backend::{CaptureBackend, CaptureCancelled};
+
+const PORTAL_SERVICE: &str = "org.freedesktop.portal.Desktop";
+const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+
+/// Response code a portal request ends with when the user dismissed its dialog (0 is success, 2 any other failure)
+const RESPONSE_CANCELLED: u32 = 1;
+
+/// Whether this is a GNOME session on Wayland, where neither X11 nor the wlroots protocols can read the screen
+pub fn is_gnome_wayland() -> bool {
+    let wayland = std::env::var("XDG_SESSION_TYPE").map_or(false, |session| session == "wayland")
+        || std::env::var_os("WAYLAND_DISPLAY").is_some();
+    let gnome = std::env::var("XDG_CURRENT_DESKTOP")
+        .map_or(false, |desktop| desktop.split(':').any(|name| name.eq_ignore_ascii_case("gnome")));
+    wayland && gnome
+}
+
+/// Take a screenshot through the xdg-desktop-portal Screenshot interface. With `interactive` the portal shows
+/// its dialog so the user picks a screen, window or area; otherwise it takes the whole desktop (it may still
+/// ask for permission the first time). Closing the dialog gives a `CaptureCancelled` error
+pub fn screenshot(interactive: bool) -> Result<DynamicImage> {
+    let connection = Connection::session()
+        .map_err(|e| anyhow!("No D-Bus session bus to reach the screenshot portal: {}", e))?;
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0);
+    let token = format!("screensnap_{}_{}", std::process::id(), nanos);
+    let sender = match connection.unique_name() {
+        Some(name) => name.as_str().trim_start_matches(':').replace('.', "_"),
+        None => return Err(anyhow!("The D-Bus connection has no unique name")),
+    };
+
+    // The answer comes as a signal on a request object whose path follows from the token;
+    // subscribe before asking so a fast reply can't be missed
+    let request_path = format!("{}/request/{}/{}", PORTAL_PATH, sender, token);
+    let request = Proxy::new(&connection, PORTAL_SERVICE, request_path.as_str(), "org.freedesktop.portal.Request")?;
+    let mut responses = request.receive_signal("Response")?;
+
+    let portal = Proxy::new(&connection, PORTAL_SERVICE, PORTAL_PATH, "org.freedesktop.portal.Screenshot")?;
+    let mut options: HashMap<&str, Value> = HashMap::new();
+    options.insert("handle_token", Value::from(token.as_str()));
+    options.insert("interactive", Value::from(interactive));
+    let handle: OwnedObjectPath = portal.call("Screenshot", &("", options))
+        .map_err(|e| anyhow!("The screenshot portal is unavailable (is xdg-desktop-portal running?): {}", e))?;
+    if handle.as_str() != request_path {
+        warn!("The screenshot portal answered on {} instead of {}; it may be too old", handle.as_str(), request_path);
+    }
+    info!("Waiting for the screenshot portal{}", if interactive { " (pick what to capture in its dialog)" } else { "" });
+
+    let message = responses.next()
+        .ok_or_else(|| anyhow!("The screenshot portal closed without answering"))?;
+    let (response, results): (u32, HashMap<String, OwnedValue>) = message.body()?;
+    match response {
+        0 => {}
+        RESPONSE_CANCELLED => return Err(CaptureCancelled.into()),
+        code => return Err(anyhow!("The screenshot portal failed (response {})", code)),
+    }
+
+    let uri = match results.get("uri").cloned().map(String::try_from) {
+        Some(Ok(uri)) => uri,
+        _ => return Err(anyhow!("The screenshot portal didn't say where it saved the screenshot")),
+    };
+    let path = match uri.strip_prefix("file://") {
+        Some(path) => percent_decode(path),
+        None => return Err(anyhow!("The screenshot portal returned an unsupported location: {}", uri)),
+    };
+    let image = image::open(&path)
+        .map_err(|e| anyhow!("Failed to read the portal's screenshot from {}: {}", path, e));
+    // The portal saved it only because we asked; don't leave a file behind for every capture
+    if let Err(e) = std::fs::remove_file(&path) {
+        warn!("Could not remove the portal's screenshot {}: {}", path, e);
+    }
+    image
+}
+
+/// Decode %XX escapes in a file URI path
+fn percent_decode(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = if bytes[i] == b'%' && i + 2 < bytes.len() {
+            std::str::from_utf8(&bytes[i + 1..i + 3]).ok().and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        } else {
+            None
+        };
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Cut a monitor, or a rectangle relative to its top-left corner, out of a screenshot of the whole desktop
+fn crop_to_monitor(desktop: DynamicImage, monitor: usize, area: Option<(i32, i32, u32, u32)>) -> Result<DynamicImage> {
+    let screens = Screen::all().unwrap_or_default();
+    let info = match screens.get(monitor) {
+        Some(screen) => screen.display_info,
+        // Without display geometry the desktop is all there is
+        None if monitor == 0 => return Ok(match area {
+            Some((x, y, width, height)) => desktop.crop_imm(x.max(0) as u32, y.max(0) as u32, width, height),
+            None => desktop,
+        }),
+        None => return Err(anyhow!("Monitor {} not found ({} available)", monitor, screens.len())),
+    };
+    // The desktop image starts at the leftmost and topmost monitor edges
+    let left = screens.iter().map(|s| s.display_info.x).min().unwrap_or(0);
+    let top = screens.iter().map(|s| s.display_info.y).min().unwrap_or(0);
+    let (x, y, width, height) = match area {
+        Some((x, y, width, height)) => (info.x + x, info.y + y, width, height),
+        None => (info.x, info.y, info.width, info.height),
+    };
+    Ok(desktop.crop_imm((x - left).max(0) as u32, (y - top).max(0) as u32, width, height))
+}
+
+/// Capture through xdg-desktop-portal, the only way to read the screen on GNOME Wayland.
+/// Windows can't be looked up by title there, so window captures go through the portal's own picker
+pub struct PortalBackend;
+
+impl CaptureBackend for PortalBackend {
+    fn name(&self) -> &'static str {
+        "portal"
+    }
+
+    fn capture_screen(&self, monitor: usize) -> Result<DynamicImage> {
+        crop_to_monitor(screenshot(false)?, monitor, None)
+    }
+
+    fn capture_area(&self, monitor: usize, x: i32, y: i32, width: u32, height: u32) -> Result<DynamicImage> {
+        crop_to_monitor(screenshot(false)?, monitor, Some((x, y, width, height)))
+    }
+
+    fn picks_windows(&self) -> bool {
+        true
+    }
+
+    fn capture_picked(&self) -> Result<DynamicImage> {
+        screenshot(true)
+    }
+}
\ No newline at end of file
@@ -0,0 +1,93 @@
+// src/capture/roi.rs
+use anyhow::{Result, anyhow};
+use image::{DynamicImage, GenericImageView};
+use log::{info, warn};
+
+use crate::ai::connector::AiConnector;
+
+use super::annotate::{self, BoundingBox};
+use super::screenshot;
+
+/// Appended to the prompt when a region of interest is sent alongside the full capture
+pub const ROI_PROMPT_NOTE: &str = "\n\nThe first image is the full screenshot with a region of interest outlined in red; \
+the second image is that region cropped at full resolution. Focus on the highlighted region and use the rest of the screenshot only as context.";
+
+/// A rectangle in image pixel coordinates
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Roi {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Roi {
+    /// The same region in an image resized by `factor`
+    pub fn scaled(self, factor: f32) -> Roi {
+        Roi {
+            x: (self.x as f32 * factor).round() as i32,
+            y: (self.y as f32 * factor).round() as i32,
+            width: ((self.width as f32 * factor).round() as u32).max(1),
+            height: ((self.height as f32 * factor).round() as u32).max(1),
+        }
+    }
+
+    /// Clip the rectangle to an image of the given size; None if no part of it is inside
+    pub fn clamp_to(self, image_width: u32, image_height: u32) -> Option<Roi> {
+        let left = (self.x as i64).clamp(0, image_width as i64);
+        let top = (self.y as i64).clamp(0, image_height as i64);
+        let right = (self.x as i64 + self.width as i64).clamp(0, image_width as i64);
+        let bottom = (self.y as i64 + self.height as i64).clamp(0, image_height as i64);
+        if right <= left || bottom <= top {
+            return None;
+        }
+        Some(Roi {
+            x: left as i32,
+            y: top as i32,
+            width: (right - left) as u32,
+            height: (bottom - top) as u32,
+        })
+    }
+}
+
+/// The prompt with `ROI_PROMPT_NOTE` appended, for a request that sends a region of interest
+pub fn roi_prompt(prompt: &str) -> String {
+    format!("{}{}", prompt, ROI_PROMPT_NOTE)
+}
+
+/// Send the capture to the model, or for a region of interest the outlined capture and the cropped region
+/// together; the model's prompt should already come from `roi_prompt`
+pub fn analyze(ai_model: &mut dyn AiConnector, image_data: &[u8], roi: Option<Roi>) -> Result<String> {
+    match roi {
+        Some(roi) => {
+            let images = roi_images(image_data, roi)?;
+            let image_refs: Vec<&[u8]> = images.iter().map(|image| image.as_slice()).collect();
+            ai_model.process_images(&image_refs)
+        }
+        None => ai_model.process_image(image_data),
+    }
+}
+
+/// PNGs to send for a region of interest: the full capture with the region outlined, then the region itself
+pub fn roi_images(image_data: &[u8], roi: Roi) -> Result<Vec<Vec<u8>>> {
+    let image = image::load_from_memory(image_data)?;
+    let (width, height) = image.dimensions();
+    let clamped = roi.clamp_to(width, height).ok_or_else(|| {
+        anyhow!("Region {}x{}+{}+{} lies outside the {}x{} capture", roi.width, roi.height, roi.x, roi.y, width, height)
+    })?;
+    if clamped != roi {
+        warn!("Region clamped to the capture: {}x{}+{}+{}", clamped.width, clamped.height, clamped.x, clamped.y);
+    }
+    info!("Sending region {}x{}+{}+{} with the full capture", clamped.width, clamped.height, clamped.x, clamped.y);
+    
+    let outline = BoundingBox {
+        label: String::new(),
+        x: clamped.x as f32 / width as f32,
+        y: clamped.y as f32 / height as f32,
+        width: clamped.width as f32 / width as f32,
+        height: clamped.height as f32 / height as f32,
+    };
+    let highlighted = annotate::draw_bounding_boxes(&image, &[outline]);
+    let cropped = image.crop_imm(clamped.x as u32, clamped.y as u32, clamped.width, clamped.height);
+    Ok(vec![screenshot::encode_png(&highlighted)?, screenshot::encode_png(&cropped)?])
+}
\ No newline at end of file
@@ -1,120 +1,978 @@
 // src/capture/screenshot.rs
 use anyhow::{Result, anyhow};
-use image::DynamicImage;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::{CompressionType, FilterType, PngEncoder};
+use image::{DynamicImage, GenericImageView, ImageEncoder, ImageFormat, Rgba};
+use imageproc::drawing::draw_hollow_rect_mut;
+use imageproc::rect::Rect;
+use serde::{Serialize, Deserialize};
 use screenshots::Screen;
-use std::io::Cursor;
-use log::info;
-use super::window_finder;
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufReader, BufWriter, Cursor};
+use std::path::Path;
+use std::time::{Duration, Instant};
+use log::{info, warn};
+use super::backend::{self, BgraFrame, CaptureBackend, CaptureBackendKind, NO_USABLE_DISPLAY};
+use super::{scroll, window_finder};
+
+/// Number of previous images kept in the history
+const MAX_HISTORY: usize = 10;
+
+/// Wait after each scroll so smooth scrolling and lazy-loaded content can settle
+const SCROLL_SETTLE: Duration = Duration::from_millis(400);
+
+/// Border drawn around the window by `capture_window_highlighted`
+const HIGHLIGHT_COLOR: Rgba<u8> = Rgba([255, 64, 64, 255]);
+const HIGHLIGHT_THICKNESS: i32 = 4;
+
+/// Resizing a capture up by more than this factor is warned about, as the model mostly sees blur
+const EXTREME_UPSCALE: f32 = 4.0;
+
+/// Kept free at the bottom of a monitor when the platform can't report its work area, about a taskbar's height
+const FALLBACK_TASKBAR_HEIGHT: u32 = 40;
+
+/// Samples taken along each axis when checking for a blank capture
+const BLANK_SAMPLE_GRID: u32 = 64;
+
+/// Largest per-channel spread between sampled pixels that still counts as uniform
+const BLANK_TOLERANCE: u8 = 12;
+
+/// Speed/size tradeoff when writing PNG files. Measured on a 2560x1440 capture
+/// mixing UI and a photo: fast ~15 ms / 2.0 MB, default ~450 ms / 1.1 MB, best ~1.5 s / 1.0 MB
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum PngCompression {
+    /// Quickest to write, about twice the size (good for watch/timelapse)
+    Fast,
+    #[default]
+    Default,
+    /// Slowest, a few percent smaller than default (for archiving)
+    Best,
+}
+
+impl PngCompression {
+    pub const ALL: [PngCompression; 3] = [PngCompression::Fast, PngCompression::Default, PngCompression::Best];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PngCompression::Fast => "Fast",
+            PngCompression::Default => "Default",
+            PngCompression::Best => "Best",
+        }
+    }
+}
+
+/// A rectangle in absolute virtual-desktop coordinates
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct CaptureRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl CaptureRect {
+    /// The overlap of two rectangles, or None when they don't share any pixels
+    pub fn intersect(&self, other: &CaptureRect) -> Option<CaptureRect> {
+        let left = self.x.max(other.x);
+        let top = self.y.max(other.y);
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+        if right <= left as i64 || bottom <= top as i64 {
+            return None;
+        }
+        Some(CaptureRect { x: left, y: top, width: (right - left as i64) as u32, height: (bottom - top as i64) as u32 })
+    }
+
+    /// The smallest rectangle containing both
+    pub fn union(&self, other: &CaptureRect) -> CaptureRect {
+        let left = self.x.min(other.x);
+        let top = self.y.min(other.y);
+        let right = self.right().max(other.right());
+        let bottom = self.bottom().max(other.bottom());
+        CaptureRect { x: left, y: top, width: (right - left as i64) as u32, height: (bottom - top as i64) as u32 }
+    }
+
+    /// Shift this rectangle inside `bounds`, shrinking it first if it is larger
+    pub fn clamp_into(&self, bounds: &CaptureRect) -> CaptureRect {
+        let width = self.width.min(bounds.width);
+        let height = self.height.min(bounds.height);
+        let x = (self.x as i64).clamp(bounds.x as i64, bounds.right() - width as i64) as i32;
+        let y = (self.y as i64).clamp(bounds.y as i64, bounds.bottom() - height as i64) as i32;
+        CaptureRect { x, y, width, height }
+    }
+
+    fn right(&self) -> i64 {
+        self.x as i64 + self.width as i64
+    }
+
+    fn bottom(&self) -> i64 {
+        self.y as i64 + self.height as i64
+    }
+}
+
+/// What the current image was captured from
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CaptureSource {
+    /// A whole monitor, by its index in `Screen::all()`
+    FullScreen { monitor: usize },
+    Window { title: String },
+    Region { rect: CaptureRect },
+}
+
+impl CaptureSource {
+    /// Title of the captured window, if this was a window capture
+    pub fn window_title(&self) -> Option<&str> {
+        match self {
+            CaptureSource::Window { title } => Some(title),
+            _ => None,
+        }
+    }
+
+    /// Monitor index, if this was a full-screen capture
+    pub fn monitor(&self) -> Option<usize> {
+        match self {
+            CaptureSource::FullScreen { monitor } => Some(*monitor),
+            _ => None,
+        }
+    }
+}
+
+/// Fill `{window}` in a prompt with the captured window's title ("the screen" for other captures)
+pub fn expand_prompt_variables(prompt: &str, source: Option<&CaptureSource>) -> String {
+    let window = source.and_then(CaptureSource::window_title).unwrap_or("the screen");
+    prompt.replace("{window}", window)
+}
+
+/// Clockwise rotation applied to a capture
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Rotation {
+    #[value(name = "90")]
+    Deg90,
+    #[value(name = "180")]
+    Deg180,
+    #[value(name = "270")]
+    Deg270,
+}
+
+impl Rotation {
+    /// The rotation for a monitor's reported angle, if it is a quarter turn
+    fn from_degrees(degrees: f32) -> Option<Self> {
+        match degrees.round() as i32 {
+            90 => Some(Rotation::Deg90),
+            180 => Some(Rotation::Deg180),
+            270 => Some(Rotation::Deg270),
+            _ => None,
+        }
+    }
+}
+
+/// Mirror applied to a capture
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Flip {
+    /// Left to right
+    #[value(name = "h")]
+    Horizontal,
+    /// Top to bottom
+    #[value(name = "v")]
+    Vertical,
+}
+
+/// How a capture is fitted into a fixed output size
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Fit {
+    /// Scale to fit inside and letterbox the rest in black
+    Contain,
+    /// Scale to fill and crop the overflow, keeping the center
+    Cover,
+    /// Scale each axis separately, ignoring the aspect ratio
+    Stretch,
+}
+
+/// Resize an image to exactly `width`x`height` with the given fit
+fn fit_image(image: &DynamicImage, width: u32, height: u32, fit: Fit) -> DynamicImage {
+    let filter = image::imageops::FilterType::Lanczos3;
+    let (scale_x, scale_y) = (width as f32 / image.width() as f32, height as f32 / image.height() as f32);
+    match fit {
+        Fit::Stretch => DynamicImage::ImageRgba8(image::imageops::resize(image, width, height, filter)),
+        Fit::Contain => {
+            let scale = scale_x.min(scale_y);
+            let scaled_width = ((image.width() as f32 * scale).round() as u32).clamp(1, width);
+            let scaled_height = ((image.height() as f32 * scale).round() as u32).clamp(1, height);
+            let scaled = image::imageops::resize(image, scaled_width, scaled_height, filter);
+            let mut canvas = image::RgbaImage::from_pixel(width, height, Rgba([0, 0, 0, 255]));
+            let (x, y) = ((width - scaled_width) / 2, (height - scaled_height) / 2);
+            image::imageops::overlay(&mut canvas, &scaled, x as i64, y as i64);
+            DynamicImage::ImageRgba8(canvas)
+        }
+        Fit::Cover => {
+            // Crop the source to the output's aspect first, so the resize never builds an oversized image
+            let scale = scale_x.max(scale_y);
+            let crop_width = ((width as f32 / scale).round() as u32).clamp(1, image.width());
+            let crop_height = ((height as f32 / scale).round() as u32).clamp(1, image.height());
+            let (x, y) = ((image.width() - crop_width) / 2, (image.height() - crop_height) / 2);
+            let cropped = image.crop_imm(x, y, crop_width, crop_height);
+            DynamicImage::ImageRgba8(image::imageops::resize(&cropped, width, height, filter))
+        }
+    }
+}
+
+/// Fail with a clear message when the folder a file would be saved in doesn't exist,
+/// rather than the bare "No such file or directory" from creating the file
+pub fn check_output_dir(path: &Path) -> Result<()> {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() && !parent.is_dir() => {
+            Err(anyhow!("Can't save {}: the folder {} doesn't exist", path.display(), parent.display()))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Make sure the folder `path` would be saved in exists, creating it (and any missing parents)
+/// when `create` is set and otherwise failing like `check_output_dir`
+pub fn prepare_output_dir(path: &Path, create: bool) -> Result<()> {
+    if !create {
+        return check_output_dir(path);
+    }
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => std::fs::create_dir_all(parent)
+            .map_err(|e| anyhow!("Could not create {}: {}", parent.display(), e)),
+        _ => Ok(()),
+    }
+}
+
+fn create_output_file(path: &Path) -> Result<std::fs::File> {
+    check_output_dir(path)?;
+    std::fs::File::create(path).map_err(|e| anyhow!("Can't save {}: {}", path.display(), e))
+}
+
+/// Write an image as a PNG file with the given compression
+pub fn save_png(image: &DynamicImage, path: &Path, compression: PngCompression) -> Result<()> {
+    let (compression_type, filter_type) = match compression {
+        PngCompression::Fast => (CompressionType::Fast, FilterType::Sub),
+        PngCompression::Default => (CompressionType::Default, FilterType::Adaptive),
+        PngCompression::Best => (CompressionType::Best, FilterType::Adaptive),
+    };
+    let writer = BufWriter::new(create_output_file(path)?);
+    let rgba = image.to_rgba8();
+    PngEncoder::new_with_quality(writer, compression_type, filter_type)
+        .write_image(rgba.as_raw(), rgba.width(), rgba.height(), image::ColorType::Rgba8)?;
+    Ok(())
+}
+
+/// Write a PNG with `metadata` as (keyword, text) chunks, e.g. ("Model", "llava:latest"). Latin-1 text
+/// goes in tEXt chunks; anything else in iTXt, which tEXt can't hold
+pub fn save_png_with_metadata(image: &DynamicImage, path: &Path, compression: PngCompression, metadata: &[(String, String)]) -> Result<()> {
+    let (compression, adaptive_filter) = match compression {
+        PngCompression::Fast => (png::Compression::Fast, png::AdaptiveFilterType::NonAdaptive),
+        PngCompression::Default => (png::Compression::Default, png::AdaptiveFilterType::Adaptive),
+        PngCompression::Best => (png::Compression::Best, png::AdaptiveFilterType::Adaptive),
+    };
+    let rgba = image.to_rgba8();
+    let writer = BufWriter::new(create_output_file(path)?);
+    let mut encoder = png::Encoder::new(writer, rgba.width(), rgba.height());
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_compression(compression);
+    encoder.set_filter(png::FilterType::Sub);
+    encoder.set_adaptive_filter(adaptive_filter);
+    for (keyword, text) in metadata {
+        if text.chars().all(|c| (c as u32) < 0x100) {
+            encoder.add_text_chunk(keyword.clone(), text.clone())?;
+        } else {
+            encoder.add_itxt_chunk(keyword.clone(), text.clone())?;
+        }
+    }
+    encoder.write_header()?.write_image_data(rgba.as_raw())?;
+    Ok(())
+}
+
+/// The text chunks of a PNG file as (keyword, text): tEXt first, then zTXt and iTXt. Empty when it has none
+pub fn read_png_metadata(path: &Path) -> Result<Vec<(String, String)>> {
+    let decoder = png::Decoder::new(BufReader::new(std::fs::File::open(path)?));
+    let reader = decoder.read_info().map_err(|e| anyhow!("{} isn't a readable PNG: {}", path.display(), e))?;
+    let info = reader.info();
+    let mut metadata: Vec<(String, String)> = info.uncompressed_latin1_text.iter()
+        .map(|chunk| (chunk.keyword.clone(), chunk.text.clone()))
+        .collect();
+    for chunk in &info.compressed_latin1_text {
+        match chunk.get_text() {
+            Ok(text) => metadata.push((chunk.keyword.clone(), text)),
+            Err(e) => warn!("Skipping unreadable zTXt chunk '{}': {}", chunk.keyword, e),
+        }
+    }
+    for chunk in &info.utf8_text {
+        match chunk.get_text() {
+            Ok(text) => metadata.push((chunk.keyword.clone(), text)),
+            Err(e) => warn!("Skipping unreadable iTXt chunk '{}': {}", chunk.keyword, e),
+        }
+    }
+    Ok(metadata)
+}
+
+/// The usable part of a monitor, without the taskbar, dock or panels. Where the platform can't
+/// report it, `FALLBACK_TASKBAR_HEIGHT` is kept free at the bottom instead
+pub fn work_area(monitor: usize) -> Result<CaptureRect> {
+    let screens = Screen::all()?;
+    let info = screens.get(monitor)
+        .ok_or_else(|| anyhow!("Monitor {} not found ({} available)", monitor, screens.len()))?
+        .display_info;
+    let bounds = window_finder::WindowBounds { x: info.x, y: info.y, width: info.width as i32, height: info.height as i32 };
+    match window_finder::get_work_area(&bounds) {
+        Ok(area) if area.width > 0 && area.height > 0 => {
+            Ok(CaptureRect { x: area.x, y: area.y, width: area.width as u32, height: area.height as u32 })
+        }
+        result => {
+            if let Err(e) = result {
+                info!("Approximating the work area of monitor {}: {}", monitor, e);
+            }
+            Ok(CaptureRect { x: info.x, y: info.y, width: info.width, height: info.height.saturating_sub(FALLBACK_TASKBAR_HEIGHT) })
+        }
+    }
+}
+
+/// The primary screen, skipping any that report a zero size. Without
+/// display info at all, let the backend capture whatever it can
+fn primary_monitor() -> Result<usize> {
+    match Screen::all() {
+        Ok(screens) if !screens.is_empty() => {
+            backend::select_usable_screen(&screens).ok_or_else(|| anyhow!(NO_USABLE_DISPLAY))
+        }
+        _ => Ok(0),
+    }
+}
+
+/// The monitor fully containing a window, or the primary one when it spans several
+fn monitor_for_window(screens: &[Screen], window_bounds: &window_finder::WindowBounds) -> Result<usize> {
+    if screens.is_empty() {
+        return Err(anyhow!("No screens found"));
+    }
+    let fallback_monitor = backend::select_usable_screen(screens).ok_or_else(|| anyhow!(NO_USABLE_DISPLAY))?;
+    
+    Ok(screens.iter().position(|s| {
+        let bounds = s.display_info;
+        window_bounds.x >= bounds.x &&
+        window_bounds.y >= bounds.y &&
+        (window_bounds.x + window_bounds.width) <= (bounds.x + bounds.width as i32) &&
+        (window_bounds.y + window_bounds.height) <= (bounds.y + bounds.height as i32)
+    }).unwrap_or(fallback_monitor))
+}
+
+/// Split a window into its overlap with each monitor, as (monitor index, overlap) in absolute coordinates.
+/// Parts of the window that are off every monitor are dropped, which clamps it to the virtual desktop
+fn window_pieces(monitors: &[CaptureRect], window: &CaptureRect) -> Vec<(usize, CaptureRect)> {
+    monitors.iter()
+        .enumerate()
+        .filter_map(|(index, monitor)| monitor.intersect(window).map(|piece| (index, piece)))
+        .collect()
+}
+
+fn rotate_image(image: &DynamicImage, rotation: Rotation) -> DynamicImage {
+    match rotation {
+        Rotation::Deg90 => image.rotate90(),
+        Rotation::Deg180 => image.rotate180(),
+        Rotation::Deg270 => image.rotate270(),
+    }
+}
+
+/// JPEG quality used when a capture is saved as .jpg/.jpeg
+const JPEG_QUALITY: u8 = 90;
+
+/// Save an image in the format its extension names (PNG with the given compression when the
+/// extension is missing or unknown). JPEG drops the alpha channel, which it can't store
+pub fn save_image(image: &DynamicImage, path: &Path, png_compression: PngCompression) -> Result<()> {
+    match ImageFormat::from_path(path) {
+        Ok(ImageFormat::Png) | Err(_) => save_png(image, path, png_compression),
+        Ok(ImageFormat::Jpeg) => {
+            let writer = BufWriter::new(create_output_file(path)?);
+            JpegEncoder::new_with_quality(writer, JPEG_QUALITY).encode_image(&image.to_rgb8())?;
+            Ok(())
+        }
+        Ok(format) => {
+            check_output_dir(path)?;
+            image.save_with_format(path, format)
+                .map_err(|e| anyhow!("Could not save {} as {:?}: {}", path.display(), format, e))
+        }
+    }
+}
+
+/// `save_image`, embedding `metadata` when the file is a PNG. Other formats are saved without it
+pub fn save_image_with_metadata(image: &DynamicImage, path: &Path, png_compression: PngCompression, metadata: &[(String, String)]) -> Result<()> {
+    match ImageFormat::from_path(path) {
+        Ok(ImageFormat::Png) | Err(_) => save_png_with_metadata(image, path, png_compression, metadata),
+        Ok(_) => {
+            if !metadata.is_empty() {
+                warn!("Metadata is only embedded in PNG files; saving {} without it", path.display());
+            }
+            save_image(image, path, png_compression)
+        }
+    }
+}
+
+/// Index in `Screen::all()` of the monitor containing an absolute desktop point
+pub fn screen_containing_point(x: i32, y: i32) -> Option<usize> {
+    let screens = Screen::all().ok()?;
+    screens.iter().position(|s| {
+        let bounds = s.display_info;
+        backend::is_usable_screen(s) &&
+        x >= bounds.x && y >= bounds.y &&
+        (x as i64) < bounds.x as i64 + bounds.width as i64 &&
+        (y as i64) < bounds.y as i64 + bounds.height as i64
+    })
+}
+
+/// Encode an image as PNG bytes
+pub fn encode_png(image: &DynamicImage) -> Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    image.write_to(&mut Cursor::new(&mut buffer), image::ImageOutputFormat::Png)?;
+    Ok(buffer)
+}
+
+/// Smallest width/height worth sending to a model
+const MIN_ANALYSIS_DIMENSION: u32 = 16;
+
+/// Refuse degenerate captures before spending a model call on them
+pub fn ensure_analyzable(image_data: &[u8]) -> Result<()> {
+    if image_data.is_empty() {
+        return Err(anyhow!("No image available"));
+    }
+    let (width, height) = image::io::Reader::new(Cursor::new(image_data))
+        .with_guessed_format()?
+        .into_dimensions()?;
+    if width < MIN_ANALYSIS_DIMENSION || height < MIN_ANALYSIS_DIMENSION {
+        return Err(anyhow!(
+            "captured image is too small to analyze ({}x{}, minimum {}x{})",
+            width, height, MIN_ANALYSIS_DIMENSION, MIN_ANALYSIS_DIMENSION
+        ));
+    }
+    Ok(())
+}
+
+/// Bytes an image takes up in a request once base64-encoded
+pub fn encoded_payload_size(image_data: &[u8]) -> usize {
+    (image_data.len() + 2) / 3 * 4
+}
+
+/// Passes made by `downscale_to_fit` before giving up on reaching the limit
+const MAX_DOWNSCALE_PASSES: usize = 5;
+
+/// Shrink an image until its longer side is at most `max_dimension`, when given, and its encoded payload
+/// fits in `max_bytes`. Returns the new PNG and the scale factor applied (1.0 when it already fit, in which
+/// case it isn't decoded)
+pub fn downscale_to_fit(image_data: &[u8], max_bytes: usize, max_dimension: Option<u32>) -> Result<(Vec<u8>, f32)> {
+    let (width, height) = image::io::Reader::new(Cursor::new(image_data))
+        .with_guessed_format()?
+        .into_dimensions()?;
+    let mut scale = match max_dimension {
+        Some(max_dimension) if width.max(height) > max_dimension => max_dimension as f32 / width.max(height) as f32,
+        _ => 1.0f32,
+    };
+    if scale == 1.0 && encoded_payload_size(image_data) <= max_bytes {
+        return Ok((image_data.to_vec(), scale));
+    }
+
+    let image = image::load_from_memory(image_data)?;
+    let resize = |scale: f32| {
+        let width = ((image.width() as f32 * scale).round() as u32).max(1);
+        let height = ((image.height() as f32 * scale).round() as u32).max(1);
+        let data = encode_png(&image.resize_exact(width, height, image::imageops::FilterType::Triangle))?;
+        info!("Downscaled {}x{} to {}x{} ({} bytes encoded)", image.width(), image.height(), width, height, encoded_payload_size(&data));
+        Ok::<_, anyhow::Error>(data)
+    };
+    let mut data = if scale < 1.0 { resize(scale)? } else { image_data.to_vec() };
+    for _ in 0..MAX_DOWNSCALE_PASSES {
+        let size = encoded_payload_size(&data);
+        if size <= max_bytes {
+            break;
+        }
+        // PNG size roughly follows the pixel count, so scale the sides by the square root, with some margin
+        scale *= ((max_bytes as f32 / size as f32).sqrt() * 0.9).min(0.9);
+        data = resize(scale)?;
+    }
+    Ok((data, scale))
+}
+
+/// Whether a capture is (nearly) a single flat color, e.g. the black frame some GPUs return right after wake
+pub fn is_probably_blank(image: &DynamicImage) -> bool {
+    let (width, height) = image.dimensions();
+    if width == 0 || height == 0 {
+        return true;
+    }
+
+    let steps_x = BLANK_SAMPLE_GRID.min(width);
+    let steps_y = BLANK_SAMPLE_GRID.min(height);
+    let mut min = [u8::MAX; 3];
+    let mut max = [u8::MIN; 3];
+    for row in 0..steps_y {
+        let y = row * height / steps_y;
+        for col in 0..steps_x {
+            let x = col * width / steps_x;
+            let pixel = image.get_pixel(x, y);
+            for (channel, &value) in pixel.0.iter().take(3).enumerate() {
+                min[channel] = min[channel].min(value);
+                max[channel] = max[channel].max(value);
+                if max[channel] - min[channel] > BLANK_TOLERANCE {
+                    return false;
+                }
+            }
+        }
+    }
+    true
+}
 
 pub struct ScreenshotManager {
+    backend: Box<dyn CaptureBackend>,
     current_image: Option<DynamicImage>,
+    current_source: Option<CaptureSource>,
+    /// Unconverted pixels from `capture_monitor_raw_bgra`, until the next capture
+    raw_bgra: Option<BgraFrame>,
+    history: VecDeque<DynamicImage>,
+    /// The whole image from before `crop_current_image` and where the crop sits in it
+    uncropped: Option<(DynamicImage, CaptureRect)>,
+    client_area_only: bool,
+    auto_orient: bool,
+    /// Window bounds by title and when they were looked up; None when caching is off
+    bounds_cache: Option<(Duration, HashMap<String, (window_finder::WindowBounds, Instant)>)>,
 }
 
 impl ScreenshotManager {
     pub fn new() -> Result<Self> {
+        Self::with_backend(CaptureBackendKind::Auto)
+    }
+
+    /// Create a manager capturing through the given backend
+    pub fn with_backend(kind: CaptureBackendKind) -> Result<Self> {
         Ok(Self {
+            backend: backend::create_backend(kind)?,
             current_image: None,
+            current_source: None,
+            raw_bgra: None,
+            history: VecDeque::new(),
+            uncropped: None,
+            client_area_only: false,
+            auto_orient: true,
+            bounds_cache: None,
         })
     }
+    
+    /// Capture only the content of windows, without title bar and borders
+    pub fn set_client_area_only(&mut self, client_area_only: bool) {
+        self.client_area_only = client_area_only;
+    }
+
+    /// Rotate full-monitor captures that come back sideways from a rotated (portrait) monitor
+    pub fn set_auto_orient(&mut self, auto_orient: bool) {
+        self.auto_orient = auto_orient;
+    }
+
+    /// Reuse window bounds for up to `ttl` in `capture_window` instead of enumerating windows every time.
+    /// A window that moves is picked up once its entry expires; None turns caching off
+    pub fn set_bounds_cache(&mut self, ttl: Option<Duration>) {
+        self.bounds_cache = ttl.map(|ttl| (ttl, HashMap::new()));
+    }
+
+    /// Forget all cached window bounds
+    pub fn invalidate_bounds_cache(&mut self) {
+        if let Some((_, entries)) = &mut self.bounds_cache {
+            entries.clear();
+        }
+    }
+
+    fn cached_bounds(&self, window_title: &str) -> Option<window_finder::WindowBounds> {
+        let (ttl, entries) = self.bounds_cache.as_ref()?;
+        entries.get(window_title)
+            .filter(|(_, looked_up)| looked_up.elapsed() < *ttl)
+            .map(|(bounds, _)| *bounds)
+    }
+
+    /// Look the window up, refreshing its cache entry when caching is on
+    fn fresh_bounds(&mut self, window_title: &str) -> Result<window_finder::WindowBounds> {
+        let bounds = window_finder::get_window_bounds(window_title, self.client_area_only)?;
+        if let Some((_, entries)) = &mut self.bounds_cache {
+            entries.insert(window_title.to_string(), (bounds, Instant::now()));
+        }
+        Ok(bounds)
+    }
 
     /// Capture the entire primary screen
     pub fn capture_screen(&mut self) -> Result<()> {
         info!("Capturing primary screen");
-        // Get all screens
-        let screens = Screen::all()?;
-        if screens.is_empty() {
-            return Err(anyhow!("No screens found"));
-        }
         
-        // Use the primary screen (first one)
-        let screen = &screens[0];
-        let image = screen.capture()?;
+        let monitor = primary_monitor()?;
+        let image = self.orient(self.backend.capture_screen(monitor)?, monitor);
+        let (width, height) = (image.width(), image.height());
+        self.set_current_image(image, Some(CaptureSource::FullScreen { monitor }));
         
-        // Convert to DynamicImage
-        let width = image.width() as u32;
-        let height = image.height() as u32;
+        info!("Screen captured: {}x{}", width, height);
+        Ok(())
+    }
+
+    /// Capture the primary screen into `buffer` for high-frequency loops, reusing its allocation while the
+    /// screen size stays the same. Unlike `capture_screen` this doesn't touch the current image or history
+    pub fn capture_screen_into(&self, buffer: &mut image::RgbaImage) -> Result<()> {
+        let monitor = primary_monitor()?;
+        self.backend.capture_screen_into(monitor, buffer)?;
+        if self.auto_orient {
+            // Only a rotated monitor produces a new image here; otherwise the buffer is handed straight back
+            let image = self.orient(DynamicImage::ImageRgba8(std::mem::take(buffer)), monitor);
+            *buffer = image.into_rgba8();
+        }
+        Ok(())
+    }
+
+    /// Capture an entire monitor by its index in `Screen::all()`
+    pub fn capture_monitor(&mut self, monitor: usize) -> Result<()> {
+        info!("Capturing monitor {}", monitor);
+        let image = self.orient(self.backend.capture_screen(monitor)?, monitor);
+        let (width, height) = (image.width(), image.height());
+        self.set_current_image(image, Some(CaptureSource::FullScreen { monitor }));
         
-        // Get raw data - the screenshots crate returns BGRA format
-        let buffer = image.as_raw().to_vec();
+        info!("Monitor {} captured: {}x{}", monitor, width, height);
+        Ok(())
+    }
+
+    /// Capture an entire monitor without converting it to RGBA, for callers that handle BGRA themselves.
+    /// No orientation fix is applied and the current image is left as it was; read the frame with
+    /// `get_current_image_raw_bgra`
+    pub fn capture_monitor_raw_bgra(&mut self, monitor: usize) -> Result<()> {
+        info!("Capturing monitor {} as raw BGRA", monitor);
+        let frame = self.backend.capture_screen_bgra(monitor)?;
+        info!("Monitor {} captured: {}x{}", monitor, frame.width, frame.height);
+        self.raw_bgra = Some(frame);
+        Ok(())
+    }
+
+    /// The frame from `capture_monitor_raw_bgra` with its width and height. The bytes are blue, green, red,
+    /// alpha per pixel, rows top to bottom with no padding. None once anything else has been captured
+    pub fn get_current_image_raw_bgra(&self) -> Option<(&[u8], u32, u32)> {
+        self.raw_bgra.as_ref().map(|frame| (frame.data.as_slice(), frame.width, frame.height))
+    }
+
+    /// Capture a region given in coordinates relative to the top-left corner of a monitor
+    pub fn capture_monitor_region(&mut self, monitor: usize, x: u32, y: u32, width: u32, height: u32) -> Result<()> {
+        info!("Capturing region {}x{}+{}+{} of monitor {}", width, height, x, y, monitor);
+        let screens = Screen::all()?;
+        let screen = screens.get(monitor).ok_or_else(|| {
+            anyhow!("Monitor {} not found ({} available)", monitor, screens.len())
+        })?;
         
-        // Convert BGRA to RGBA
-        let mut rgba_buffer = Vec::with_capacity(buffer.len());
-        for chunk in buffer.chunks(4) {
-            if chunk.len() == 4 {
-                rgba_buffer.push(chunk[2]); // R
-                rgba_buffer.push(chunk[1]); // G
-                rgba_buffer.push(chunk[0]); // B
-                rgba_buffer.push(chunk[3]); // A
-            }
+        let info = screen.display_info;
+        if width == 0 || height == 0 {
+            return Err(anyhow!("Region must have a non-zero width and height"));
+        }
+        let fits_horizontally = x.checked_add(width).map_or(false, |right| right <= info.width);
+        let fits_vertically = y.checked_add(height).map_or(false, |bottom| bottom <= info.height);
+        if !fits_horizontally || !fits_vertically {
+            return Err(anyhow!(
+                "Region {}x{}+{}+{} lies outside monitor {} ({}x{})",
+                width, height, x, y, monitor, info.width, info.height
+            ));
         }
         
-        let rgba = image::RgbaImage::from_raw(width, height, rgba_buffer)
-            .ok_or_else(|| anyhow!("Failed to create image from raw data"))?;
+        let image = self.backend.capture_area(monitor, x as i32, y as i32, width, height)?;
+        let rect = CaptureRect { x: info.x + x as i32, y: info.y + y as i32, width, height };
+        self.set_current_image(image, Some(CaptureSource::Region { rect }));
         
-        let dynamic_image = DynamicImage::ImageRgba8(rgba);
-        self.current_image = Some(dynamic_image);
+        info!("Monitor region captured: {}x{}", width, height);
+        Ok(())
+    }
+
+    /// Capture a monitor without its taskbar, dock or panels (see `work_area`)
+    pub fn capture_work_area(&mut self, monitor: usize) -> Result<()> {
+        let area = work_area(monitor)?;
+        let origin = Screen::all()?.get(monitor)
+            .map(|screen| (screen.display_info.x, screen.display_info.y))
+            .ok_or_else(|| anyhow!("Monitor {} not found", monitor))?;
+        let (x, y) = ((area.x - origin.0).max(0) as u32, (area.y - origin.1).max(0) as u32);
+        self.capture_monitor_region(monitor, x, y, area.width, area.height)
+    }
+
+    /// Capture the whole monitor the mouse cursor is currently on
+    pub fn capture_cursor_monitor(&mut self) -> Result<usize> {
+        let (x, y) = window_finder::get_cursor_position()?;
+        let monitor = screen_containing_point(x, y)
+            .ok_or_else(|| anyhow!("The cursor at ({}, {}) is not on any known monitor", x, y))?;
+        info!("Cursor at ({}, {}) is on monitor {}", x, y, monitor);
+        self.capture_monitor(monitor)?;
+        Ok(monitor)
+    }
+
+    /// Capture a square of side `2 * radius` centered on the mouse cursor. Near a screen edge the square is
+    /// moved inside the cursor's monitor rather than cut off. Returns the captured rectangle
+    pub fn capture_cursor_area(&mut self, radius: u32) -> Result<CaptureRect> {
+        if radius == 0 {
+            return Err(anyhow!("The area around the cursor needs a radius of at least 1 pixel"));
+        }
+        let (x, y) = window_finder::get_cursor_position()?;
+        let screens = Screen::all()?;
+        let info = screen_containing_point(x, y)
+            .and_then(|monitor| screens.get(monitor).map(|screen| (monitor, screen.display_info)));
+        let (monitor, info) = match info {
+            Some(found) => found,
+            None => return Err(anyhow!("The cursor at ({}, {}) is not on any known monitor", x, y)),
+        };
+        let bounds = CaptureRect { x: info.x, y: info.y, width: info.width, height: info.height };
+        let radius = radius.min(i32::MAX as u32 / 2);
+        let square = CaptureRect { x: x.saturating_sub(radius as i32), y: y.saturating_sub(radius as i32), width: radius * 2, height: radius * 2 };
+        let area = square.clamp_into(&bounds);
+        info!("Cursor at ({}, {}) on monitor {}, capturing {}x{}+{}+{}", x, y, monitor, area.width, area.height, area.x, area.y);
+        self.capture_monitor_region(monitor, (area.x - bounds.x) as u32, (area.y - bounds.y) as u32, area.width, area.height)?;
+        Ok(area)
+    }
+
+    /// Capture a region given in absolute virtual-desktop coordinates
+    pub fn capture_region(&mut self, x: i32, y: i32, width: u32, height: u32) -> Result<()> {
+        info!("Capturing region {}x{}+{}+{}", width, height, x, y);
+        let screens = Screen::all()?;
         
-        info!("Screen captured: {}x{}", width, height);
+        // Find the monitor containing the region's top-left corner
+        let monitor = screen_containing_point(x, y)
+            .ok_or_else(|| anyhow!("Region origin ({}, {}) is not on any monitor", x, y))?;
+        
+        let bounds = screens[monitor].display_info;
+        self.capture_monitor_region(monitor, (x - bounds.x) as u32, (y - bounds.y) as u32, width, height)
+    }
+
+    /// Whether window captures go through the backend's own picker instead of looking windows up by title
+    pub fn picks_windows(&self) -> bool {
+        self.backend.picks_windows()
+    }
+
+    /// Capture the window (or screen or area) the user picks in the backend's dialog
+    pub fn capture_picked(&mut self, title: &str) -> Result<()> {
+        let image = self.backend.capture_picked()?;
+        info!("Picked capture: {}x{}", image.width(), image.height());
+        self.set_current_image(image, Some(CaptureSource::Window { title: title.to_string() }));
         Ok(())
     }
 
     /// Capture a specific window by its title
     pub fn capture_window(&mut self, window_title: &str) -> Result<()> {
         info!("Capturing window: {}", window_title);
-        // Get window bounds
-        let window_bounds = window_finder::get_window_bounds(window_title)?;
-        
-        // Capture the region
-        let screens = Screen::all()?;
-        if screens.is_empty() {
-            return Err(anyhow!("No screens found"));
+        if self.backend.picks_windows() {
+            info!("The {} backend can't find '{}' by title; pick it in the dialog", self.backend.name(), window_title);
+            return self.capture_picked(window_title);
         }
+        let cached = self.cached_bounds(window_title);
+        let window_bounds = match cached {
+            Some(bounds) => bounds,
+            None => self.fresh_bounds(window_title)?,
+        };
         
-        // Find appropriate screen
-        let screen = screens.iter().find(|s| {
-            let bounds = s.display_info;
-            window_bounds.x >= bounds.x as i32 &&
-            window_bounds.y >= bounds.y as i32 &&
-            (window_bounds.x + window_bounds.width as i32) <= (bounds.x as i32 + bounds.width as i32) &&
-            (window_bounds.y + window_bounds.height as i32) <= (bounds.y as i32 + bounds.height as i32)
-        }).unwrap_or(&screens[0]);
-        
-        // Calculate the capture region relative to the screen
-        let capture_x = window_bounds.x - screen.display_info.x as i32;
-        let capture_y = window_bounds.y - screen.display_info.y as i32;
-        
-        let image = screen.capture_area(
-            capture_x.max(0) as i32,
-            capture_y.max(0) as i32,
-            window_bounds.width as u32,
-            window_bounds.height as u32
-        )?;
-        
-        // Convert to DynamicImage
-        let width = image.width() as u32;
-        let height = image.height() as u32;
+        let result = self.capture_window_at(window_title, &window_bounds);
+        if result.is_err() && cached.is_some() {
+            // The window may have closed or moved off its monitor since it was cached. A monitor change
+            // would make the other entries just as stale, so start over rather than only replacing this one
+            warn!("Capture with cached bounds failed, looking up '{}' again", window_title);
+            self.invalidate_bounds_cache();
+            let window_bounds = self.fresh_bounds(window_title)?;
+            return self.capture_window_at(window_title, &window_bounds);
+        }
+        result
+    }
+
+    fn capture_window_at(&mut self, window_title: &str, window_bounds: &window_finder::WindowBounds) -> Result<()> {
+        let image = self.grab_window(window_title, window_bounds)?;
+        info!("Window captured: {}x{}", image.width(), image.height());
+        self.set_current_image(image, Some(CaptureSource::Window { title: window_title.to_string() }));
+        Ok(())
+    }
+
+    /// Capture a window, scroll it down and capture again `scroll_steps` times, then stitch the frames into one
+    /// tall image. Best effort: if the frames can't be lined up, only the first one is kept
+    pub fn capture_scrolling_window(&mut self, window_title: &str, scroll_steps: u32) -> Result<()> {
+        info!("Capturing window '{}' over {} scroll steps", window_title, scroll_steps);
+        let window_bounds = self.fresh_bounds(window_title)?;
+        let (center_x, center_y) = (window_bounds.x + window_bounds.width / 2, window_bounds.y + window_bounds.height / 2);
         
-        // Get raw data - the screenshots crate returns BGRA format
-        let buffer = image.as_raw().to_vec();
+        let mut frames = vec![self.grab_window(window_title, &window_bounds)?];
+        for step in 1..=scroll_steps {
+            if let Err(e) = window_finder::scroll_down_at(center_x, center_y) {
+                warn!("Stopping after {} of {} scroll steps: {}", step - 1, scroll_steps, e);
+                break;
+            }
+            std::thread::sleep(SCROLL_SETTLE);
+            frames.push(self.grab_window(window_title, &window_bounds)?);
+        }
         
-        // Convert BGRA to RGBA
-        let mut rgba_buffer = Vec::with_capacity(buffer.len());
-        for chunk in buffer.chunks(4) {
-            if chunk.len() == 4 {
-                rgba_buffer.push(chunk[2]); // R
-                rgba_buffer.push(chunk[1]); // G
-                rgba_buffer.push(chunk[0]); // B
-                rgba_buffer.push(chunk[3]); // A
+        let source = Some(CaptureSource::Window { title: window_title.to_string() });
+        let rgba_frames: Vec<image::RgbaImage> = frames.iter().map(DynamicImage::to_rgba8).collect();
+        match scroll::stitch_frames(&rgba_frames) {
+            Ok(stitched) => {
+                info!("Stitched {} frames into {}x{}", frames.len(), stitched.width(), stitched.height());
+                self.set_current_image(DynamicImage::ImageRgba8(stitched), source);
+            }
+            Err(e) => {
+                warn!("Could not stitch the scrolled frames ({}), keeping a single capture", e);
+                self.set_current_image(frames.swap_remove(0), source);
             }
         }
+        Ok(())
+    }
+
+    /// Capture a window's pixels without making them the current image
+    fn grab_window(&self, window_title: &str, window_bounds: &window_finder::WindowBounds) -> Result<DynamicImage> {
+        let screens = Screen::all()?;
+        let monitors: Vec<CaptureRect> = screens.iter()
+            .map(|s| {
+                let bounds = s.display_info;
+                CaptureRect { x: bounds.x, y: bounds.y, width: bounds.width, height: bounds.height }
+            })
+            .collect();
+        let window = CaptureRect {
+            x: window_bounds.x,
+            y: window_bounds.y,
+            width: window_bounds.width.max(0) as u32,
+            height: window_bounds.height.max(0) as u32,
+        };
+        let pieces = window_pieces(&monitors, &window);
         
-        let rgba = image::RgbaImage::from_raw(width, height, rgba_buffer)
-            .ok_or_else(|| anyhow!("Failed to create image from raw data"))?;
+        let image = match pieces.as_slice() {
+            [] => return Err(anyhow!("Window '{}' is not on any monitor", window_title)),
+            [(monitor, piece)] => {
+                // Capture the region relative to the screen
+                let origin = monitors[*monitor];
+                self.backend.capture_area(*monitor, piece.x - origin.x, piece.y - origin.y, piece.width, piece.height)?
+            }
+            _ => {
+                // The window straddles monitors: capture each one's share and place it on a shared canvas
+                let extent = pieces.iter().skip(1).fold(pieces[0].1, |extent, (_, piece)| extent.union(piece));
+                let mut canvas = image::RgbaImage::new(extent.width, extent.height);
+                for (monitor, piece) in &pieces {
+                    let origin = monitors[*monitor];
+                    let part = self.backend.capture_area(*monitor, piece.x - origin.x, piece.y - origin.y, piece.width, piece.height)?;
+                    image::imageops::overlay(&mut canvas, &part.to_rgba8(), (piece.x - extent.x) as i64, (piece.y - extent.y) as i64);
+                }
+                info!("Window spans {} monitors, stitched their captures", pieces.len());
+                DynamicImage::ImageRgba8(canvas)
+            }
+        };
+        Ok(image)
+    }
+
+    /// Capture the whole monitor a window is on, with a border drawn around the window
+    pub fn capture_window_highlighted(&mut self, window_title: &str) -> Result<()> {
+        info!("Capturing window with highlight: {}", window_title);
+        let window_bounds = window_finder::get_window_bounds(window_title, self.client_area_only)?;
+        let screens = Screen::all()?;
+        let monitor = monitor_for_window(&screens, &window_bounds)?;
+        let origin = screens[monitor].display_info;
         
-        let dynamic_image = DynamicImage::ImageRgba8(rgba);
-        self.current_image = Some(dynamic_image);
+        let mut canvas = self.backend.capture_screen(monitor)?.to_rgba8();
+        let (left, top) = (window_bounds.x - origin.x, window_bounds.y - origin.y);
+        // Grow the border outwards so it doesn't cover the window's own edges
+        for grow in 1..=HIGHLIGHT_THICKNESS {
+            draw_hollow_rect_mut(
+                &mut canvas,
+                Rect::at(left - grow, top - grow)
+                    .of_size((window_bounds.width + 2 * grow).max(1) as u32, (window_bounds.height + 2 * grow).max(1) as u32),
+                HIGHLIGHT_COLOR,
+            );
+        }
+        self.set_current_image(
+            DynamicImage::ImageRgba8(canvas),
+            Some(CaptureSource::Window { title: window_title.to_string() }),
+        );
         
-        info!("Window captured: {}x{}", window_bounds.width, window_bounds.height);
+        info!("Monitor {} captured with window {}x{} highlighted", monitor, window_bounds.width, window_bounds.height);
+        Ok(())
+    }
+
+    /// Undo a monitor's rotation when the backend returned the capture in the panel's native orientation.
+    /// Only a mismatch between the capture's and the monitor's aspect can be detected, so 180° is left alone
+    fn orient(&self, image: DynamicImage, monitor: usize) -> DynamicImage {
+        if !self.auto_orient {
+            return image;
+        }
+        let info = match Screen::all().ok().and_then(|screens| screens.get(monitor).map(|s| s.display_info)) {
+            Some(info) => info,
+            None => return image,
+        };
+        let monitor_is_portrait = info.height > info.width;
+        let image_is_portrait = image.height() > image.width();
+        match Rotation::from_degrees(info.rotation) {
+            Some(rotation @ (Rotation::Deg90 | Rotation::Deg270)) if monitor_is_portrait != image_is_portrait => {
+                info!("Monitor {} is rotated {}°, correcting the capture's orientation", monitor, info.rotation);
+                rotate_image(&image, rotation)
+            }
+            _ => image,
+        }
+    }
+
+    /// Rotate the current image clockwise in place
+    pub fn rotate_current_image(&mut self, rotation: Rotation) -> Result<()> {
+        let image = self.current_image.as_mut().ok_or_else(|| anyhow!("No image available"))?;
+        *image = rotate_image(image, rotation);
+        Ok(())
+    }
+
+    /// Mirror the current image in place
+    pub fn flip_current_image(&mut self, flip: Flip) -> Result<()> {
+        let image = self.current_image.as_mut().ok_or_else(|| anyhow!("No image available"))?;
+        *image = match flip {
+            Flip::Horizontal => image.fliph(),
+            Flip::Vertical => image.flipv(),
+        };
+        Ok(())
+    }
+
+    /// Resize the current image to exactly `width`x`height`, so captures from different monitors come out alike
+    pub fn resize_current_image(&mut self, width: u32, height: u32, fit: Fit) -> Result<()> {
+        if width == 0 || height == 0 {
+            return Err(anyhow!("Output size must be positive, got {}x{}", width, height));
+        }
+        let image = self.current_image.as_mut().ok_or_else(|| anyhow!("No image available"))?;
+        let (scale_x, scale_y) = (width as f32 / image.width() as f32, height as f32 / image.height() as f32);
+        let upscale = match fit {
+            Fit::Contain => scale_x.min(scale_y),
+            Fit::Cover | Fit::Stretch => scale_x.max(scale_y),
+        };
+        if upscale > EXTREME_UPSCALE {
+            warn!("Upscaling the {}x{} capture {:.1}x to fit {}x{}; it will look blurry", image.width(), image.height(), upscale, width, height);
+        }
+        *image = fit_image(image, width, height, fit);
+        info!("Resized capture to {}x{}", width, height);
+        Ok(())
+    }
+
+    /// Crop the current image to a selection, keeping the whole one for `uncrop`. The selection is clipped
+    /// to the image, so one larger than a small window keeps all of it. Cropping again narrows the crop further
+    pub fn crop_current_image(&mut self, x: u32, y: u32, width: u32, height: u32) -> Result<()> {
+        let image = self.current_image.as_ref().ok_or_else(|| anyhow!("No image available"))?;
+        let bounds = CaptureRect { x: 0, y: 0, width: image.width(), height: image.height() };
+        let selection = CaptureRect { x: x as i32, y: y as i32, width, height };
+        let rect = selection.intersect(&bounds).ok_or_else(|| anyhow!("The selection is outside the capture"))?;
+        if rect.width < MIN_ANALYSIS_DIMENSION || rect.height < MIN_ANALYSIS_DIMENSION {
+            return Err(anyhow!(
+                "The selection is too small to crop to ({}x{} px, at least {} px each way)",
+                rect.width, rect.height, MIN_ANALYSIS_DIMENSION
+            ));
+        }
+        if rect == bounds {
+            info!("The selection covers the whole capture; nothing to crop");
+            return Ok(());
+        }
+        let cropped = image.crop_imm(rect.x as u32, rect.y as u32, rect.width, rect.height);
+        let previous = self.current_image.replace(cropped);
+        self.uncropped = match (self.uncropped.take(), previous) {
+            // Already cropped: keep the original whole image, with the new crop offset into it
+            (Some((whole, outer)), _) => Some((whole, CaptureRect { x: outer.x + rect.x, y: outer.y + rect.y, ..rect })),
+            (None, Some(whole)) => Some((whole, rect)),
+            (None, None) => None,
+        };
+        Ok(())
+    }
+
+    /// Where the current image was cropped from the whole capture, if it is a crop
+    pub fn crop_rect(&self) -> Option<CaptureRect> {
+        self.uncropped.as_ref().map(|(_, rect)| *rect)
+    }
+
+    /// Put back the whole image from before `crop_current_image`
+    pub fn uncrop(&mut self) -> Result<()> {
+        let (whole, _) = self.uncropped.take().ok_or_else(|| anyhow!("The current image isn't cropped"))?;
+        self.current_image = Some(whole);
         Ok(())
     }
 
@@ -123,15 +981,107 @@ impl ScreenshotManager {
         self.current_image.as_ref()
     }
 
+    /// What the current image was captured from, if known
+    pub fn current_capture_source(&self) -> Option<&CaptureSource> {
+        self.current_source.as_ref()
+    }
+
+    /// Replace the current image, moving the previous one into the history.
+    /// `source` is None for images that didn't come from a capture, e.g. a paste
+    pub fn set_current_image(&mut self, image: DynamicImage, source: Option<CaptureSource>) {
+        self.current_source = source;
+        self.uncropped = None;
+        self.raw_bgra = None;
+        if let Some(previous) = self.current_image.replace(image) {
+            if self.history.len() == MAX_HISTORY {
+                self.history.pop_front();
+            }
+            self.history.push_back(previous);
+        }
+    }
+
+    /// Get the previous images, oldest first
+    pub fn history(&self) -> &VecDeque<DynamicImage> {
+        &self.history
+    }
+
     /// Get the current image as raw bytes
     pub fn get_current_image_data(&self) -> Result<Vec<u8>> {
         if let Some(image) = &self.current_image {
-            let mut buffer = Vec::new();
-            let mut cursor = Cursor::new(&mut buffer);
-            image.write_to(&mut cursor, image::ImageOutputFormat::Png)?;
-            Ok(buffer)
+            encode_png(image)
         } else {
             Err(anyhow!("No image available"))
         }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: i32, y: i32, width: u32, height: u32) -> CaptureRect {
+        CaptureRect { x, y, width, height }
+    }
+
+    #[test]
+    fn saving_into_a_missing_nested_folder_needs_mkdir() {
+        let root = std::env::temp_dir().join(format!("screensnap-mkdir-{}", std::process::id()));
+        let path = root.join("shots").join("today").join("capture.png");
+        let image = DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(4, 4, Rgba([10, 20, 30, 255])));
+
+        let missing = save_image(&image, &path, PngCompression::Fast).unwrap_err();
+        assert!(missing.to_string().contains("doesn't exist"), "{}", missing);
+        assert!(prepare_output_dir(&path, false).is_err());
+
+        prepare_output_dir(&path, true).unwrap();
+        save_image(&image, &path, PngCompression::Fast).unwrap();
+        assert_eq!(image::open(&path).unwrap().dimensions(), (4, 4));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn all_black_capture_is_blank() {
+        let image = DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(320, 200, Rgba([0, 0, 0, 255])));
+        assert!(is_probably_blank(&image));
+    }
+
+    #[test]
+    fn uniform_colored_capture_is_blank() {
+        let image = DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(320, 200, Rgba([200, 60, 90, 255])));
+        assert!(is_probably_blank(&image));
+    }
+
+    #[test]
+    fn noisy_capture_is_not_blank() {
+        let image = DynamicImage::ImageRgba8(image::RgbaImage::from_fn(320, 200, |x, y| {
+            let value = ((x * 37 + y * 101) % 256) as u8;
+            Rgba([value, value.wrapping_mul(3), value.wrapping_add(90), 255])
+        }));
+        assert!(!is_probably_blank(&image));
+    }
+
+    #[test]
+    fn window_across_two_monitors_splits_at_the_seam() {
+        // A monitor left of the primary one has a negative origin
+        let monitors = [rect(-1920, 0, 1920, 1080), rect(0, 0, 2560, 1440)];
+        let pieces = window_pieces(&monitors, &rect(-500, 100, 1000, 600));
+        assert_eq!(pieces, vec![(0, rect(-500, 100, 500, 600)), (1, rect(0, 100, 500, 600))]);
+
+        // The pieces meet at the seam, and each lands at its offset on the window's canvas
+        let (left, right) = (pieces[0].1, pieces[1].1);
+        assert_eq!(left.x + left.width as i32, right.x);
+        let extent = left.union(&right);
+        assert_eq!(extent, rect(-500, 100, 1000, 600));
+        assert_eq!((left.x - extent.x, right.x - extent.x), (0, 500));
+        // Relative to its own monitor, the left piece starts 1420 px in
+        assert_eq!(left.x - monitors[0].x, 1420);
+    }
+
+    #[test]
+    fn window_across_stacked_monitors_is_clipped_to_the_desktop() {
+        let monitors = [rect(0, -1080, 1920, 1080), rect(0, 0, 1920, 1080)];
+        let pieces = window_pieces(&monitors, &rect(1800, -200, 400, 500));
+        assert_eq!(pieces, vec![(0, rect(1800, -200, 120, 200)), (1, rect(1800, 0, 120, 300))]);
+        assert!(window_pieces(&monitors, &rect(3000, 0, 100, 100)).is_empty());
+    }
 }
\ No newline at end of file
@@ -0,0 +1,187 @@
+// src/capture/scroll.rs
+use anyhow::{Result, anyhow};
+use image::RgbaImage;
+use std::ops::Range;
+
+/// Rows from the upper part of a frame that are looked up in the previous frame
+const MATCH_BAND_ROWS: usize = 48;
+
+/// Every nth pixel of a row goes into its signature
+const SIGNATURE_STEP: usize = 4;
+
+/// Largest mean luma difference per sample still treated as the same rows
+const MAX_MATCH_ERROR: f64 = 3.0;
+
+/// A band with less luma range than this (blank space) would match anywhere, so it isn't trusted
+const MIN_BAND_CONTRAST: u8 = 16;
+
+/// Downsampled luma of every row, which is all the overlap search looks at
+fn row_signatures(frame: &RgbaImage) -> Vec<Vec<u8>> {
+    frame.rows()
+        .map(|row| {
+            row.step_by(SIGNATURE_STEP)
+                .map(|p| ((p[0] as u32 * 299 + p[1] as u32 * 587 + p[2] as u32 * 114) / 1000) as u8)
+                .collect()
+        })
+        .collect()
+}
+
+/// Mean difference per sample between two runs of rows
+fn rows_error(a: &[Vec<u8>], b: &[Vec<u8>]) -> f64 {
+    let mut total = 0u64;
+    let mut samples = 0u64;
+    for (row_a, row_b) in a.iter().zip(b) {
+        for (x, y) in row_a.iter().zip(row_b) {
+            total += x.abs_diff(*y) as u64;
+            samples += 1;
+        }
+    }
+    if samples == 0 { 0.0 } else { total as f64 / samples as f64 }
+}
+
+/// How many rows the content moved up between two frames, found by looking up a band from a quarter of the
+/// way down `next` in `prev` (below most toolbars, and high enough to find scrolls of over half the height). Some(0) means nothing moved (the end of the page), None that no position matched
+fn scroll_distance(prev: &[Vec<u8>], next: &[Vec<u8>]) -> Option<usize> {
+    let height = next.len();
+    let band = MATCH_BAND_ROWS.min(height / 4);
+    if band == 0 || prev.len() != height {
+        return None;
+    }
+    let start = height / 4;
+    let needle = &next[start..start + band];
+    let (low, high) = needle.iter().flatten().fold((u8::MAX, u8::MIN), |(low, high), &v| (low.min(v), high.max(v)));
+    if high.saturating_sub(low) < MIN_BAND_CONTRAST {
+        return None;
+    }
+
+    // next[start + r] shows what prev[start + r + distance] showed; the first best match wins ties
+    (0..=height - band - start)
+        .map(|distance| (distance, rows_error(&prev[start + distance..start + distance + band], needle)))
+        .filter(|(_, error)| *error <= MAX_MATCH_ERROR)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(distance, _)| distance)
+}
+
+/// Rows at the bottom that stayed put between two frames, such as a status bar or an input box
+fn static_footer_rows(prev: &[Vec<u8>], next: &[Vec<u8>]) -> usize {
+    prev.iter().rev().zip(next.iter().rev())
+        .take(next.len() / 3)
+        .take_while(|(a, b)| rows_error(std::slice::from_ref(*a), std::slice::from_ref(*b)) <= MAX_MATCH_ERROR)
+        .count()
+}
+
+/// Which rows of which frame make up the tall image, top to bottom: the first frame without its footer,
+/// the rows each later frame scrolled into view, then the footer once
+fn plan_stitch(signatures: &[Vec<Vec<u8>>]) -> Result<Vec<(usize, Range<usize>)>> {
+    let height = signatures.first().map(Vec::len).ok_or_else(|| anyhow!("No frames to stitch"))?;
+    let mut footer = None;
+    let mut pieces = Vec::new();
+    let mut last_frame = 0;
+    for (index, pair) in signatures.windows(2).enumerate() {
+        let distance = scroll_distance(&pair[0], &pair[1])
+            .ok_or_else(|| anyhow!("Couldn't line up frame {} with the one before it", index + 2))?;
+        if distance == 0 {
+            // The window stopped scrolling: the rest of the frames are the same
+            break;
+        }
+        let footer = *footer.get_or_insert_with(|| static_footer_rows(&pair[0], &pair[1]));
+        let end = height - footer;
+        if distance > end {
+            return Err(anyhow!("Frame {} scrolled further than the window is tall", index + 2));
+        }
+        if pieces.is_empty() {
+            pieces.push((0, 0..end));
+        }
+        pieces.push((index + 1, end - distance..end));
+        last_frame = index + 1;
+    }
+    match footer {
+        Some(footer) => pieces.push((last_frame, height - footer..height)),
+        None => return Err(anyhow!("The window didn't scroll")),
+    }
+    Ok(pieces)
+}
+
+/// Stitch same-sized frames of a window scrolled down between captures into one tall image
+pub fn stitch_frames(frames: &[RgbaImage]) -> Result<RgbaImage> {
+    let width = frames.first().map(RgbaImage::width).ok_or_else(|| anyhow!("No frames to stitch"))?;
+    if frames.iter().any(|frame| frame.dimensions() != frames[0].dimensions()) {
+        return Err(anyhow!("The window changed size while scrolling"));
+    }
+    let signatures: Vec<Vec<Vec<u8>>> = frames.iter().map(row_signatures).collect();
+    let pieces = plan_stitch(&signatures)?;
+
+    let height: usize = pieces.iter().map(|(_, rows)| rows.len()).sum();
+    let mut stitched = RgbaImage::new(width, height as u32);
+    let mut y = 0;
+    for (frame, rows) in pieces {
+        for row in rows {
+            for x in 0..width {
+                stitched.put_pixel(x, y, *frames[frame].get_pixel(x, row as u32));
+            }
+            y += 1;
+        }
+    }
+    Ok(stitched)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Row `r` of a made-up page: 16 noisy samples, so no two rows look alike
+    fn page_row(r: usize) -> Vec<u8> {
+        (0..16).map(|c| {
+            let mut x = ((r * 16 + c) as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+            x ^= x >> 29;
+            (x >> 56) as u8
+        }).collect()
+    }
+
+    /// Signatures of a `height`-row window showing the page from row `top`
+    fn window(top: usize, height: usize) -> Vec<Vec<u8>> {
+        (top..top + height).map(page_row).collect()
+    }
+
+    #[test]
+    fn shifted_content_gives_the_distance() {
+        assert_eq!(scroll_distance(&window(0, 120), &window(10, 120)), Some(10));
+        assert_eq!(scroll_distance(&window(5, 120), &window(45, 120)), Some(40));
+    }
+
+    #[test]
+    fn frames_that_did_not_scroll_stop_the_stitch() {
+        assert_eq!(scroll_distance(&window(0, 120), &window(0, 120)), Some(0));
+        let pieces = plan_stitch(&[window(0, 120), window(10, 120), window(10, 120), window(20, 120)]).unwrap();
+        // Only the first scroll is used; the frame after the stalled one is ignored
+        assert_eq!(pieces, vec![(0, 0..120), (1, 110..120), (1, 120..120)]);
+        assert!(plan_stitch(&[window(0, 120), window(0, 120)]).is_err());
+    }
+
+    #[test]
+    fn low_contrast_band_is_not_matched() {
+        let blank: Vec<Vec<u8>> = (0..120).map(|r| vec![200 + (r % 3) as u8; 16]).collect();
+        assert_eq!(scroll_distance(&blank, &blank), None);
+    }
+
+    #[test]
+    fn static_footer_is_kept_once() {
+        let footer: Vec<Vec<u8>> = (0..20).map(|r| page_row(10_000 + r)).collect();
+        let with_footer = |top: usize| {
+            let mut rows = window(top, 100);
+            rows.extend(footer.iter().cloned());
+            rows
+        };
+        let frames = [with_footer(0), with_footer(15), with_footer(30)];
+        assert_eq!(static_footer_rows(&frames[0], &frames[1]), 20);
+
+        let pieces = plan_stitch(&frames).unwrap();
+        assert_eq!(pieces, vec![(0, 0..100), (1, 85..100), (2, 85..100), (2, 100..120)]);
+        let stitched: Vec<Vec<u8>> = pieces.iter()
+            .flat_map(|(frame, rows)| frames[*frame][rows.clone()].to_vec())
+            .collect();
+        let mut expected = window(0, 130);
+        expected.extend(footer);
+        assert_eq!(stitched, expected);
+    }
+}
\ No newline at end of file
@@ -1,7 +1,10 @@
 // src/capture/window_finder.rs
 use anyhow::{Result, anyhow};
 use log::info;
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+use log::warn;
 
+#[derive(Clone, Copy, Debug)]
 pub struct WindowBounds {
     pub x: i32,
     pub y: i32,
@@ -61,15 +64,93 @@ unsafe extern "system" fn enum_window_proc(
     TRUE
 }
 
+/// Wait before the one retry of a window-listing command that failed
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+const ENUMERATION_RETRY_DELAY: std::time::Duration = std::time::Duration::from_millis(300);
+
+/// Shown when macOS hasn't granted the permissions osascript needs to list windows
+#[cfg(target_os = "macos")]
+const MACOS_PERMISSION_MESSAGE: &str = "Listing windows needs permission. Open System Settings > Privacy & Security, \
+allow your terminal (or screensnap) under Accessibility and Automation > System Events, then try again.";
+
+/// Run a window-listing command, retrying once after a short delay if it can't start or exits with an error.
+/// The last attempt's output is returned as is, so callers still check its status
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn output_with_retry(program: &str, args: &[&str]) -> Result<std::process::Output> {
+    let run = || std::process::Command::new(program).args(args).output();
+    match run() {
+        Ok(output) if output.status.success() => Ok(output),
+        first => {
+            match &first {
+                Ok(output) => warn!("{} exited with {}, retrying", program, output.status),
+                Err(e) => warn!("Could not run {} ({}), retrying", program, e),
+            }
+            std::thread::sleep(ENUMERATION_RETRY_DELAY);
+            run().map_err(|e| anyhow!("Could not run {}: {}", program, e))
+        }
+    }
+}
+
 #[cfg(target_os = "linux")]
 pub fn get_window_titles() -> Result<Vec<String>> {
     info!("Finding window titles on Linux");
     
+    // wmctrl lists one managed window per line; xwininfo is the fallback
+    if wmctrl_available() {
+        match get_window_titles_wmctrl() {
+            Ok(titles) => return Ok(titles),
+            Err(e) => warn!("wmctrl failed, falling back to xwininfo: {}", e),
+        }
+    }
+    get_window_titles_xwininfo()
+}
+
+/// Whether wmctrl is installed, probed once per run
+#[cfg(target_os = "linux")]
+fn wmctrl_available() -> bool {
+    static AVAILABLE: std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+    *AVAILABLE.get_or_init(|| {
+        let available = std::process::Command::new("wmctrl")
+            .arg("--version")
+            .output()
+            .is_ok();
+        info!("wmctrl {}", if available { "found" } else { "not installed, using xwininfo" });
+        available
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn get_window_titles_wmctrl() -> Result<Vec<String>> {
+    let output = output_with_retry("wmctrl", &["-l"])?;
+    if !output.status.success() {
+        return Err(anyhow!("wmctrl -l exited with {}", output.status));
+    }
+    
+    // Each line is "<id> <desktop> <host> <title>"; the title may contain spaces
+    let stdout = String::from_utf8(output.stdout)?;
+    let titles = stdout
+        .lines()
+        .filter_map(|line| {
+            let mut rest = line.trim_start();
+            for _ in 0..3 {
+                let end = rest.find(char::is_whitespace)?;
+                rest = rest[end..].trim_start();
+            }
+            let title = rest.trim_end();
+            if title.is_empty() { None } else { Some(title.to_string()) }
+        })
+        .collect();
+    
+    Ok(titles)
+}
+
+#[cfg(target_os = "linux")]
+fn get_window_titles_xwininfo() -> Result<Vec<String>> {
     // Use the command-line tool to get window list
-    let output = std::process::Command::new("xwininfo")
-        .arg("-root")
-        .arg("-tree")
-        .output()?;
+    let output = output_with_retry("xwininfo", &["-root", "-tree"])?;
+    if !output.status.success() {
+        return Err(anyhow!("xwininfo exited with {}: {}", output.status, String::from_utf8_lossy(&output.stderr).trim()));
+    }
     
     let stdout = String::from_utf8(output.stdout)?;
     let titles: Vec<String> = stdout
@@ -99,10 +180,18 @@ pub fn get_window_titles() -> Result<Vec<String>> {
     info!("Finding window titles on macOS");
     
     // Use a command-line utility to get window list on macOS
-    let output = std::process::Command::new("osascript")
-        .arg("-e")
-        .arg("tell application \"System Events\" to get name of every window of every process")
-        .output()?;
+    let output = output_with_retry(
+        "osascript",
+        &["-e", "tell application \"System Events\" to get name of every window of every process"],
+    )?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        // -1719/-25211: no Accessibility access; -1743: not allowed to automate System Events
+        if ["-1719", "-25211", "-1743", "assistive access"].iter().any(|code| stderr.contains(code)) {
+            return Err(anyhow!(MACOS_PERMISSION_MESSAGE));
+        }
+        return Err(anyhow!("osascript exited with {}: {}", output.status, stderr.trim()));
+    }
     
     let stdout = String::from_utf8(output.stdout)?;
     let titles = stdout
@@ -115,21 +204,29 @@ pub fn get_window_titles() -> Result<Vec<String>> {
 }
 
 #[cfg(target_os = "windows")]
-pub fn get_window_bounds(window_title: &str) -> Result<WindowBounds> {
+struct FindData {
+    title: String,
+    client_area_only: bool,
+    bounds: Option<WindowBounds>,
+}
+
+/// Get a window's bounds in screen coordinates, optionally just its client area (no title bar or borders).
+/// All platforms return the whole window including its frame by default:
+/// - Windows: DWM extended frame bounds (the visible frame, without the invisible resize border), or the client rect
+/// - Linux: xwininfo's client geometry grown by the window manager's `_NET_FRAME_EXTENTS`, or the client geometry as is
+/// - macOS: System Events position and size, which always include the title bar
+#[cfg(target_os = "windows")]
+pub fn get_window_bounds(window_title: &str, client_area_only: bool) -> Result<WindowBounds> {
     use windows::{
-        Win32::Foundation::{BOOL, HWND, LPARAM, RECT},
-        Win32::UI::WindowsAndMessaging::{EnumWindows, GetWindowRect, GetWindowTextW},
+        Win32::Foundation::LPARAM,
+        Win32::UI::WindowsAndMessaging::EnumWindows,
     };
     
     info!("Getting window bounds for: {}", window_title);
     
-    struct FindData {
-        title: String,
-        bounds: Option<WindowBounds>,
-    }
-    
     let mut find_data = FindData {
         title: window_title.to_string(),
+        client_area_only,
         bounds: None,
     };
     
@@ -149,8 +246,8 @@ unsafe extern "system" fn find_window_proc(
     lparam: windows::Win32::Foundation::LPARAM,
 ) -> windows::Win32::Foundation::BOOL {
     use windows::{
-        Win32::Foundation::{FALSE, RECT, TRUE},
-        Win32::UI::WindowsAndMessaging::{GetWindowTextLengthW, GetWindowTextW, GetWindowRect, IsWindowVisible},
+        Win32::Foundation::{FALSE, TRUE},
+        Win32::UI::WindowsAndMessaging::{GetWindowTextLengthW, GetWindowTextW, IsWindowVisible},
     };
     
     if IsWindowVisible(hwnd).as_bool() {
@@ -164,8 +261,7 @@ unsafe extern "system" fn find_window_proc(
                 
                 let find_data = &mut *(lparam.0 as *mut FindData);
                 if title == find_data.title {
-                    let mut rect = RECT::default();
-                    if GetWindowRect(hwnd, &mut rect).is_ok() {
+                    if let Some(rect) = window_rect(hwnd, find_data.client_area_only) {
                         find_data.bounds = Some(WindowBounds {
                             x: rect.left,
                             y: rect.top,
@@ -182,15 +278,59 @@ unsafe extern "system" fn find_window_proc(
     TRUE
 }
 
+#[cfg(target_os = "windows")]
+unsafe fn window_rect(hwnd: windows::Win32::Foundation::HWND, client_area_only: bool) -> Option<windows::Win32::Foundation::RECT> {
+    use windows::{
+        Win32::Foundation::{POINT, RECT},
+        Win32::Graphics::Dwm::{DwmGetWindowAttribute, DWMWA_EXTENDED_FRAME_BOUNDS},
+        Win32::Graphics::Gdi::ClientToScreen,
+        Win32::UI::WindowsAndMessaging::{GetClientRect, GetWindowRect},
+    };
+    
+    let mut rect = RECT::default();
+    if client_area_only {
+        // The client rect is relative to the client origin, so map it to screen coordinates
+        if !GetClientRect(hwnd, &mut rect).as_bool() {
+            return None;
+        }
+        let mut origin = POINT::default();
+        if !ClientToScreen(hwnd, &mut origin).as_bool() {
+            return None;
+        }
+        return Some(RECT {
+            left: origin.x,
+            top: origin.y,
+            right: origin.x + rect.right,
+            bottom: origin.y + rect.bottom,
+        });
+    }
+    
+    // On Windows 10/11 GetWindowRect includes an invisible resize border; DWM knows the visible frame
+    let dwm_result = DwmGetWindowAttribute(
+        hwnd,
+        DWMWA_EXTENDED_FRAME_BOUNDS,
+        &mut rect as *mut RECT as *mut std::ffi::c_void,
+        std::mem::size_of::<RECT>() as u32,
+    );
+    if dwm_result.is_ok() || GetWindowRect(hwnd, &mut rect).as_bool() {
+        Some(rect)
+    } else {
+        None
+    }
+}
+
 #[cfg(target_os = "linux")]
-pub fn get_window_bounds(window_title: &str) -> Result<WindowBounds> {
+pub fn get_window_bounds(window_title: &str, client_area_only: bool) -> Result<WindowBounds> {
     info!("Getting window bounds for: {}", window_title);
     
-    // Use xwininfo to get window bounds
+    // xwininfo reports the client window; a reparenting window manager draws its frame around it
     let output = std::process::Command::new("xwininfo")
         .arg("-name")
         .arg(window_title)
         .output()?;
+    if !output.status.success() {
+        return Err(anyhow!("Window not found: {} ({})", window_title, String::from_utf8_lossy(&output.stderr).trim()));
+    }
     
     let stdout = String::from_utf8(output.stdout)?;
     
@@ -224,12 +364,56 @@ pub fn get_window_bounds(window_title: &str) -> Result<WindowBounds> {
         return Err(anyhow!("Window not found or has invalid dimensions: {}", window_title));
     }
     
-    Ok(WindowBounds { x, y, width, height })
+    if client_area_only {
+        return Ok(WindowBounds { x, y, width, height });
+    }
+    match get_frame_extents(window_title) {
+        Some((left, right, top, bottom)) => {
+            info!("Adding window frame: left {}, right {}, top {}, bottom {}", left, right, top, bottom);
+            Ok(WindowBounds {
+                x: x - left,
+                y: y - top,
+                width: width + left + right,
+                height: height + top + bottom,
+            })
+        }
+        None => Ok(WindowBounds { x, y, width, height }),
+    }
+}
+
+/// The window manager's frame size around a window as (left, right, top, bottom), from `_NET_FRAME_EXTENTS`.
+/// None when xprop is missing or the window manager doesn't set the property (e.g. client-side decorations)
+#[cfg(target_os = "linux")]
+fn get_frame_extents(window_title: &str) -> Option<(i32, i32, i32, i32)> {
+    let output = std::process::Command::new("xprop")
+        .arg("-name")
+        .arg(window_title)
+        .arg("_NET_FRAME_EXTENTS")
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    
+    // e.g. "_NET_FRAME_EXTENTS(CARDINAL) = 1, 1, 37, 1"
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (_, values) = stdout.split_once('=')?;
+    let extents: Vec<i32> = values.split(',')
+        .map(|v| v.trim().parse::<i32>())
+        .collect::<std::result::Result<_, _>>()
+        .ok()?;
+    match extents.as_slice() {
+        [left, right, top, bottom] => Some((*left, *right, *top, *bottom)),
+        _ => None,
+    }
 }
 
 #[cfg(target_os = "macos")]
-pub fn get_window_bounds(window_title: &str) -> Result<WindowBounds> {
+pub fn get_window_bounds(window_title: &str, client_area_only: bool) -> Result<WindowBounds> {
     info!("Getting window bounds for: {}", window_title);
+    if client_area_only {
+        warn!("Client-area-only capture isn't supported on macOS; capturing the whole window");
+    }
     
     // AppleScript to get window bounds
     let script = format!(
@@ -269,4 +453,179 @@ pub fn get_window_bounds(window_title: &str) -> Result<WindowBounds> {
         width: values[2],
         height: values[3],
     })
+}
+
+#[cfg(target_os = "windows")]
+pub fn get_cursor_position() -> Result<(i32, i32)> {
+    use windows::{
+        Win32::Foundation::POINT,
+        Win32::UI::WindowsAndMessaging::GetCursorPos,
+    };
+    
+    let mut point = POINT::default();
+    unsafe {
+        GetCursorPos(&mut point).ok()?;
+    }
+    Ok((point.x, point.y))
+}
+
+#[cfg(target_os = "linux")]
+pub fn get_cursor_position() -> Result<(i32, i32)> {
+    // xdotool prints X=..., Y=... lines with --shell
+    let output = std::process::Command::new("xdotool")
+        .args(["getmouselocation", "--shell"])
+        .output()
+        .map_err(|e| anyhow!("Cursor polling needs xdotool ({})", e))?;
+    if !output.status.success() {
+        return Err(anyhow!("xdotool getmouselocation failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    
+    let stdout = String::from_utf8(output.stdout)?;
+    let mut x = None;
+    let mut y = None;
+    for line in stdout.lines() {
+        if let Some(val) = line.strip_prefix("X=") {
+            x = val.trim().parse::<i32>().ok();
+        } else if let Some(val) = line.strip_prefix("Y=") {
+            y = val.trim().parse::<i32>().ok();
+        }
+    }
+    
+    match (x, y) {
+        (Some(x), Some(y)) => Ok((x, y)),
+        _ => Err(anyhow!("Could not parse xdotool output: {}", stdout.trim())),
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub fn get_cursor_position() -> Result<(i32, i32)> {
+    Err(anyhow!("Cursor polling is not available on this platform"))
+}
+
+/// The part of a monitor not covered by the taskbar, from the monitor info Windows keeps for it.
+/// Unlike SPI_GETWORKAREA, which only knows the primary monitor, this works for any monitor
+#[cfg(target_os = "windows")]
+pub fn get_work_area(monitor: &WindowBounds) -> Result<WindowBounds> {
+    use windows::{
+        Win32::Foundation::POINT,
+        Win32::Graphics::Gdi::{GetMonitorInfoW, MonitorFromPoint, MONITORINFO, MONITOR_DEFAULTTONEAREST},
+    };
+    
+    let center = POINT { x: monitor.x + monitor.width / 2, y: monitor.y + monitor.height / 2 };
+    let mut info = MONITORINFO { cbSize: std::mem::size_of::<MONITORINFO>() as u32, ..Default::default() };
+    unsafe {
+        let handle = MonitorFromPoint(center, MONITOR_DEFAULTTONEAREST);
+        if !GetMonitorInfoW(handle, &mut info).as_bool() {
+            return Err(anyhow!("GetMonitorInfoW failed: {}", windows::core::Error::from_win32()));
+        }
+    }
+    let work = info.rcWork;
+    Ok(WindowBounds { x: work.left, y: work.top, width: work.right - work.left, height: work.bottom - work.top })
+}
+
+/// The part of a monitor not covered by panels, from the window manager's `_NET_WORKAREA`.
+/// That is one rectangle for the whole desktop, so it is only exact for panels on the outer edges
+#[cfg(target_os = "linux")]
+pub fn get_work_area(monitor: &WindowBounds) -> Result<WindowBounds> {
+    let output = std::process::Command::new("xprop")
+        .args(["-root", "_NET_WORKAREA"])
+        .output()
+        .map_err(|e| anyhow!("Reading the work area needs xprop ({})", e))?;
+    if !output.status.success() {
+        return Err(anyhow!("xprop failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    
+    // _NET_WORKAREA(CARDINAL) = x, y, width, height[, ... one set per virtual desktop]
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let values: Vec<i32> = stdout.split_once('=')
+        .map(|(_, values)| values.split(',').filter_map(|value| value.trim().parse().ok()).collect())
+        .unwrap_or_default();
+    if values.len() < 4 {
+        return Err(anyhow!("The window manager doesn't report a work area: {}", stdout.trim()));
+    }
+    
+    let left = values[0].max(monitor.x);
+    let top = values[1].max(monitor.y);
+    let right = (values[0] + values[2]).min(monitor.x + monitor.width);
+    let bottom = (values[1] + values[3]).min(monitor.y + monitor.height);
+    if right <= left || bottom <= top {
+        return Err(anyhow!("The work area doesn't overlap this monitor"));
+    }
+    Ok(WindowBounds { x: left, y: top, width: right - left, height: bottom - top })
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub fn get_work_area(_monitor: &WindowBounds) -> Result<WindowBounds> {
+    Err(anyhow!("Reading the work area is not available on this platform"))
+}
+
+/// Opt the process into per-monitor (v2) DPI awareness. Without it, Windows hands a DPI-unaware
+/// process virtualized window rectangles on scaled displays (125%, 150%, ...), which don't line up
+/// with the physical pixels the capture backend returns, so window captures come out offset and cropped.
+/// Must run before any window is created; harmless if awareness was already set (e.g. by a manifest).
+#[cfg(target_os = "windows")]
+pub fn enable_dpi_awareness() {
+    use windows::Win32::UI::HiDpi::{SetProcessDpiAwarenessContext, DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2};
+    
+    let enabled = unsafe { SetProcessDpiAwarenessContext(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2) };
+    if enabled.as_bool() {
+        info!("Enabled per-monitor DPI awareness");
+    } else {
+        info!("Per-monitor DPI awareness not changed: {}", windows::core::Error::from_win32());
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn enable_dpi_awareness() {}
+
+/// Mouse wheel notches sent per scroll step
+const SCROLL_NOTCHES: i32 = 3;
+
+/// Move the cursor to a point and scroll whatever is under it down a few notches. The cursor is left there
+#[cfg(target_os = "windows")]
+pub fn scroll_down_at(x: i32, y: i32) -> Result<()> {
+    use windows::{
+        Win32::UI::Input::KeyboardAndMouse::{SendInput, INPUT, INPUT_0, INPUT_MOUSE, MOUSEEVENTF_WHEEL, MOUSEINPUT},
+        Win32::UI::WindowsAndMessaging::{SetCursorPos, WHEEL_DELTA},
+    };
+    
+    let input = INPUT {
+        r#type: INPUT_MOUSE,
+        Anonymous: INPUT_0 {
+            mi: MOUSEINPUT {
+                dx: 0,
+                dy: 0,
+                // Negative wheel data scrolls down
+                mouseData: (-(WHEEL_DELTA as i32) * SCROLL_NOTCHES) as _,
+                dwFlags: MOUSEEVENTF_WHEEL,
+                time: 0,
+                dwExtraInfo: 0,
+            },
+        },
+    };
+    unsafe {
+        SetCursorPos(x, y).ok()?;
+        if SendInput(&[input], std::mem::size_of::<INPUT>() as i32) != 1 {
+            return Err(anyhow!("SendInput could not send the scroll"));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+pub fn scroll_down_at(x: i32, y: i32) -> Result<()> {
+    // Button 5 is the wheel scrolling down
+    let status = std::process::Command::new("xdotool")
+        .args(["mousemove", &x.to_string(), &y.to_string(), "click", "--repeat", &SCROLL_NOTCHES.to_string(), "5"])
+        .status()
+        .map_err(|e| anyhow!("Scrolling needs xdotool ({})", e))?;
+    if !status.success() {
+        return Err(anyhow!("xdotool could not scroll"));
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub fn scroll_down_at(_x: i32, _y: i32) -> Result<()> {
+    Err(anyhow!("Scrolling windows is not available on this platform"))
 }
\ No newline at end of file
@@ -0,0 +1,100 @@
+// src/clipboard.rs
+#[cfg(feature = "clipboard")]
+use anyhow::{Result, anyhow};
+#[cfg(feature = "clipboard")]
+use image::DynamicImage;
+#[cfg(feature = "clipboard")]
+use log::{info, warn};
+use serde::{Serialize, Deserialize};
+
+/// How an image is put on the clipboard
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ClipboardFormat {
+    /// Raw pixels, understood by most apps
+    #[default]
+    Rgba,
+    /// PNG-encoded bytes typed image/png, for apps that paste raw pixels as a solid block or not at all
+    Png,
+}
+
+impl ClipboardFormat {
+    pub const ALL: [ClipboardFormat; 2] = [ClipboardFormat::Rgba, ClipboardFormat::Png];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ClipboardFormat::Rgba => "Raw RGBA",
+            ClipboardFormat::Png => "PNG",
+        }
+    }
+}
+
+/// Copy an image to the clipboard. PNG falls back to raw pixels where it can't be placed
+#[cfg(feature = "clipboard")]
+pub fn copy_image(image: &DynamicImage, format: ClipboardFormat) -> Result<()> {
+    if format == ClipboardFormat::Png {
+        match crate::capture::screenshot::encode_png(image).and_then(|png| copy_png(&png)) {
+            Ok(()) => {
+                info!("Image copied to clipboard as PNG");
+                return Ok(());
+            }
+            Err(e) => warn!("Couldn't copy the image as PNG ({}), copying raw pixels instead", e),
+        }
+    }
+
+    let rgba8 = image.to_rgba8();
+    let image_data = arboard::ImageData {
+        width: image.width() as usize,
+        height: image.height() as usize,
+        bytes: rgba8.as_raw().into(),
+    };
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_image(image_data))
+        .map_err(|e| anyhow!("Failed to copy image to clipboard: {}", e))?;
+    info!("Image copied to clipboard");
+    Ok(())
+}
+
+/// arboard only takes raw pixels, so PNG goes through the platform's own clipboard tool
+#[cfg(all(feature = "clipboard", target_os = "linux"))]
+fn copy_png(png: &[u8]) -> Result<()> {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let mut command = if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        let mut command = Command::new("wl-copy");
+        command.args(["--type", "image/png"]);
+        command
+    } else {
+        let mut command = Command::new("xclip");
+        command.args(["-selection", "clipboard", "-t", "image/png"]);
+        command
+    };
+    let mut child = command.stdin(Stdio::piped()).spawn()
+        .map_err(|e| anyhow!("PNG clipboard copies need wl-copy or xclip ({})", e))?;
+    child.stdin.take().ok_or_else(|| anyhow!("No stdin for the clipboard tool"))?.write_all(png)?;
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(anyhow!("The clipboard tool exited with {}", status));
+    }
+    Ok(())
+}
+
+#[cfg(all(feature = "clipboard", target_os = "macos"))]
+fn copy_png(png: &[u8]) -> Result<()> {
+    let path = std::env::temp_dir().join(format!("screensnap-clipboard-{}.png", std::process::id()));
+    std::fs::write(&path, png)?;
+    let script = format!("set the clipboard to (read (POSIX file \"{}\") as «class PNGf»)", path.display());
+    let output = std::process::Command::new("osascript").arg("-e").arg(script).output();
+    let _ = std::fs::remove_file(&path);
+    let output = output?;
+    if !output.status.success() {
+        return Err(anyhow!("osascript failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(())
+}
+
+#[cfg(all(feature = "clipboard", not(any(target_os = "linux", target_os = "macos"))))]
+fn copy_png(_png: &[u8]) -> Result<()> {
+    Err(anyhow!("PNG clipboard copies aren't supported on this platform"))
+}
\ No newline at end of file
@@ -0,0 +1,437 @@
+// src/config.rs
+use anyhow::{Result, anyhow};
+use log::{info, warn};
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::ai::local_model::{self, Quality, ResponseFormat};
+use crate::ai::postprocess::{self, Rule};
+use crate::ai::subprocess_model;
+use crate::capture::screenshot::PngCompression;
+use crate::clipboard::ClipboardFormat;
+
+/// Model used when neither --model nor the config file names one
+pub const DEFAULT_MODEL: &str = "llava:latest";
+
+/// Set by --profile to pick a profile for this run instead of the active one
+pub const PROFILE_ENV: &str = "SCREENSNAP_PROFILE";
+
+/// Server used when neither --ollama-url, OLLAMA_HOST nor the config file names one and none is discovered
+pub const DEFAULT_OLLAMA_URL: &str = "http://localhost:11434";
+
+/// Where an effective setting came from, lowest precedence first
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ValueSource {
+    Default,
+    /// Found by probing for a running server
+    Discovered,
+    File,
+    Env,
+    Flag,
+}
+
+impl ValueSource {
+    pub fn label(self) -> &'static str {
+        match self {
+            ValueSource::Default => "default",
+            ValueSource::Discovered => "discovered",
+            ValueSource::File => "file",
+            ValueSource::Env => "env",
+            ValueSource::Flag => "flag",
+        }
+    }
+}
+
+/// A setting's effective value and where it came from
+pub struct Resolved<T> {
+    pub value: T,
+    pub source: ValueSource,
+}
+
+impl<T> Resolved<T> {
+    fn new(value: T, source: ValueSource) -> Self {
+        Self { value, source }
+    }
+}
+
+/// Values given on the command line, which beat every other source
+#[derive(Default)]
+pub struct FlagOverrides {
+    pub model: Option<String>,
+    pub ollama_url: Option<String>,
+    pub format: Option<ResponseFormat>,
+    pub cmd_timeout_secs: Option<u64>,
+}
+
+/// The settings in effect once defaults, the config file, the environment and flags are merged
+pub struct ResolvedConfig {
+    pub model: Resolved<String>,
+    pub ollama_url: Resolved<String>,
+    pub prompt: Resolved<String>,
+    pub system_prompt: Resolved<Option<String>>,
+    pub format: Resolved<ResponseFormat>,
+    pub request_timeout_secs: Resolved<u64>,
+    pub cmd_timeout_secs: Resolved<u64>,
+}
+
+/// How the sidebar moves when it opens or closes
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Easing {
+    /// No animation, jump straight to the end state
+    Instant,
+    Linear,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    pub const ALL: [Easing; 4] = [Easing::Instant, Easing::Linear, Easing::EaseOut, Easing::EaseInOut];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Easing::Instant => "Instant",
+            Easing::Linear => "Linear",
+            Easing::EaseOut => "Ease out",
+            Easing::EaseInOut => "Ease in-out",
+        }
+    }
+}
+
+/// What the GUI's main capture button captures
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureTarget {
+    /// Everything on the screen
+    Screen,
+    /// One window, picked from a list
+    Window,
+}
+
+impl CaptureTarget {
+    pub const ALL: [CaptureTarget; 2] = [CaptureTarget::Screen, CaptureTarget::Window];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CaptureTarget::Screen => "Screen",
+            CaptureTarget::Window => "Window",
+        }
+    }
+}
+
+/// Settings a profile overrides; anything left out falls through to the base config
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct PartialConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub animation_duration: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub animation_easing: Option<Easing>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub respect_reduced_motion: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ollama_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage_stats: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub capture_delay_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto_analyze: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub render_markdown: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_capture: Option<CaptureTarget>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trim_preamble: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_rules: Option<Vec<Rule>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quality: Option<Quality>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub png_compression: Option<PngCompression>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clipboard_format: Option<ClipboardFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_payload_kb: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sidebar_idle_timeout_secs: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub save_dir: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name_template: Option<String>,
+}
+
+/// Settings persisted between sessions
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Config {
+    /// Sidebar open/close animation length in seconds
+    pub animation_duration: f32,
+    pub animation_easing: Easing,
+    /// Skip the animation when the OS asks for reduced motion
+    pub respect_reduced_motion: bool,
+    /// System prompt sent with every analysis from the GUI
+    pub system_prompt: Option<String>,
+    /// Ollama model used when --model isn't given
+    pub model: Option<String>,
+    /// Ollama server used when neither --ollama-url nor OLLAMA_HOST is set
+    pub ollama_url: Option<String>,
+    /// Keep local usage counters (see `screensnap stats`); nothing is sent anywhere
+    pub usage_stats: bool,
+    /// Extra wait before a GUI screen capture, in milliseconds (quick capture skips it)
+    pub capture_delay_ms: u64,
+    /// Start the default analysis as soon as a capture lands
+    pub auto_analyze: bool,
+    /// Render markdown in GUI replies instead of showing the model's raw text
+    pub render_markdown: bool,
+    /// What the GUI's main capture button does
+    pub default_capture: CaptureTarget,
+    /// Strip openers like "Sure, here's what I see:" from responses
+    pub trim_preamble: bool,
+    /// Regex replacements applied to every response, after the preamble is trimmed
+    pub response_rules: Vec<Rule>,
+    /// Preset for analysis speed vs. detail, used when --quality isn't given
+    pub quality: Quality,
+    /// PNG compression used when saving from the GUI
+    pub png_compression: PngCompression,
+    /// How captures are copied to the clipboard
+    pub clipboard_format: ClipboardFormat,
+    /// Captures whose encoded request would be larger than this (in KiB) are offered for downscaling
+    pub max_payload_kb: u64,
+    /// Collapse the GUI sidebar after this many seconds without input (0 = never)
+    pub sidebar_idle_timeout_secs: u64,
+    /// Directory CLI captures are saved to when --save isn't given
+    pub save_dir: Option<PathBuf>,
+    /// File name template used with the save directory
+    pub name_template: Option<String>,
+    /// Named sets of overrides layered on top of these settings
+    pub profiles: HashMap<String, PartialConfig>,
+    /// Profile applied when --profile isn't given (see `screensnap profile use`)
+    pub active_profile: Option<String>,
+    /// Profile merged into this config by `load`; `save` writes its settings back into it
+    #[serde(skip)]
+    pub applied_profile: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            animation_duration: 0.3,
+            animation_easing: Easing::EaseOut,
+            respect_reduced_motion: true,
+            system_prompt: None,
+            model: None,
+            ollama_url: None,
+            usage_stats: false,
+            capture_delay_ms: 0,
+            auto_analyze: false,
+            render_markdown: true,
+            default_capture: CaptureTarget::Screen,
+            trim_preamble: false,
+            response_rules: Vec::new(),
+            quality: Quality::Balanced,
+            png_compression: PngCompression::Default,
+            clipboard_format: ClipboardFormat::Rgba,
+            max_payload_kb: 4096,
+            sidebar_idle_timeout_secs: 0,
+            save_dir: None,
+            name_template: None,
+            profiles: HashMap::new(),
+            active_profile: None,
+            applied_profile: None,
+        }
+    }
+}
+
+impl Config {
+    /// Apply the configured response post-processing; the text comes back unchanged when none is set up
+    pub fn post_process(&self, response: &str) -> String {
+        let mut rules = if self.trim_preamble { postprocess::trim_preamble_rules() } else { Vec::new() };
+        rules.extend(self.response_rules.iter().cloned());
+        postprocess::post_process(response, &rules)
+    }
+
+    /// Location of the config file, e.g. ~/.config/screensnap/config.json
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("screensnap").join("config.json"))
+    }
+
+    /// Load the config file with the selected profile (--profile, else the active one) applied
+    pub fn load() -> Self {
+        let config = Self::load_file();
+        let profile = std::env::var(PROFILE_ENV).ok().or_else(|| config.active_profile.clone());
+        match profile {
+            Some(name) => config.with_profile(&name),
+            None => config,
+        }
+    }
+
+    /// Layer a profile's overrides on top of these settings. An unknown profile is warned about and ignored
+    pub fn with_profile(self, name: &str) -> Self {
+        let profile = match self.profiles.get(name) {
+            Some(profile) => profile,
+            None => {
+                warn!("No profile named '{}', using the base settings", name);
+                return self;
+            }
+        };
+        let merged = serde_json::to_value(&self).and_then(|mut base| {
+            if let (Some(base), serde_json::Value::Object(overrides)) = (base.as_object_mut(), serde_json::to_value(profile)?) {
+                base.extend(overrides);
+            }
+            serde_json::from_value::<Config>(base)
+        });
+        match merged {
+            Ok(mut merged) => {
+                merged.applied_profile = Some(name.to_string());
+                merged
+            }
+            Err(e) => {
+                warn!("Could not apply profile '{}': {}", name, e);
+                self
+            }
+        }
+    }
+
+    /// Load the config file as written, without applying any profile
+    pub fn load_file() -> Self {
+        let path = match Self::path() {
+            Some(path) => path,
+            None => {
+                warn!("No config directory available, using default settings");
+                return Self::default();
+            }
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                warn!("Ignoring invalid config file {}: {}", path.display(), e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// The model to use: the flag, then the config file, then `DEFAULT_MODEL`
+    pub fn resolve_model(&self, flag: Option<&str>) -> Resolved<String> {
+        match (flag, &self.model) {
+            (Some(model), _) => Resolved::new(model.to_string(), ValueSource::Flag),
+            (None, Some(model)) => Resolved::new(model.clone(), ValueSource::File),
+            (None, None) => Resolved::new(DEFAULT_MODEL.to_string(), ValueSource::Default),
+        }
+    }
+
+    /// The Ollama server: the flag, then OLLAMA_HOST, then the config file, then a discovered running
+    /// server, then `DEFAULT_OLLAMA_URL`
+    pub fn resolve_ollama_url(&self, flag: Option<&str>) -> Resolved<String> {
+        if let Some(url) = flag {
+            return Resolved::new(url.to_string(), ValueSource::Flag);
+        }
+        if let Ok(url) = std::env::var("OLLAMA_HOST") {
+            return Resolved::new(url, ValueSource::Env);
+        }
+        if let Some(url) = &self.ollama_url {
+            return Resolved::new(url.clone(), ValueSource::File);
+        }
+        match local_model::discover_ollama() {
+            Some(url) => Resolved::new(url, ValueSource::Discovered),
+            None => Resolved::new(DEFAULT_OLLAMA_URL.to_string(), ValueSource::Default),
+        }
+    }
+
+    /// Every effective setting, for `screensnap config show`
+    pub fn resolved(&self, flags: &FlagOverrides) -> ResolvedConfig {
+        ResolvedConfig {
+            model: self.resolve_model(flags.model.as_deref()),
+            ollama_url: self.resolve_ollama_url(flags.ollama_url.as_deref()),
+            prompt: Resolved::new(local_model::DEFAULT_PROMPT.to_string(), ValueSource::Default),
+            system_prompt: match &self.system_prompt {
+                Some(prompt) => Resolved::new(Some(prompt.clone()), ValueSource::File),
+                None => Resolved::new(None, ValueSource::Default),
+            },
+            format: match flags.format {
+                Some(format) => Resolved::new(format, ValueSource::Flag),
+                None => Resolved::new(ResponseFormat::Text, ValueSource::Default),
+            },
+            request_timeout_secs: Resolved::new(local_model::REQUEST_TIMEOUT_SECS, ValueSource::Default),
+            cmd_timeout_secs: match flags.cmd_timeout_secs {
+                Some(secs) => Resolved::new(secs, ValueSource::Flag),
+                None => Resolved::new(subprocess_model::DEFAULT_TIMEOUT_SECS, ValueSource::Default),
+            },
+        }
+    }
+
+    /// Write the config file, creating its directory if needed. With a profile applied, settings
+    /// the profile overrides are saved into it and the rest into the base config
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path().ok_or_else(|| anyhow!("No config directory available"))?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let contents = match &self.applied_profile {
+            Some(name) => self.split_profile(name)?,
+            None => serde_json::to_value(self)?,
+        };
+        std::fs::write(&path, serde_json::to_string_pretty(&contents)?)?;
+        info!("Settings saved to {}", path.display());
+        Ok(())
+    }
+
+    /// Undo `with_profile`: move the profile's keys back into it and restore the file's base values
+    fn split_profile(&self, name: &str) -> Result<serde_json::Value> {
+        let mut merged = serde_json::to_value(self)?;
+        let file = serde_json::to_value(Self::load_file())?;
+        let overridden: Vec<String> = match self.profiles.get(name).map(serde_json::to_value).transpose()? {
+            Some(serde_json::Value::Object(overrides)) => overrides.keys().cloned().collect(),
+            _ => Vec::new(),
+        };
+        let mut profile = serde_json::Map::new();
+        for key in overridden {
+            profile.insert(key.clone(), merged[&key].take());
+            merged[&key] = file[&key].clone();
+        }
+        merged["profiles"][name] = serde_json::Value::Object(profile);
+        Ok(merged)
+    }
+}
+
+/// Location of a file or folder kept next to the config file, e.g. ~/.config/screensnap/prompts.json
+pub fn sibling_path(file_name: &str) -> Option<PathBuf> {
+    Config::path().and_then(|path| path.parent().map(|dir| dir.join(file_name)))
+}
+
+/// Write `value` as pretty-printed JSON to a file kept next to the config file, creating the folder
+pub fn save_json_file<T: Serialize>(path: Option<PathBuf>, value: &T) -> Result<()> {
+    let path = path.ok_or_else(|| anyhow!("No config directory available"))?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, serde_json::to_string_pretty(value)?)?;
+    Ok(())
+}
+
+/// Load a JSON file kept next to the config file, such as the saved prompts. A file that doesn't parse
+/// is copied to `<name>.bak` first, so saving the empty default in its place can't lose what it held
+pub fn load_json_file<T: serde::de::DeserializeOwned + Default>(path: Option<PathBuf>) -> T {
+    let path = match path {
+        Some(path) => path,
+        None => return T::default(),
+    };
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(_) => return T::default(),
+    };
+    serde_json::from_str(&contents).unwrap_or_else(|e| {
+        let mut backup = path.clone().into_os_string();
+        backup.push(".bak");
+        match std::fs::write(&backup, &contents) {
+            Ok(()) => warn!("Ignoring invalid {} ({}); kept a copy at {}", path.display(), e, PathBuf::from(backup).display()),
+            Err(copy_error) => warn!("Ignoring invalid {} ({}) and could not back it up: {}", path.display(), e, copy_error),
+        }
+        T::default()
+    })
+}
\ No newline at end of file
@@ -0,0 +1,146 @@
+// src/countdown.rs
+use anyhow::Result;
+use eframe::egui;
+use egui::{Align2, Color32, RichText};
+use log::warn;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Size of the standalone countdown window
+const OVERLAY_SIZE: egui::Vec2 = egui::vec2(180.0, 130.0);
+
+/// Wait after the countdown window closes so the compositor has removed it before the capture
+const OVERLAY_CLOSE_SETTLE: Duration = Duration::from_millis(200);
+
+/// How often the countdown redraws
+const TICK: Duration = Duration::from_millis(100);
+
+/// Draw the countdown panel centered on the screen. Returns true when the user asked to cancel
+pub fn show(ctx: &egui::Context, remaining: Duration) -> bool {
+    let mut cancel = ctx.input(|i| i.key_pressed(egui::Key::Escape));
+    egui::Area::new("capture_countdown")
+        .order(egui::Order::Foreground)
+        .anchor(Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(ctx, |ui| {
+            egui::Frame::none()
+                .fill(Color32::from_rgba_unmultiplied(20, 20, 20, 230))
+                .rounding(12.0)
+                .inner_margin(16.0)
+                .show(ui, |ui| {
+                    ui.vertical_centered(|ui| {
+                        ui.label(RichText::new(format!("{}", remaining.as_secs_f32().ceil() as u64)).size(48.0).strong());
+                        ui.label(RichText::new("Capturing... Esc to cancel").small());
+                        if ui.button("Cancel").clicked() {
+                            cancel = true;
+                        }
+                    });
+                });
+        });
+    ctx.request_repaint_after(TICK);
+    cancel
+}
+
+/// Counts down in its own small always-on-top window, closed again before returning
+struct CountdownApp {
+    deadline: Instant,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl eframe::App for CountdownApp {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        let remaining = self.deadline.saturating_duration_since(Instant::now());
+        if show(ctx, remaining) {
+            self.cancelled.store(true, Ordering::SeqCst);
+            frame.close();
+        } else if remaining.is_zero() {
+            frame.close();
+        }
+    }
+
+    fn clear_color(&self, _visuals: &egui::Visuals) -> [f32; 4] {
+        [0.0, 0.0, 0.0, 0.0]
+    }
+}
+
+/// Whether a window could be opened at all. On Linux that needs an X11 or Wayland session;
+/// without one winit panics instead of returning an error
+fn has_display() -> bool {
+    if cfg!(target_os = "linux") {
+        ["DISPLAY", "WAYLAND_DISPLAY"].iter().any(|var| std::env::var_os(var).map_or(false, |value| !value.is_empty()))
+    } else {
+        true
+    }
+}
+
+/// Count down on stderr, one line per second, until `deadline`
+fn run_terminal(deadline: Instant) {
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        eprintln!("Capturing in {}...", remaining.as_secs_f32().ceil() as u64);
+        // Wake on the next whole second so the numbers count down evenly
+        let to_next = remaining.as_secs_f32().fract();
+        let to_next = if to_next == 0.0 { 1.0 } else { to_next };
+        std::thread::sleep(Duration::from_secs_f32(to_next).min(remaining));
+    }
+}
+
+/// Count down `delay` in an always-on-top window with a Cancel button. The window doesn't take focus
+/// from the app being captured (click it first for Esc to cancel) and is gone before this returns, so
+/// it never shows up in the capture. Returns false if the capture was cancelled. Without a display to
+/// open the window on, this counts down in the terminal instead
+pub fn run_overlay(delay: Duration) -> Result<bool> {
+    let deadline = Instant::now() + delay;
+    if !has_display() {
+        run_terminal(deadline);
+        return Ok(true);
+    }
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let (mon_x, mon_y, mon_width, mon_height) = match screenshots::Screen::all() {
+        Ok(screens) if !screens.is_empty() => {
+            let info = screens[0].display_info;
+            (info.x as f32, info.y as f32, info.width as f32, info.height as f32)
+        }
+        _ => (0.0, 0.0, 1920.0, 1080.0),
+    };
+    let native_options = eframe::NativeOptions {
+        initial_window_pos: Some(egui::pos2(
+            mon_x + (mon_width - OVERLAY_SIZE.x) / 2.0,
+            mon_y + (mon_height - OVERLAY_SIZE.y) / 2.0,
+        )),
+        initial_window_size: Some(OVERLAY_SIZE),
+        transparent: true,
+        decorated: false,
+        always_on_top: true,
+        active: false,
+        resizable: false,
+        ..eframe::NativeOptions::default()
+    };
+    let app_cancelled = Arc::clone(&cancelled);
+    // winit panics rather than erroring on some setups without a usable display
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| eframe::run_native(
+        "ScreenSnap countdown",
+        native_options,
+        Box::new(move |_cc| Box::new(CountdownApp { deadline, cancelled: app_cancelled })),
+    )));
+    match result {
+        Ok(Ok(())) => std::thread::sleep(OVERLAY_CLOSE_SETTLE),
+        Ok(Err(e)) => {
+            warn!("Could not open the countdown window ({}); counting down here instead", e);
+            run_terminal(deadline);
+        }
+        Err(_) => {
+            warn!("The countdown window crashed; counting down here instead");
+            run_terminal(deadline);
+        }
+    }
+    if cancelled.load(Ordering::SeqCst) {
+        return Ok(false);
+    }
+    // The window may have been closed early by the window manager
+    std::thread::sleep(deadline.saturating_duration_since(Instant::now()));
+    Ok(true)
+}
\ No newline at end of file
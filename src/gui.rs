@@ -2,19 +2,31 @@
 use anyhow::Result;
 use eframe::egui;
 use egui::{Align, Color32, Layout, RichText, ScrollArea, Stroke, Vec2, Ui, Order}; // Removed ViewportCommand
-use image::ImageFormat;
 use log::{error, info, warn}; 
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread;
 use std::time::{Duration, Instant};
 
-use arboard::{Clipboard, ImageData};
+use arboard::Clipboard;
 
 use crate::ai::connector::AiConnector;
-use crate::ai::local_model::LocalModel;
-use crate::capture::screenshot::ScreenshotManager;
-use crate::capture::window_finder::get_window_titles;
+use crate::ai::local_model::{self, LocalModel, Quality};
+use crate::capture::{annotate, diff, roi};
+use crate::capture::backend::{self, CaptureBackendKind};
+use crate::capture::screenshot::{self, ScreenshotManager};
+use crate::capture::window_finder::{self, get_window_titles, WindowBounds};
+use crate::config::{self, CaptureTarget, Config, Easing};
+use crate::countdown;
+use crate::bundle;
+#[cfg(feature = "clipboard")]
+use crate::clipboard::{self, ClipboardFormat};
+use crate::markdown;
+use crate::notes::PinnedNotes;
+use crate::prompts::PromptLibrary;
+use crate::stats;
 
 const SIDEBAR_WIDTH: f32 = 400.0;
 const HANDLE_WIDTH: f32 = 20.0;
@@ -22,13 +34,35 @@ const HANDLE_HEIGHT: f32 = 100.0;
 const DEFAULT_WINDOW_HEIGHT: f32 = 600.0; 
 const CLOSED_WINDOW_HEIGHT: f32 = HANDLE_HEIGHT + 20.0;
 const CHAT_INPUT_AREA_HEIGHT: f32 = 50.0; 
-const TASKBAR_BUFFER: f32 = 40.0;
-
-fn get_ollama_url(url_arg: Option<String>) -> String {
-    url_arg.unwrap_or_else(|| {
-        std::env::var("OLLAMA_HOST").unwrap_or_else(|_| "http://localhost:11434".to_string())
-    })
-}
+const BOBBING_FRAME_INTERVAL: Duration = Duration::from_millis(33);
+const MAX_PREVIEW_ZOOM: f32 = 8.0;
+const MAX_SYSTEM_PROMPT_CHARS: usize = 4000;
+const PROMPT_PREVIEW_CHARS: usize = 40;
+/// Pinned answers longer than this are cut short in the sidebar; the whole text is on hover
+const PINNED_NOTE_PREVIEW_CHARS: usize = 200;
+/// Longest a screen capture waits for the window to report itself minimized
+const MINIMIZE_TIMEOUT: Duration = Duration::from_millis(1000);
+/// How often a capture thread checks on the countdown and the minimize before a screen capture
+const COUNTDOWN_POLL_STEP: Duration = Duration::from_millis(50);
+const MAX_CAPTURE_DELAY_MS: u64 = 3000;
+const MAX_SIDEBAR_IDLE_SECS: u64 = 600;
+/// How long recognized text stays on screen after "Copy Text"
+#[cfg(all(feature = "ocr", feature = "clipboard"))]
+const OCR_TOAST_DURATION: Duration = Duration::from_secs(6);
+/// How often the status dot re-checks the Ollama server
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// How often the health thread looks for a manual recheck while it waits
+const HEALTH_POLL_STEP: Duration = Duration::from_millis(250);
+/// How long a window hovered in the picker stays outlined
+const WINDOW_FLASH_DURATION: Duration = Duration::from_millis(1200);
+/// Pause in typing after which live prompt mode re-runs the analysis
+const LIVE_PROMPT_DEBOUNCE: Duration = Duration::from_millis(800);
+/// Earlier answers replayed with a follow-up question; older ones are left out to keep the prompt short
+const MAX_FOLLOW_UP_TURNS: usize = 6;
+/// Half the side of the square "Cursor Area" captures around the mouse
+const CURSOR_AREA_RADIUS: u32 = 200;
+/// Capture requests this soon after the last one are ignored, so a double click captures once
+const CAPTURE_DEBOUNCE: Duration = Duration::from_millis(400);
 
 fn get_primary_monitor_info() -> (f32, f32, f32, f32) {
     let mut mon_abs_x = 0.0f32;
@@ -40,16 +74,17 @@ fn get_primary_monitor_info() -> (f32, f32, f32, f32) {
         Ok(screens) => {
             if screens.is_empty() {
                 error!("get_primary_monitor_info: No screens found. Using default values.");
-            } else {
-                let primary_screen_opt = screens.iter().find(|s| s.display_info.is_primary);
-                let screen_to_use = primary_screen_opt.unwrap_or_else(|| {
-                    warn!("get_primary_monitor_info: Could not identify primary screen. Using first screen.");
-                    &screens[0]
-                });
+            } else if let Some(index) = backend::select_usable_screen(&screens) {
+                let screen_to_use = &screens[index];
+                if !screen_to_use.display_info.is_primary {
+                    warn!("get_primary_monitor_info: Could not identify a usable primary screen. Using screen {}.", index);
+                }
                 mon_abs_x = screen_to_use.display_info.x as f32;
                 mon_abs_y = screen_to_use.display_info.y as f32;
                 mon_width = screen_to_use.display_info.width as f32;
                 mon_height = screen_to_use.display_info.height as f32;
+            } else {
+                error!("get_primary_monitor_info: All {} screen(s) report a size of 0x0 (running headless?). Using default values.", screens.len());
             }
         }
         Err(e) => {
@@ -59,18 +94,431 @@ fn get_primary_monitor_info() -> (f32, f32, f32, f32) {
     (mon_abs_x, mon_abs_y, mon_width, mon_height)
 }
 
+/// Bottom edge of the primary monitor's work area, so the closed sidebar sits just above the taskbar
+fn get_primary_work_area_bottom(mon_abs_y: f32, mon_height: f32) -> f32 {
+    let area = screenshots::Screen::all().ok()
+        .and_then(|screens| backend::select_usable_screen(&screens))
+        .and_then(|index| screenshot::work_area(index).ok());
+    match area {
+        Some(area) => (area.y as i64 + area.height as i64) as f32,
+        None => mon_abs_y + mon_height,
+    }
+}
+
+/// Window options for the GUI, set from the `gui` command's flags
+#[derive(Clone)]
+pub struct GuiOptions {
+    pub always_on_top: bool,
+    pub decorated: bool,
+    pub transparent: bool,
+    pub capture_backend: CaptureBackendKind,
+    pub power_save: bool,
+    /// None tries glow first and falls back to software rendering
+    pub renderer: Option<GuiRenderer>,
+}
+
+/// How the GUI is drawn
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum GuiRenderer {
+    /// OpenGL on the GPU
+    Glow,
+    /// wgpu (Vulkan, Metal or DirectX 12); needs the `wgpu` build feature
+    Wgpu,
+    /// OpenGL through Mesa's software rasterizer, for VMs and remote desktops without a usable GPU
+    Software,
+}
+
+/// Whether a transparent window will actually be composited
+#[cfg(target_os = "linux")]
+fn transparency_supported() -> bool {
+    use xcb::{x, Xid};
+
+    // Wayland compositors always handle alpha
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        return true;
+    }
+
+    // On X11 a compositing manager owns the _NET_WM_CM_S<screen> selection
+    let (conn, screen_num) = match xcb::Connection::connect(None) {
+        Ok(connection) => connection,
+        Err(e) => {
+            warn!("transparency_supported: Could not connect to X server: {}", e);
+            return false;
+        }
+    };
+    let atom_name = format!("_NET_WM_CM_S{}", screen_num);
+    let cookie = conn.send_request(&x::InternAtom {
+        only_if_exists: true,
+        name: atom_name.as_bytes(),
+    });
+    let atom = match conn.wait_for_reply(cookie) {
+        Ok(reply) => reply.atom(),
+        Err(_) => return false,
+    };
+    if atom == x::ATOM_NONE {
+        return false;
+    }
+    let cookie = conn.send_request(&x::GetSelectionOwner { selection: atom });
+    match conn.wait_for_reply(cookie) {
+        Ok(reply) => !reply.owner().is_none(),
+        Err(_) => false,
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn transparency_supported() -> bool {
+    true
+}
+
+/// Whether the OS asks applications to minimize non-essential motion
+#[cfg(target_os = "linux")]
+fn prefers_reduced_motion() -> bool {
+    // GNOME (and most GTK desktops) expose this as a gsettings key
+    match std::process::Command::new("gsettings")
+        .args(["get", "org.gnome.desktop.interface", "enable-animations"])
+        .output()
+    {
+        Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout).trim() == "false",
+        _ => false,
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn prefers_reduced_motion() -> bool {
+    match std::process::Command::new("defaults")
+        .args(["read", "com.apple.universalaccess", "reduceMotion"])
+        .output()
+    {
+        Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout).trim() == "1",
+        _ => false,
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn prefers_reduced_motion() -> bool {
+    use windows::Win32::Foundation::BOOL;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        SystemParametersInfoW, SPI_GETCLIENTAREAANIMATION, SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS,
+    };
+
+    let mut animations_enabled = BOOL(1);
+    let ok = unsafe {
+        SystemParametersInfoW(
+            SPI_GETCLIENTAREAANIMATION,
+            0,
+            Some(&mut animations_enabled as *mut BOOL as *mut std::ffi::c_void),
+            SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+        )
+    };
+    ok.as_bool() && !animations_enabled.as_bool()
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn prefers_reduced_motion() -> bool {
+    false
+}
+
+/// Map linear animation progress (0..1) onto the chosen easing curve
+fn ease(easing: Easing, progress: f32) -> f32 {
+    match easing {
+        Easing::Instant => 1.0,
+        Easing::Linear => progress,
+        Easing::EaseOut => 1.0 - (1.0 - progress).powi(3),
+        Easing::EaseInOut => {
+            if progress < 0.5 {
+                4.0 * progress.powi(3)
+            } else {
+                1.0 - (-2.0 * progress + 2.0).powi(3) / 2.0
+            }
+        }
+    }
+}
+
+/// Upload an image for drawing with the painter
+fn image_texture(ctx: &egui::Context, name: &str, image: &image::DynamicImage) -> egui::TextureHandle {
+    let size = [image.width() as usize, image.height() as usize];
+    let color_image = egui::ColorImage::from_rgba_unmultiplied(size, image.to_rgba8().as_flat_samples().as_slice());
+    ctx.load_texture(name, color_image, egui::TextureOptions::LINEAR)
+}
+
+/// Shorten text to at most `max` characters, marking the cut with an ellipsis
+fn truncate_chars(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((index, _)) => format!("{}…", &text[..index]),
+        None => text.to_string(),
+    }
+}
+
+/// Analyze the capture, or its region of interest with the full capture as context.
+/// With `max_payload`, the capture is first downscaled until its request fits in that many bytes
+fn analyze_capture(ai_model: &mut LocalModel, image_data: &[u8], roi: Option<roi::Roi>, max_payload: Option<usize>) -> Result<String> {
+    let downscaled;
+    let (image_data, roi) = match max_payload {
+        Some(max_bytes) => {
+            let (data, scale) = screenshot::downscale_to_fit(image_data, max_bytes, None)?;
+            downscaled = data;
+            (downscaled.as_slice(), roi.map(|roi| roi.scaled(scale)))
+        }
+        None => (image_data, roi),
+    };
+    if roi.is_some() {
+        let prompt = roi::roi_prompt(ai_model.prompt());
+        ai_model.set_prompt(&prompt);
+    }
+    roi::analyze(ai_model, image_data, roi)
+}
+
+/// Chat text for a failed analysis, with a hint for the common Ollama problems
+fn analysis_failure_reply(e: &anyhow::Error, model_name: &str) -> String {
+    let mut reply = format!("AI processing failed: {}", e);
+    if e.to_string().contains("not found") {
+        reply.push_str(&format!("\n\nTo fix: ollama pull {}", model_name));
+    } else if e.to_string().contains("not available") || e.to_string().contains("connection refused") {
+        reply.push_str("\n\nEnsure Ollama is running: ollama serve");
+    }
+    reply
+}
+
 struct ThreadSafeState {
     processing: bool,
     ai_response: String,
     image_data: Vec<u8>,
     current_image: Option<egui::TextureHandle>,
+    looks_blank: bool,
+    /// Chat id reserved for the reply now in `ai_response`, so it lands next to the message that asked for it
+    response_id: Option<u64>,
+    /// Replies that finished after a newer operation took over `ai_response`
+    finished_replies: Vec<ChatMessage>,
+    /// Bumped every time a new capture lands
+    capture_count: u64,
+    /// Capture the default analysis is running on, so it isn't started twice
+    analyzing_capture: Option<u64>,
+    /// A capture is running: new capture requests are ignored until it ends, and a window minimized
+    /// for a screen capture is restored
+    capturing: bool,
+    /// Countdown shown before a delayed capture; the window minimizes once it runs out
+    capture_countdown: Option<CaptureCountdown>,
+    /// The window was minimized as of the last frame
+    window_minimized: bool,
+    /// Latest live prompt answer, replaced as the prompt is refined
+    live_preview: String,
+    live_processing: bool,
+    /// Bumped for every live request; a reply from an older one is dropped
+    live_generation: u64,
+    /// A live request is running, even one whose reply will be dropped
+    live_in_flight: bool,
+    /// Text from the last "Copy Text" and when it arrived, shown briefly; no timestamp while OCR is running
+    #[cfg(all(feature = "ocr", feature = "clipboard"))]
+    ocr_toast: Option<(String, Option<Instant>)>,
+    ollama_health: OllamaHealth,
+    /// Set by clicking the status dot; the health thread checks again right away
+    health_recheck_requested: bool,
+    /// Whether any installed model takes images; None until the model list has been checked
+    has_vision_model: Option<bool>,
+    /// The "Download llava" pull while it runs
+    model_pull: Option<ModelPull>,
+    model_pull_error: Option<String>,
+    /// Ollama server for every request and the health monitor; switching profiles can change it
+    ollama_url: String,
+}
+
+impl ThreadSafeState {
+    /// Store a new capture's PNG bytes and drop the stale preview texture
+    fn set_capture(&mut self, image_data: Vec<u8>, looks_blank: bool) {
+        self.image_data = image_data;
+        self.current_image = None;
+        self.looks_blank = looks_blank;
+        self.capture_count += 1;
+    }
+
+    /// Hand a worker's reply to the UI; replies superseded by a newer operation go straight to the history
+    fn finish_reply(&mut self, id: u64, text: String) {
+        if self.response_id == Some(id) {
+            self.ai_response = text;
+            self.processing = false;
+        } else {
+            self.finished_replies.push(ChatMessage { id, text, is_user: false, timestamp: chrono::Local::now(), model: None });
+        }
+    }
+}
+
+/// A model download started from the status banner
+struct ModelPull {
+    progress: local_model::PullProgress,
+    /// Set by the Cancel button; the pull stops at its next status line
+    cancel: Arc<AtomicBool>,
+}
+
+/// A delayed capture counting down in the window
+#[derive(Clone, Copy)]
+struct CaptureCountdown {
+    deadline: Instant,
+    /// Set from the UI; the capture thread then gives up
+    cancelled: bool,
+}
+
+/// Let a delayed capture's countdown run out, then wait (up to `MINIMIZE_TIMEOUT`) for the window to
+/// minimize. Returns false, clearing `capturing`, if the countdown was cancelled
+fn wait_for_capture(state: &Mutex<ThreadSafeState>, repaint_ctx: &egui::Context) -> bool {
+    loop {
+        let mut state_guard = state.lock().unwrap();
+        let capture_countdown = state_guard.capture_countdown;
+        match capture_countdown {
+            Some(countdown) if countdown.cancelled => {
+                state_guard.capture_countdown = None;
+                state_guard.capturing = false;
+                drop(state_guard);
+                repaint_ctx.request_repaint();
+                return false;
+            }
+            Some(countdown) if Instant::now() < countdown.deadline => {
+                drop(state_guard);
+                thread::sleep(COUNTDOWN_POLL_STEP);
+            }
+            Some(_) => {
+                state_guard.capture_countdown = None;
+                drop(state_guard);
+                repaint_ctx.request_repaint();
+                break;
+            }
+            None => break,
+        }
+    }
+    let minimize_deadline = Instant::now() + MINIMIZE_TIMEOUT;
+    while !state.lock().unwrap().window_minimized && Instant::now() < minimize_deadline {
+        // A frame has to run for the window to minimize and report it
+        repaint_ctx.request_repaint();
+        thread::sleep(COUNTDOWN_POLL_STEP);
+    }
+    true
+}
+
+/// What the last background check of the Ollama server found
+#[derive(Clone, PartialEq)]
+enum OllamaHealth {
+    /// No check has finished yet
+    Checking,
+    /// The server answered, listing these installed models
+    Up(Vec<String>),
+    Down(String),
+}
+
+/// Ping the server's /api/tags and read the installed model names
+fn check_ollama_health(url: &str) -> OllamaHealth {
+    let client = match reqwest::blocking::Client::builder().timeout(Duration::from_secs(3)).build() {
+        Ok(client) => client,
+        Err(e) => return OllamaHealth::Down(e.to_string()),
+    };
+    match client.get(format!("{}/api/tags", url)).send() {
+        Ok(response) if response.status().is_success() => match response.json::<serde_json::Value>() {
+            Ok(body) => OllamaHealth::Up(
+                body["models"].as_array()
+                    .map(|models| models.iter().filter_map(|model| model["name"].as_str().map(String::from)).collect())
+                    .unwrap_or_default(),
+            ),
+            Err(e) => OllamaHealth::Down(format!("Unexpected reply from {}: {}", url, e)),
+        },
+        Ok(response) => OllamaHealth::Down(format!("{} answered {}", url, response.status())),
+        Err(_) => OllamaHealth::Down(format!("No answer from {}", url)),
+    }
+}
+
+/// Ask the server whether any installed model takes images; None if it can't tell
+fn check_vision_models(url: &str) -> Option<bool> {
+    std::env::set_var("OLLAMA_HOST", url);
+    let ai_model = LocalModel::new(local_model::RECOMMENDED_VISION_MODEL).ok()?;
+    match ai_model.installed_vision_models() {
+        Ok(vision_models) => Some(!vision_models.is_empty()),
+        Err(e) => {
+            warn!("Could not check for vision models: {}", e);
+            None
+        }
+    }
+}
+
+/// Re-check the server in `ThreadSafeState::ollama_url` every `HEALTH_CHECK_INTERVAL`, or sooner when a
+/// recheck is requested. Vision support is only looked up again when the server or its model list changes
+fn spawn_health_monitor(state: Arc<Mutex<ThreadSafeState>>, repaint_ctx: egui::Context) {
+    thread::spawn(move || {
+        let mut vision_checked_for: Option<(String, Vec<String>)> = None;
+        loop {
+            let ollama_url = state.lock().unwrap().ollama_url.clone();
+            let health = check_ollama_health(&ollama_url);
+            let has_vision_model = match &health {
+                OllamaHealth::Up(models) if vision_checked_for.as_ref().map_or(true, |(url, checked)| *url != ollama_url || checked != models) => {
+                    vision_checked_for = Some((ollama_url.clone(), models.clone()));
+                    Some(check_vision_models(&ollama_url))
+                }
+                _ => None,
+            };
+            {
+                let mut state_guard = state.lock().unwrap();
+                if let Some(has_vision_model) = has_vision_model {
+                    state_guard.has_vision_model = has_vision_model;
+                    repaint_ctx.request_repaint();
+                }
+                if state_guard.ollama_health != health {
+                    state_guard.ollama_health = health;
+                    repaint_ctx.request_repaint();
+                }
+            }
+            let checked_at = Instant::now();
+            while checked_at.elapsed() < HEALTH_CHECK_INTERVAL {
+                thread::sleep(HEALTH_POLL_STEP);
+                if std::mem::take(&mut state.lock().unwrap().health_recheck_requested) {
+                    break;
+                }
+            }
+        }
+    });
 }
 
 #[derive(Clone)]
 struct ChatMessage {
+    /// Increases monotonically; the history is kept and drawn in id order
+    id: u64,
     text: String,
     is_user: bool,
     timestamp: chrono::DateTime<chrono::Local>,
+    /// Model that wrote an analysis reply, shown next to the name
+    model: Option<String>,
+}
+
+/// An analysis waiting on the oversized-capture banner
+struct OversizeAnalysis {
+    /// The chat prompt, or None for the default analysis
+    prompt: Option<String>,
+    encoded_bytes: usize,
+}
+
+/// Input of the last analysis, kept so it can be re-run with another model
+#[derive(Clone)]
+struct LastAnalysis {
+    image_data: Vec<u8>,
+    /// The expanded chat prompt, or None for the default analysis
+    prompt: Option<String>,
+    roi: Option<roi::Roi>,
+    max_payload: Option<usize>,
+}
+
+/// A window hovered in the picker, outlined on screen for a moment
+struct WindowFlash {
+    title: String,
+    /// Filled in by a worker thread so hovering never waits on the window system. Stays None where
+    /// bounds can't be read (e.g. Wayland); nothing is drawn then
+    bounds: Arc<Mutex<Option<WindowBounds>>>,
+    started: Instant,
+}
+
+/// Before/after slider over two captures: `before` shows left of the divider, `after` right of it.
+/// Captures are numbered like `comparison_images`: the history oldest first, then the current image
+struct CaptureComparison {
+    before: usize,
+    after: usize,
+    /// Divider position as a fraction of the width
+    divider: f32,
+    /// Textures of the pair they were made for, rebuilt when the pick changes
+    textures: Option<((usize, usize), egui::TextureHandle, egui::TextureHandle)>,
 }
 
 pub struct ScreenSnapApp {
@@ -79,43 +527,117 @@ pub struct ScreenSnapApp {
     current_x: f32,
     animation_start_x: f32,
     animation_start_time: Option<Instant>,
-    animation_duration: f32,
+    /// Last input event, for collapsing the sidebar when idle
+    last_interaction: Instant,
+    config: Config,
+    reduced_motion: bool,
     was_layout_initialized: bool,
     was_style_initialized: bool,
     screenshot_manager: Arc<Mutex<ScreenshotManager>>,
     state: Arc<Mutex<ThreadSafeState>>,
     model_name: String,
+    /// OLLAMA_HOST as it was at launch; requests overwrite the variable, so it is put back before
+    /// a profile switch resolves the server again
+    launch_ollama_host: Option<String>,
     window_list: Vec<String>,
+    /// Why the window list couldn't be read, shown instead of an empty picker
+    window_list_error: Option<String>,
     selected_window: Option<String>,
+    last_capture_window: Option<String>,
+    /// When the last accepted capture request came in, for `CAPTURE_DEBOUNCE`
+    last_capture_request: Option<Instant>,
+    zoom: f32,
+    pan: Vec2,
+    /// Region of interest as fractions of the image size, marked with Shift+drag on the preview
+    roi: Option<egui::Rect>,
+    roi_drag_start: Option<egui::Pos2>,
     chat_history: Vec<ChatMessage>,
+    next_message_id: u64,
+    /// Model behind each pending analysis reply, by reserved chat id
+    reply_models: HashMap<u64, String>,
+    last_analysis: Option<LastAnalysis>,
+    /// Reply that came from a thumbnail, offering the full-resolution analysis until it runs
+    quick_look_reply: Option<u64>,
+    /// Open before/after slider, closed when a new capture lands
+    comparison: Option<CaptureComparison>,
+    seen_capture_count: u64,
+    minimize_for_capture: bool,
+    restore_after_capture: bool,
+    /// Analysis held back until the user decides what to do with an oversized capture
+    oversize_analysis: Option<OversizeAnalysis>,
+    /// That decision (true = downscale), used by the next analysis
+    oversize_choice: Option<bool>,
+    /// Re-analyze as the chat input is edited
+    live_prompt: bool,
+    /// Send chat messages as follow-ups to the earlier answers instead of fresh analyses
+    follow_up: bool,
+    /// When the chat input was last edited, while a live re-analysis is pending
+    live_edited_at: Option<Instant>,
+    /// Latest debounced live prompt, waiting for the running request to finish
+    live_pending: Option<String>,
+    window_flash: Option<WindowFlash>,
+    /// Outer position of the app window in points, for mapping screen coordinates into it
+    window_origin: Option<egui::Pos2>,
+    prompt_library: PromptLibrary,
+    /// Answers pinned above the capture buttons; kept apart from the chat so /clear leaves them
+    pinned_notes: PinnedNotes,
+    /// Name typed for saving the chat box text to the prompt library
+    new_prompt_name: String,
     current_input: String,
     should_exit: bool, // Added flag
+    /// First launch (no config file yet): explain the capture buttons until the user dismisses it
+    show_intro: bool,
+    egui_ctx: egui::Context,
+    power_save: bool,
 }
 
-impl Default for ScreenSnapApp {
-    fn default() -> Self {
-        let screenshot_manager = ScreenshotManager::new().map_or_else(
+impl ScreenSnapApp {
+    fn new(options: &GuiOptions, egui_ctx: egui::Context) -> Self {
+        let screenshot_manager = ScreenshotManager::with_backend(options.capture_backend).map_or_else(
             |e| {
                 error!("Failed to initialize screenshot manager: {}", e);
                 Arc::new(Mutex::new(ScreenshotManager::new().unwrap()))
             },
             |manager| Arc::new(Mutex::new(manager)),
         );
-        let window_list = get_window_titles().unwrap_or_else(|e| {
-            error!("Failed to get window titles on init: {}", e); Vec::new()
-        });
+        let (window_list, window_list_error) = match get_window_titles() {
+            Ok(list) => (list, None),
+            Err(e) => {
+                error!("Failed to get window titles on init: {}", e);
+                (Vec::new(), Some(e.to_string()))
+            }
+        };
+        let show_intro = Config::path().map_or(false, |path| !path.exists());
+        let config = Config::load();
+        let model_name = config.resolve_model(None).value;
+        let launch_ollama_host = std::env::var("OLLAMA_HOST").ok();
         let state = Arc::new(Mutex::new(ThreadSafeState {
             processing: false, ai_response: String::new(), image_data: Vec::new(), current_image: None,
+            looks_blank: false, response_id: None, finished_replies: Vec::new(), capture_count: 0, analyzing_capture: None, capturing: false,
+            capture_countdown: None, window_minimized: false,
+            live_preview: String::new(), live_processing: false, live_generation: 0, live_in_flight: false,
+            #[cfg(all(feature = "ocr", feature = "clipboard"))]
+            ocr_toast: None,
+            ollama_health: OllamaHealth::Checking, health_recheck_requested: false,
+            has_vision_model: None, model_pull: None, model_pull_error: None,
+            ollama_url: config.resolve_ollama_url(None).value,
         }));
+        spawn_health_monitor(Arc::clone(&state), egui_ctx.clone());
+        let reduced_motion = prefers_reduced_motion();
+        if reduced_motion {
+            info!("OS requests reduced motion");
+        }
 
         Self {
             open: false, target_x: 0.0, current_x: 0.0, animation_start_x: 0.0,
-            animation_start_time: None, animation_duration: 0.3,
+            animation_start_time: None, last_interaction: Instant::now(), config, reduced_motion,
             was_layout_initialized: false, 
             was_style_initialized: false, 
-            screenshot_manager, state, model_name: "llava:latest".to_string(), window_list,
-            selected_window: None, chat_history: Vec::new(), current_input: String::new(),
+            screenshot_manager, state, model_name, launch_ollama_host, window_list, window_list_error,
+            selected_window: None, last_capture_window: None, last_capture_request: None, zoom: 1.0, pan: Vec2::ZERO, roi: None, roi_drag_start: None, chat_history: Vec::new(), next_message_id: 0, reply_models: HashMap::new(), last_analysis: None, quick_look_reply: None, comparison: None, seen_capture_count: 0, minimize_for_capture: false, restore_after_capture: false, oversize_analysis: None, oversize_choice: None, live_prompt: false, follow_up: false, live_edited_at: None, live_pending: None, window_flash: None, window_origin: None, prompt_library: PromptLibrary::load(), pinned_notes: PinnedNotes::load(), new_prompt_name: String::new(), current_input: String::new(),
             should_exit: false, // Initialize flag
+            show_intro,
+            egui_ctx, power_save: options.power_save,
         }
     }
 }
@@ -127,6 +649,8 @@ impl eframe::App for ScreenSnapApp {
             frame.close();
             return;
         }
+        self.window_origin = frame.info().window_info.position;
+        self.state.lock().unwrap().window_minimized = frame.info().window_info.minimized;
 
         if !self.was_style_initialized {
             let mut style = (*ctx.style()).clone();
@@ -158,6 +682,54 @@ impl eframe::App for ScreenSnapApp {
             self.was_style_initialized = true;
         }
 
+        #[cfg(feature = "clipboard")]
+        if !ctx.wants_keyboard_input() && ctx.input(|i| i.modifiers.command && i.key_pressed(egui::Key::V)) {
+            self.paste_image_from_clipboard();
+        }
+
+        self.handle_shortcuts(ctx, frame);
+        self.collapse_if_idle(ctx, frame);
+        self.run_live_prompt();
+
+        let capture_countdown = self.state.lock().unwrap().capture_countdown;
+        if let Some(capture_countdown) = capture_countdown {
+            let remaining = capture_countdown.deadline.saturating_duration_since(Instant::now());
+            if !capture_countdown.cancelled && countdown::show(ctx, remaining) {
+                // Only while the countdown is still running, so a capture already under way keeps its minimize
+                let mut state_guard = self.state.lock().unwrap();
+                if let Some(capture_countdown) = &mut state_guard.capture_countdown {
+                    info!("Delayed capture cancelled");
+                    capture_countdown.cancelled = true;
+                    self.minimize_for_capture = false;
+                }
+            }
+        } else if self.minimize_for_capture {
+            self.minimize_for_capture = false;
+            self.restore_after_capture = true;
+            frame.set_minimized(true);
+        } else if self.restore_after_capture && !self.state.lock().unwrap().capturing {
+            self.restore_after_capture = false;
+            frame.set_minimized(false);
+        }
+
+        let new_capture = {
+            let state_guard = self.state.lock().unwrap();
+            (state_guard.capture_count != self.seen_capture_count)
+                .then_some((state_guard.capture_count, state_guard.looks_blank))
+        };
+        if let Some((capture_count, looks_blank)) = new_capture {
+            self.seen_capture_count = capture_count;
+            self.roi = None;
+            self.oversize_analysis = None;
+            self.quick_look_reply = None;
+            self.comparison = None;
+            // A blank capture gets the retry prompt instead
+            if self.config.auto_analyze && !looks_blank {
+                info!("Analyzing the new capture automatically");
+                self.analyze_image();
+            }
+        }
+
         if !self.was_layout_initialized && ctx.screen_rect().width() > 0.0 {
             let current_app_window_width = ctx.screen_rect().width();
             let initial_x = current_app_window_width; 
@@ -188,9 +760,13 @@ impl eframe::App for ScreenSnapApp {
 
         if let Some(start_time) = self.animation_start_time {
             let elapsed = start_time.elapsed().as_secs_f32();
-            let progress = (elapsed / self.animation_duration).min(1.0);
-            let ease = 1.0 - (1.0 - progress).powi(3);
-            self.current_x = self.animation_start_x + (self.target_x - self.animation_start_x) * ease;
+            let easing = self.effective_easing();
+            let progress = if easing == Easing::Instant || self.config.animation_duration <= 0.0 {
+                1.0
+            } else {
+                (elapsed / self.config.animation_duration).min(1.0)
+            };
+            self.current_x = self.animation_start_x + (self.target_x - self.animation_start_x) * ease(easing, progress);
             
             if progress >= 1.0 {
                 self.current_x = self.target_x;
@@ -204,7 +780,7 @@ impl eframe::App for ScreenSnapApp {
 
                     let (mon_abs_x, mon_abs_y, mon_width, mon_height) = get_primary_monitor_info();
                     let desired_x = mon_abs_x + mon_width - closed_width;
-                    let desired_y = mon_abs_y + mon_height - closed_height - TASKBAR_BUFFER;
+                    let desired_y = get_primary_work_area_bottom(mon_abs_y, mon_height) - closed_height;
                     frame.set_window_pos(egui::pos2(desired_x.max(0.0), desired_y.max(0.0)));
                     info!(
                         "Animation to CLOSE ended. Repositioned to ({}, {}). Window size: ({}, {})",
@@ -252,8 +828,14 @@ impl eframe::App for ScreenSnapApp {
 
         let handle_x_pos = self.current_x - HANDLE_WIDTH;
         let handle_center_y = (ctx.screen_rect().height() - HANDLE_HEIGHT) / 2.0f32;
-        let time = ctx.input(|i| i.time);
-        let bobbing_offset_f32 = (time * 1.5).sin() as f32 * 3.0;
+        // The handle bobs to draw attention to the closed sidebar, and only while the window has focus
+        let bobbing = !self.power_save && !self.open && ctx.input(|i| i.focused);
+        let bobbing_offset_f32 = if bobbing {
+            let time = ctx.input(|i| i.time);
+            (time * 1.5).sin() as f32 * 3.0
+        } else {
+            0.0
+        };
         let handle_rect = egui::Rect::from_min_size(
             egui::pos2(handle_x_pos.max(0.0), handle_center_y + bobbing_offset_f32),
             egui::vec2(HANDLE_WIDTH, HANDLE_HEIGHT),
@@ -280,87 +862,675 @@ impl eframe::App for ScreenSnapApp {
                                 .fill(Color32::TRANSPARENT)
                                 .frame(false)
                             ).clicked() {
-                                self.open = !self.open;
-                                self.animation_start_x = self.current_x;
-                                
-                                if self.open { 
-                                    frame.set_window_size(egui::vec2(SIDEBAR_WIDTH + HANDLE_WIDTH, DEFAULT_WINDOW_HEIGHT));
-                                    self.target_x = HANDLE_WIDTH;
-                                    info!(
-                                        "Handle clicked to OPEN. Current panel_x: {}. Target panel_x: {}. Window expanded.",
-                                        self.animation_start_x, self.target_x
-                                    );
-                                } else { 
-                                    self.target_x = ctx.screen_rect().width();
-                                    info!(
-                                        "Handle clicked to CLOSE. Current panel_x: {}. Target panel_x: {} (current window width).",
-                                        self.animation_start_x, self.target_x
-                                    );
-                                }
-                                self.animation_start_time = Some(Instant::now());
+                                self.toggle_sidebar(ctx, frame);
                             }
                         });
                     });
             });
+
+        // Keep repainting only while something on screen moves; worker threads
+        // wake us with request_repaint when their results land
+        if self.animation_start_time.is_none() && bobbing {
+            ctx.request_repaint_after(BOBBING_FRAME_INTERVAL);
+        }
     }
 }
 
 impl ScreenSnapApp {    
-    fn draw_sidebar_contents(&mut self, frame_ui: &mut Ui, ctx: &egui::Context) {
-        let top_section_response = frame_ui.vertical(|ui| {
-            ui.add_space(10.0);
+    /// Start the open/close animation of the sidebar
+    fn toggle_sidebar(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        self.open = !self.open;
+        self.animation_start_x = self.current_x;
+        
+        if self.open { 
+            frame.set_window_size(egui::vec2(SIDEBAR_WIDTH + HANDLE_WIDTH, DEFAULT_WINDOW_HEIGHT));
+            self.target_x = HANDLE_WIDTH;
+            info!(
+                "Sidebar toggled to OPEN. Current panel_x: {}. Target panel_x: {}. Window expanded.",
+                self.animation_start_x, self.target_x
+            );
+        } else { 
+            self.target_x = ctx.screen_rect().width();
+            info!(
+                "Sidebar toggled to CLOSE. Current panel_x: {}. Target panel_x: {} (current window width).",
+                self.animation_start_x, self.target_x
+            );
+        }
+        self.animation_start_time = Some(Instant::now());
+    }
+
+    /// Close the sidebar once it has gone `sidebar_idle_timeout_secs` without input.
+    /// Running analyses and a focused chat input count as activity
+    fn collapse_if_idle(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        let busy = self.state.lock().unwrap().processing;
+        if busy || ctx.wants_keyboard_input() || ctx.input(|i| !i.events.is_empty()) {
+            self.last_interaction = Instant::now();
+        }
+        let timeout = self.config.sidebar_idle_timeout_secs;
+        if !self.open || timeout == 0 || self.animation_start_time.is_some() {
+            return;
+        }
+        let idle_limit = Duration::from_secs(timeout);
+        let idle = self.last_interaction.elapsed();
+        if idle >= idle_limit {
+            info!("No input for {}s, collapsing the sidebar", timeout);
+            self.toggle_sidebar(ctx, frame);
+        } else {
+            // Wake up in time even when nothing else repaints (e.g. power-save mode)
+            ctx.request_repaint_after(idle_limit - idle);
+        }
+    }
+
+    /// Handle keyboard shortcuts. Ctrl combos work while typing; plain keys only when the chat input isn't focused
+    fn handle_shortcuts(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        use egui::{Key, Modifiers};
+
+        let pressed = |key: Key| ctx.input_mut(|i| i.consume_key(Modifiers::COMMAND, key));
+        if pressed(Key::Enter) {
+            self.analyze_image();
+        } else if ctx.input_mut(|i| i.consume_key(Modifiers::COMMAND | Modifiers::SHIFT, Key::N)) {
+            self.quick_capture();
+        } else if ctx.input_mut(|i| i.consume_key(Modifiers::COMMAND | Modifiers::SHIFT, Key::A)) {
+            self.capture_cursor_area();
+        } else if pressed(Key::N) {
+            self.capture_full_screen();
+        } else if pressed(Key::W) {
+            if !self.open {
+                self.toggle_sidebar(ctx, frame);
+            }
+            self.open_window_picker();
+        } else if pressed(Key::L) {
+            self.clear_conversation();
+        } else if self.open && !ctx.wants_keyboard_input() && ctx.input_mut(|i| i.consume_key(Modifiers::NONE, Key::Escape)) {
+            self.toggle_sidebar(ctx, frame);
+        }
+    }
+
+    /// Re-read the window list, keeping the old one if that fails
+    fn refresh_window_list(&mut self) {
+        match get_window_titles() {
+            Ok(list) => {
+                self.window_list = list;
+                self.window_list_error = None;
+            }
+            Err(e) => {
+                error!("Failed to get window list: {}", e);
+                self.window_list_error = Some(e.to_string());
+            }
+        }
+    }
+
+    /// Refresh the window list and show the window selector
+    fn open_window_picker(&mut self) {
+        // The portal has its own picker, and window titles can't be listed there anyway
+        let picks_windows = self.screenshot_manager.try_lock().map_or(false, |manager| manager.picks_windows());
+        if picks_windows {
+            self.selected_window = Some("Picked window".to_string());
+            self.capture_selected_window();
+            return;
+        }
+        self.refresh_window_list();
+        if !self.window_list.is_empty() && self.selected_window.is_none() {
+            self.selected_window = Some(self.window_list[0].clone());
+        }
+    }
+
+    /// The marked region of interest in pixels of the current image
+    fn roi_pixels(&self) -> Option<roi::Roi> {
+        let roi = self.roi?;
+        let (width, height) = self.screenshot_manager.lock().ok()?
+            .get_current_image()
+            .map(|image| (image.width() as f32, image.height() as f32))?;
+        Some(roi::Roi {
+            x: (roi.min.x * width).round() as i32,
+            y: (roi.min.y * height).round() as i32,
+            width: (roi.width() * width).round() as u32,
+            height: (roi.height() * height).round() as u32,
+        })
+    }
+
+    /// `normalize_model_name` against the models the last health check listed, with an installed
+    /// model to suggest when the result isn't installed but looks like a typo of one
+    fn resolve_model_name(&self, name: &str) -> Result<(String, Option<String>)> {
+        let installed = match &self.state.lock().unwrap().ollama_health {
+            OllamaHealth::Up(models) => models.clone(),
+            _ => Vec::new(),
+        };
+        let model_name = local_model::normalize_model_name(name, &installed)?;
+        let similar = local_model::similar_installed_model(&model_name, &installed);
+        Ok((model_name, similar))
+    }
+
+    /// Take the next chat message id
+    fn reserve_message_id(&mut self) -> u64 {
+        let id = self.next_message_id;
+        self.next_message_id += 1;
+        id
+    }
+
+    /// Add a message to the history, keeping it sorted by id
+    fn push_message(&mut self, mut message: ChatMessage) {
+        if let Some(model) = self.reply_models.remove(&message.id) {
+            message.model = Some(model);
+        }
+        let index = self.chat_history.partition_point(|m| m.id < message.id);
+        self.chat_history.insert(index, message);
+    }
+
+    /// Drop the chat history and the current image
+    fn clear_conversation(&mut self) {
+        self.chat_history.clear();
+        self.reply_models.clear();
+        self.last_analysis = None;
+        self.quick_look_reply = None;
+        self.comparison = None;
+        self.roi = None;
+        self.oversize_analysis = None;
+        let mut state_guard = self.state.lock().unwrap();
+        state_guard.current_image = None; 
+        state_guard.image_data.clear();
+        state_guard.ai_response.clear();
+        state_guard.finished_replies.clear();
+        info!("Chat history and current image cleared.");
+    }
+
+    fn max_payload_bytes(&self) -> usize {
+        self.config.max_payload_kb as usize * 1024
+    }
+
+    /// Whether to downscale the image before sending it. None when it is too large and the
+    /// user hasn't decided yet; the banner asking them is shown and the analysis is retried from there
+    fn payload_decision(&mut self, image_data: &[u8], prompt: Option<&str>) -> Option<bool> {
+        if let Some(downscale) = self.oversize_choice.take() {
+            return Some(downscale);
+        }
+        let encoded_bytes = screenshot::encoded_payload_size(image_data);
+        if encoded_bytes <= self.max_payload_bytes() {
+            return Some(false);
+        }
+        info!("Capture is {} bytes once encoded; asking before analyzing", encoded_bytes);
+        self.oversize_analysis = Some(OversizeAnalysis { prompt: prompt.map(str::to_string), encoded_bytes });
+        None
+    }
+
+    /// The easing to use for the sidebar, taking the OS reduced-motion preference into account
+    fn effective_easing(&self) -> Easing {
+        if self.reduced_motion && self.config.respect_reduced_motion {
+            Easing::Instant
+        } else {
+            self.config.animation_easing
+        }
+    }
+
+    fn draw_settings(&mut self, ui: &mut Ui) {
+        if !self.config.profiles.is_empty() {
+            let mut names: Vec<String> = self.config.profiles.keys().cloned().collect();
+            names.sort();
+            let mut chosen_profile: Option<Option<String>> = None;
             ui.horizontal(|ui| {
-                ui.with_layout(Layout::left_to_right(Align::Center), |ui| {
-                    ui.heading(RichText::new("ScreenSnap AI").size(22.0));
-                });
-                ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
-                    if ui.button(RichText::new("✕").size(16.0)).clicked() { 
-                        info!("Application exit button clicked from sidebar.");
-                        self.should_exit = true; // Set flag to exit
-                        ctx.request_repaint(); // Ensure update loop runs to process exit
+                ui.label("Profile:");
+                egui::ComboBox::from_id_source("profile_selector")
+                    .selected_text(self.config.applied_profile.as_deref().unwrap_or("None"))
+                    .show_ui(ui, |ui| {
+                        if ui.selectable_label(self.config.applied_profile.is_none(), "None").clicked() {
+                            chosen_profile = Some(None);
+                        }
+                        for name in names {
+                            let selected = self.config.applied_profile.as_ref() == Some(&name);
+                            if ui.selectable_label(selected, &name).clicked() {
+                                chosen_profile = Some(Some(name));
+                            }
+                        }
+                    });
+            });
+            if let Some(profile) = chosen_profile {
+                self.switch_profile(profile);
+            }
+        }
+
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            ui.label("Animation:");
+            egui::ComboBox::from_id_source("easing_selector")
+                .selected_text(self.config.animation_easing.label())
+                .show_ui(ui, |ui| {
+                    for easing in Easing::ALL {
+                        if ui.selectable_value(&mut self.config.animation_easing, easing, easing.label()).changed() {
+                            changed = true;
+                        }
                     }
                 });
-            });
-            ui.separator();
-            ui.add_space(8.0);
-            
-             ui.horizontal(|ui| {
-                let button_size = egui::vec2(ui.available_width() * 0.5 - 4.0, 36.0);
-                if ui.add_sized(button_size, egui::Button::new(
-                    RichText::new("📷 Capture Screen").size(14.0))
-                    .fill(Color32::from_rgb(45, 45, 45))
-                    .rounding(8.0)
-                ).clicked() {
-                    self.capture_full_screen();
+        });
+        ui.add_enabled_ui(self.config.animation_easing != Easing::Instant, |ui| {
+            let slider = egui::Slider::new(&mut self.config.animation_duration, 0.05..=1.0)
+                .text("seconds")
+                .fixed_decimals(2);
+            // Save once the user lets go of the slider, not on every drag step
+            let response = ui.add(slider);
+            if response.drag_released() || (response.changed() && !response.dragged()) {
+                changed = true;
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Quality:");
+            for quality in Quality::ALL {
+                if ui.selectable_value(&mut self.config.quality, quality, quality.label()).changed() {
+                    changed = true;
                 }
-                ui.add_space(8.0);
-                if ui.add_sized(button_size, egui::Button::new(
-                    RichText::new("🪟 Capture Window").size(14.0))
-                    .fill(Color32::from_rgb(45, 45, 45))
-                    .rounding(8.0)
-                ).clicked() {
-                    match get_window_titles() {
-                        Ok(list) => self.window_list = list,
-                        Err(e) => error!("Failed to get window list: {}", e),
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("PNG compression:");
+            egui::ComboBox::from_id_source("png_compression_selector")
+                .selected_text(self.config.png_compression.label())
+                .show_ui(ui, |ui| {
+                    for compression in screenshot::PngCompression::ALL {
+                        if ui.selectable_value(&mut self.config.png_compression, compression, compression.label()).changed() {
+                            changed = true;
+                        }
                     }
-                    if !self.window_list.is_empty() && self.selected_window.is_none() {
-                        self.selected_window = Some(self.window_list[0].clone());
+                });
+        });
+        #[cfg(feature = "clipboard")]
+        ui.horizontal(|ui| {
+            ui.label("Clipboard format:");
+            egui::ComboBox::from_id_source("clipboard_format_selector")
+                .selected_text(self.config.clipboard_format.label())
+                .show_ui(ui, |ui| {
+                    for format in ClipboardFormat::ALL {
+                        if ui.selectable_value(&mut self.config.clipboard_format, format, format.label()).changed() {
+                            changed = true;
+                        }
                     }
+                });
+        }).response.on_hover_text("Try PNG if pasting into some app gives a solid block or nothing");
+        let reduced_motion_label = if self.reduced_motion {
+            "Respect reduced motion (enabled in OS)"
+        } else {
+            "Respect reduced motion"
+        };
+        if ui.checkbox(&mut self.config.respect_reduced_motion, reduced_motion_label).changed() {
+            changed = true;
+        }
+        if ui.checkbox(&mut self.config.auto_analyze, "Analyze automatically after capture").changed() {
+            changed = true;
+        }
+        ui.horizontal(|ui| {
+            ui.label("Main capture:");
+            for target in CaptureTarget::ALL {
+                if ui.selectable_value(&mut self.config.default_capture, target, target.label()).changed() {
+                    changed = true;
                 }
-            });
+            }
+        });
+        if ui.checkbox(&mut self.config.trim_preamble, "Trim reply preambles")
+            .on_hover_text("Drop openers like \"Sure, here's what I see:\" from replies. More rewrite rules can be added as response_rules in the config file")
+            .changed() {
+            changed = true;
+        }
+        if ui.checkbox(&mut self.config.render_markdown, "Render markdown in replies")
+            .on_hover_text("Turn off to see exactly the text the model sent")
+            .changed() {
+            changed = true;
+        }
+        let delay_slider = egui::Slider::new(&mut self.config.capture_delay_ms, 0..=MAX_CAPTURE_DELAY_MS)
+            .text("ms extra capture delay");
+        let response = ui.add(delay_slider);
+        if response.drag_released() || (response.changed() && !response.dragged()) {
+            changed = true;
+        }
+        let idle_slider = egui::Slider::new(&mut self.config.sidebar_idle_timeout_secs, 0..=MAX_SIDEBAR_IDLE_SECS)
+            .text("s until the sidebar collapses (0 = never)");
+        let response = ui.add(idle_slider);
+        if response.drag_released() || (response.changed() && !response.dragged()) {
+            changed = true;
+        }
 
-            let mut wants_to_capture_selected_window = false;
-            let current_selection_display = self.selected_window.clone();
-            if let Some(selected_name_for_combo) = &current_selection_display {
-                ui.add_space(4.0);
-                egui::Frame::none()
-                    .fill(Color32::from_rgb(35, 35, 35))
-                    .rounding(8.0)
-                    .inner_margin(8.0)
-                    .show(ui, |ui| {
-                        ui.horizontal(|ui| {
-                            ui.label(RichText::new("Window:").size(14.0));
-                            let mut new_selection_from_combo_this_frame: Option<String> = None;
+        if changed {
+            self.save_config();
+        }
+    }
+
+    /// Colored dot and text for the Ollama server's health; clicking it checks again
+    fn draw_ollama_status(&self, ui: &mut Ui) {
+        let health = self.state.lock().unwrap().ollama_health.clone();
+        let (color, text, details) = match &health {
+            OllamaHealth::Checking => (Color32::from_rgb(130, 130, 130), "Checking Ollama...".to_string(), None),
+            OllamaHealth::Up(models) => {
+                // "llava" means "llava:latest" to Ollama
+                let installed = models.iter().any(|name| {
+                    *name == self.model_name || *name == format!("{}:latest", self.model_name)
+                });
+                if installed {
+                    (Color32::from_rgb(80, 190, 100), format!("Ollama ready · {}", self.model_name), None)
+                } else {
+                    (
+                        Color32::from_rgb(220, 180, 60),
+                        format!("{} isn't installed", self.model_name),
+                        Some(format!("Run: ollama pull {}", self.model_name)),
+                    )
+                }
+            }
+            OllamaHealth::Down(reason) => (Color32::from_rgb(210, 70, 70), "Ollama unreachable".to_string(), Some(reason.clone())),
+        };
+        let response = ui.horizontal(|ui| {
+            let (dot, _) = ui.allocate_exact_size(egui::vec2(10.0, 10.0), egui::Sense::hover());
+            ui.painter().circle_filled(dot.center(), 4.0, color);
+            ui.label(RichText::new(text).small().color(Color32::from_rgb(170, 170, 170)));
+        }).response.interact(egui::Sense::click());
+        let hover = match details {
+            Some(details) => format!("{}\n\nClick to check again", details),
+            None => "Click to check again".to_string(),
+        };
+        if response.on_hover_text(hover).clicked() {
+            let mut state_guard = self.state.lock().unwrap();
+            state_guard.ollama_health = OllamaHealth::Checking;
+            state_guard.health_recheck_requested = true;
+        }
+
+        let (has_vision_model, model_pull, model_pull_error) = {
+            let state_guard = self.state.lock().unwrap();
+            let model_pull = state_guard.model_pull.as_ref().map(|pull| (pull.progress.clone(), pull.cancel.load(Ordering::Relaxed)));
+            (state_guard.has_vision_model, model_pull, state_guard.model_pull_error.clone())
+        };
+        if matches!(health, OllamaHealth::Up(_)) && has_vision_model == Some(false) {
+            ui.add_space(4.0);
+            egui::Frame::none()
+                .fill(Color32::from_rgb(70, 55, 20))
+                .rounding(8.0)
+                .inner_margin(8.0)
+                .show(ui, |ui| {
+                    ui.label(RichText::new("⚠ No vision model installed, so captures can't be analyzed").size(14.0));
+                    match model_pull {
+                        Some((_, true)) => {
+                            ui.horizontal(|ui| {
+                                ui.spinner();
+                                ui.label(RichText::new("Cancelling download...").small());
+                            });
+                        }
+                        Some((progress, false)) => {
+                            match (progress.fraction(), progress.completed, progress.total) {
+                                (Some(fraction), Some(completed), Some(total)) => {
+                                    const GIB: f64 = 1024.0 * 1024.0 * 1024.0;
+                                    ui.add(egui::ProgressBar::new(fraction).text(format!(
+                                        "{:.2} / {:.2} GB",
+                                        completed as f64 / GIB,
+                                        total as f64 / GIB,
+                                    )));
+                                    ui.label(RichText::new(&progress.status).small());
+                                }
+                                _ => {
+                                    ui.horizontal(|ui| {
+                                        ui.spinner();
+                                        ui.label(RichText::new(&progress.status).small());
+                                    });
+                                }
+                            }
+                            if ui.button("✖ Cancel download").clicked() {
+                                if let Some(pull) = &self.state.lock().unwrap().model_pull {
+                                    pull.cancel.store(true, Ordering::Relaxed);
+                                }
+                            }
+                        }
+                        None => {
+                            if ui.button(format!("⬇ Download {}", local_model::RECOMMENDED_VISION_MODEL)).clicked() {
+                                self.download_vision_model();
+                            }
+                            ui.label(RichText::new(format!("or run: ollama pull {}", local_model::RECOMMENDED_VISION_MODEL)).small());
+                        }
+                    }
+                    if let Some(error) = model_pull_error {
+                        ui.label(RichText::new(format!("Download failed: {}", error)).small().color(Color32::from_rgb(210, 70, 70)));
+                    }
+                });
+        }
+    }
+
+    /// Pull the recommended vision model in the background, showing its progress in the status banner
+    fn download_vision_model(&self) {
+        let cancel = Arc::new(AtomicBool::new(false));
+        {
+            let mut state_guard = self.state.lock().unwrap();
+            let progress = local_model::PullProgress { status: "Starting download...".to_string(), ..Default::default() };
+            state_guard.model_pull = Some(ModelPull { progress, cancel: Arc::clone(&cancel) });
+            state_guard.model_pull_error = None;
+        }
+        let ollama_host_url_str = self.ollama_url();
+        let state = Arc::clone(&self.state);
+        let repaint_ctx = self.egui_ctx.clone();
+        thread::spawn(move || {
+            std::env::set_var("OLLAMA_HOST", &ollama_host_url_str);
+            let result = LocalModel::new(local_model::RECOMMENDED_VISION_MODEL).and_then(|ai_model| {
+                ai_model.pull(&cancel, |update| {
+                    if let Some(pull) = &mut state.lock().unwrap().model_pull {
+                        pull.progress = update.clone();
+                    }
+                    repaint_ctx.request_repaint();
+                })
+            });
+            {
+                let mut state_guard = state.lock().unwrap();
+                state_guard.model_pull = None;
+                match result {
+                    Ok(()) => state_guard.health_recheck_requested = true,
+                    Err(_) if cancel.load(Ordering::Relaxed) => info!("Download of {} cancelled", local_model::RECOMMENDED_VISION_MODEL),
+                    Err(e) => {
+                        error!("Failed to pull {}: {}", local_model::RECOMMENDED_VISION_MODEL, e);
+                        state_guard.model_pull_error = Some(e.to_string());
+                    }
+                }
+            }
+            repaint_ctx.request_repaint();
+        });
+    }
+
+    /// Saved prompts: picking one fills the chat box, and the chat box text can be saved under a new name
+    fn draw_prompt_library(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            let mut chosen_prompt: Option<String> = None;
+            egui::ComboBox::from_id_source("saved_prompt_selector")
+                .selected_text("Saved prompts")
+                .width(ui.available_width() * 0.45)
+                .show_ui(ui, |ui| {
+                    if self.prompt_library.prompts.is_empty() {
+                        ui.label(RichText::new("None saved yet").small());
+                    }
+                    for (name, prompt) in &self.prompt_library.prompts {
+                        if ui.selectable_label(false, name).on_hover_text(prompt).clicked() {
+                            chosen_prompt = Some(prompt.clone());
+                        }
+                    }
+                });
+            if let Some(prompt) = chosen_prompt {
+                self.current_input = prompt;
+            }
+
+            ui.add(egui::TextEdit::singleline(&mut self.new_prompt_name)
+                .hint_text("Name")
+                .desired_width(ui.available_width() - 50.0));
+            let can_save = !self.new_prompt_name.trim().is_empty() && !self.current_input.trim().is_empty();
+            if ui.add_enabled(can_save, egui::Button::new("Save"))
+                .on_hover_text("Save the chat box text to the prompt library under this name")
+                .clicked() {
+                let saved = self.prompt_library.add(&self.new_prompt_name, self.current_input.trim())
+                    .and_then(|_| self.prompt_library.save());
+                match saved {
+                    Ok(()) => {
+                        info!("Saved prompt '{}'", self.new_prompt_name.trim());
+                        self.new_prompt_name.clear();
+                    }
+                    Err(e) => error!("Failed to save prompt: {}", e),
+                }
+            }
+        });
+    }
+
+    /// Make a profile the active one and reload the settings with it applied
+    fn switch_profile(&mut self, profile: Option<String>) {
+        let mut file_config = Config::load_file();
+        file_config.active_profile = profile.clone();
+        if let Err(e) = file_config.save() {
+            error!("Failed to switch profile: {}", e);
+            return;
+        }
+        // Otherwise a --profile given at launch would keep winning over the choice
+        match &profile {
+            Some(name) => std::env::set_var(config::PROFILE_ENV, name),
+            None => std::env::remove_var(config::PROFILE_ENV),
+        }
+        // Requests set OLLAMA_HOST to the old server, which would win over the profile's ollama_url
+        match &self.launch_ollama_host {
+            Some(host) => std::env::set_var("OLLAMA_HOST", host),
+            None => std::env::remove_var("OLLAMA_HOST"),
+        }
+        self.config = Config::load();
+        self.model_name = self.config.resolve_model(None).value;
+        let ollama_url = self.config.resolve_ollama_url(None).value;
+        {
+            let mut state_guard = self.state.lock().unwrap();
+            state_guard.ollama_url = ollama_url;
+            state_guard.ollama_health = OllamaHealth::Checking;
+            state_guard.health_recheck_requested = true;
+        }
+        info!("Switched to profile {}", profile.as_deref().unwrap_or("(none)"));
+    }
+
+    /// Ollama server for requests started now
+    fn ollama_url(&self) -> String {
+        self.state.lock().unwrap().ollama_url.clone()
+    }
+
+    fn save_config(&self) {
+        if let Err(e) = self.config.save() {
+            error!("Failed to save settings: {}", e);
+        }
+    }
+
+    fn draw_sidebar_contents(&mut self, frame_ui: &mut Ui, ctx: &egui::Context) {
+        let top_section_response = frame_ui.vertical(|ui| {
+            ui.add_space(10.0);
+            ui.horizontal(|ui| {
+                ui.with_layout(Layout::left_to_right(Align::Center), |ui| {
+                    ui.heading(RichText::new("ScreenSnap AI").size(22.0));
+                });
+                ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                    if ui.button(RichText::new("✕").size(16.0)).clicked() { 
+                        info!("Application exit button clicked from sidebar.");
+                        self.should_exit = true; // Set flag to exit
+                        ctx.request_repaint(); // Ensure update loop runs to process exit
+                    }
+                });
+            });
+            self.draw_ollama_status(ui);
+            ui.separator();
+            self.draw_pinned_notes(ui);
+            ui.add_space(8.0);
+            
+            // Greyed out while a capture is running; a click then would be ignored anyway
+            let capturing = self.state.lock().unwrap().capturing;
+             ui.horizontal(|ui| {
+                ui.set_enabled(!capturing);
+                let button_size = egui::vec2(ui.available_width() * 0.5 - 4.0, 36.0);
+                // The default capture stands out
+                let fill = |target: CaptureTarget| if self.config.default_capture == target {
+                    Color32::from_rgb(42, 90, 170)
+                } else {
+                    Color32::from_rgb(45, 45, 45)
+                };
+                let (screen_fill, window_fill) = (fill(CaptureTarget::Screen), fill(CaptureTarget::Window));
+                if ui.add_sized(button_size, egui::Button::new(
+                    RichText::new("📷 Capture Screen").size(14.0))
+                    .fill(screen_fill)
+                    .rounding(8.0)
+                ).on_hover_text("Capture the full screen (Ctrl+N)").clicked() {
+                    self.capture_full_screen();
+                }
+                ui.add_space(8.0);
+                if ui.add_sized(button_size, egui::Button::new(
+                    RichText::new("🪟 Capture Window").size(14.0))
+                    .fill(window_fill)
+                    .rounding(8.0)
+                ).on_hover_text("Pick a window to capture (Ctrl+W)").clicked() {
+                    self.open_window_picker();
+                }
+            });
+            ui.add_space(4.0);
+            ui.horizontal(|ui| {
+                ui.set_enabled(!capturing);
+                let button_size = egui::vec2(ui.available_width() * 0.5 - 4.0, 28.0);
+                if ui.add_sized(button_size, egui::Button::new(
+                    RichText::new("⚡ Quick Capture").size(14.0))
+                    .fill(Color32::from_rgb(45, 45, 45))
+                    .rounding(8.0)
+                ).on_hover_text("Capture the full screen right away, without the delay (Ctrl+Shift+N)").clicked() {
+                    self.quick_capture();
+                }
+                ui.add_space(8.0);
+                if ui.add_sized(button_size, egui::Button::new(
+                    RichText::new("🖱 Cursor Monitor").size(14.0))
+                    .fill(Color32::from_rgb(45, 45, 45))
+                    .rounding(8.0)
+                ).on_hover_text("Capture the whole monitor the mouse is on").clicked() {
+                    self.capture_cursor_monitor();
+                }
+            });
+            ui.add_space(4.0);
+            ui.horizontal(|ui| {
+                ui.set_enabled(!capturing);
+                if ui.add_sized([ui.available_width(), 28.0], egui::Button::new(
+                    RichText::new("🔍 Cursor Area").size(14.0))
+                    .fill(Color32::from_rgb(45, 45, 45))
+                    .rounding(8.0)
+                ).on_hover_text(format!("Capture a {0}x{0} square around the mouse, for tooltips and small errors (Ctrl+Shift+A)", CURSOR_AREA_RADIUS * 2)).clicked() {
+                    self.capture_cursor_area();
+                }
+            });
+
+            #[cfg(feature = "clipboard")]
+            {
+                ui.add_space(4.0);
+                ui.horizontal(|ui| {
+                    if ui.add_sized([ui.available_width(), 28.0], egui::Button::new(
+                        RichText::new("📋 Paste Image").size(14.0))
+                        .fill(Color32::from_rgb(45, 45, 45))
+                        .rounding(8.0)
+                    ).on_hover_text("Analyze an image from the clipboard (Ctrl+V)").clicked() {
+                        self.paste_image_from_clipboard();
+                    }
+                });
+            }
+
+            if let Some(window_list_error) = self.window_list_error.clone() {
+                ui.add_space(4.0);
+                let mut retry = false;
+                egui::Frame::none()
+                    .fill(Color32::from_rgb(70, 55, 20))
+                    .rounding(8.0)
+                    .inner_margin(8.0)
+                    .show(ui, |ui| {
+                        ui.label(RichText::new(format!("⚠ Couldn't list windows: {}", window_list_error)).size(14.0));
+                        if ui.button("Retry").clicked() {
+                            retry = true;
+                        }
+                    });
+                if retry {
+                    self.open_window_picker();
+                }
+            }
+
+            let mut wants_to_capture_selected_window = false;
+            let current_selection_display = self.selected_window.clone();
+            if let Some(selected_name_for_combo) = &current_selection_display {
+                ui.add_space(4.0);
+                egui::Frame::none()
+                    .fill(Color32::from_rgb(35, 35, 35))
+                    .rounding(8.0)
+                    .inner_margin(8.0)
+                    .show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label(RichText::new("Window:").size(14.0));
+                            let mut new_selection_from_combo_this_frame: Option<String> = None;
+                            let mut hovered_window: Option<String> = None;
                             egui::ComboBox::from_id_source("window_selector")
                                 .selected_text(selected_name_for_combo.as_str())
                                 .width(ui.available_width() - 90.0)
@@ -372,18 +1542,23 @@ impl ScreenSnapApp {
                                         } else {
                                             window_title.clone()
                                         };
-                                        if ui.selectable_label(is_selected, truncated).clicked() {
+                                        let item = ui.selectable_label(is_selected, truncated);
+                                        if item.hovered() {
+                                            hovered_window = Some(window_title.clone());
+                                        }
+                                        if item.clicked() {
                                             new_selection_from_combo_this_frame = Some(window_title.clone());
                                         }
                                     }
                                 });
+                            self.flash_window(hovered_window);
                             if let Some(new_sel) = new_selection_from_combo_this_frame {
                                 self.selected_window = Some(new_sel);
                             }
-                            if ui.add_sized([80.0, 24.0], egui::Button::new("Capture")
+                            if ui.add_enabled_ui(!capturing, |ui| ui.add_sized([80.0, 24.0], egui::Button::new("Capture")
                                 .fill(Color32::from_rgb(42, 90, 170))
                                 .rounding(4.0)
-                            ).clicked() {
+                            )).inner.clicked() {
                                 if self.selected_window.is_some() {
                                     wants_to_capture_selected_window = true;
                                 }
@@ -391,17 +1566,112 @@ impl ScreenSnapApp {
                         });
                     });
             }
+            self.draw_window_flash(ctx);
             if wants_to_capture_selected_window {
                 self.capture_selected_window();
             }
 
+            let looks_blank = self.state.lock().unwrap().looks_blank;
+            if looks_blank {
+                ui.add_space(4.0);
+                let mut retry = false;
+                let mut dismiss = false;
+                egui::Frame::none()
+                    .fill(Color32::from_rgb(70, 55, 20))
+                    .rounding(8.0)
+                    .inner_margin(8.0)
+                    .show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label(RichText::new("⚠ Looks blank — retry?").size(14.0));
+                            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                                if ui.button("Dismiss").clicked() {
+                                    dismiss = true;
+                                }
+                                if ui.button("Retry").clicked() {
+                                    retry = true;
+                                }
+                            });
+                        });
+                    });
+                if retry {
+                    self.retry_capture();
+                } else if dismiss {
+                    self.state.lock().unwrap().looks_blank = false;
+                }
+            }
+
+            if let Some(encoded_bytes) = self.oversize_analysis.as_ref().map(|pending| pending.encoded_bytes) {
+                ui.add_space(4.0);
+                // Some(true) downscale, Some(false) send as is, None cancel
+                let mut decision: Option<Option<bool>> = None;
+                egui::Frame::none()
+                    .fill(Color32::from_rgb(70, 55, 20))
+                    .rounding(8.0)
+                    .inner_margin(8.0)
+                    .show(ui, |ui| {
+                        ui.label(RichText::new(format!(
+                            "⚠ This capture is {:.1} MB once encoded and may time out.",
+                            encoded_bytes as f64 / 1048576.0
+                        )).size(14.0));
+                        ui.horizontal(|ui| {
+                            if ui.button("Downscale").clicked() {
+                                decision = Some(Some(true));
+                            }
+                            if ui.button("Send anyway").clicked() {
+                                decision = Some(Some(false));
+                            }
+                            if ui.button("Cancel").clicked() {
+                                decision = Some(None);
+                            }
+                        });
+                    });
+                if let Some(decision) = decision {
+                    let pending = self.oversize_analysis.take();
+                    if let (Some(downscale), Some(pending)) = (decision, pending) {
+                        self.oversize_choice = Some(downscale);
+                        match pending.prompt {
+                            Some(prompt) => self.analyze_with_prompt(prompt),
+                            None => self.analyze_image(),
+                        }
+                    }
+                }
+            }
+
             ui.add_space(8.0);
             let mut should_analyze = false;
+            let mut should_annotate = false;
+            let mut should_quick_look = false;
+            let mut should_describe_changes = false;
             egui::Frame::none()
                 .fill(Color32::from_rgb(35, 35, 35))
                 .rounding(8.0)
                 .inner_margin(8.0)
                 .show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        let mut details = format!("Prompt: {}", local_model::DEFAULT_PROMPT);
+                        if let Some(system_prompt) = &self.config.system_prompt {
+                            details.push_str(&format!("\n\nSystem prompt: {}", system_prompt));
+                        }
+                        let preview = truncate_chars(local_model::DEFAULT_PROMPT, PROMPT_PREVIEW_CHARS);
+                        let label = if self.config.system_prompt.is_some() {
+                            format!("Prompt: {} (+ system prompt)", preview)
+                        } else {
+                            format!("Prompt: {}", preview)
+                        };
+                        ui.label(RichText::new(label).small().color(Color32::from_rgb(150, 150, 150)))
+                            .on_hover_text(details);
+                        if ui.small_button("✏").on_hover_text("Edit this prompt in the chat box and send it").clicked() {
+                            self.current_input = local_model::DEFAULT_PROMPT.to_string();
+                        }
+                    });
+                    if ui.checkbox(&mut self.live_prompt, "Live prompt")
+                        .on_hover_text("Re-run the analysis whenever you pause typing in the chat box. Every pause sends a request, so this keeps the model busy")
+                        .changed() && !self.live_prompt {
+                        self.stop_live_prompt();
+                    }
+                    ui.checkbox(&mut self.follow_up, "Follow-up questions")
+                        .on_hover_text("Send chat messages with the earlier answers as context, so you can ask \"and what about the menu?\". Off: every message is a fresh analysis");
+                    self.draw_prompt_library(ui);
                     ui.horizontal(|ui| {
                         ui.label(RichText::new("Model:").size(14.0));
                         let current_model_name_for_combo = self.model_name.clone();
@@ -411,7 +1681,12 @@ impl ScreenSnapApp {
                             .show_ui(ui, |ui| {
                                 for model_choice in &["llava:latest", "llava:13b", "llava:7b"] {
                                     if ui.selectable_label(self.model_name == *model_choice, *model_choice).clicked() {
-                                        self.model_name = model_choice.to_string();
+                                        self.model_name = self.resolve_model_name(model_choice)
+                                            .map(|(model_name, _)| model_name)
+                                            .unwrap_or_else(|e| {
+                                                warn!("{}", e);
+                                                model_choice.to_string()
+                                            });
                                     }
                                 }
                             });
@@ -426,15 +1701,79 @@ impl ScreenSnapApp {
                                 RichText::new("🤖 Analyze").size(14.0))
                                 .fill(Color32::from_rgb(42, 90, 170))
                                 .rounding(4.0)
-                            ).clicked() {
+                            ).on_hover_text("Analyze the screenshot (Ctrl+Enter)").clicked() {
                                 should_analyze = true;
                             }
                         }
                     });
+                    let can_annotate = {
+                        let state_guard = self.state.lock().unwrap();
+                        !state_guard.processing && !state_guard.image_data.is_empty()
+                    };
+                    if can_annotate {
+                        ui.add_space(4.0);
+                        if ui.add_sized([ui.available_width(), 28.0], egui::Button::new(
+                            RichText::new("🎯 Annotate UI Elements").size(14.0))
+                            .fill(Color32::from_rgb(45, 45, 45))
+                            .rounding(4.0)
+                        ).on_hover_text("Ask the model to locate UI elements and draw boxes around them").clicked() {
+                            should_annotate = true;
+                        }
+                        ui.add_space(4.0);
+                        if ui.add_sized([ui.available_width(), 28.0], egui::Button::new(
+                            RichText::new("⚡ Quick Look").size(14.0))
+                            .fill(Color32::from_rgb(45, 45, 45))
+                            .rounding(4.0)
+                        ).on_hover_text("Get a fast, rough description from a small thumbnail, then ask for the full analysis if you need it").clicked() {
+                            should_quick_look = true;
+                        }
+                        // try_lock so a capture in progress doesn't stall the UI
+                        let has_previous_capture = self.screenshot_manager.try_lock()
+                            .map_or(false, |manager| !manager.history().is_empty());
+                        if has_previous_capture {
+                            ui.add_space(4.0);
+                            if ui.add_sized([ui.available_width(), 28.0], egui::Button::new(
+                                RichText::new("🔍 What Changed?").size(14.0))
+                                .fill(Color32::from_rgb(45, 45, 45))
+                                .rounding(4.0)
+                            ).on_hover_text("Ask the model to compare the last two captures").clicked() {
+                                should_describe_changes = true;
+                            }
+                        }
+                        #[cfg(all(feature = "ocr", feature = "clipboard"))]
+                        {
+                            ui.add_space(4.0);
+                            if ui.add_sized([ui.available_width(), 28.0], egui::Button::new(
+                                RichText::new("📝 Copy Text").size(14.0))
+                                .fill(Color32::from_rgb(45, 45, 45))
+                                .rounding(4.0)
+                            ).on_hover_text("Read the text in the capture with OCR and copy it, without asking the model").clicked() {
+                                self.copy_text_from_capture();
+                            }
+                        }
+                    }
+                    #[cfg(all(feature = "ocr", feature = "clipboard"))]
+                    self.draw_ocr_toast(ui);
                 });
             if should_analyze {
                 self.analyze_image();
             }
+            if should_annotate {
+                self.annotate_image();
+            }
+            if should_quick_look {
+                self.quick_look();
+            }
+            if should_describe_changes {
+                self.describe_changes();
+            }
+
+            ui.add_space(8.0);
+            egui::CollapsingHeader::new(RichText::new("⚙ Settings").size(14.0))
+                .id_source("settings")
+                .show(ui, |ui| {
+                    self.draw_settings(ui);
+                });
         }).response; 
 
 
@@ -462,6 +1801,11 @@ impl ScreenSnapApp {
             ));
         }
 
+        let finished_replies = std::mem::take(&mut self.state.lock().unwrap().finished_replies);
+        for reply in finished_replies {
+            self.push_message(reply);
+        }
+
         let (texture_handle_clone, ai_response_cloned, processing_cloned, is_image_texture_available) = {
             let state_guard = self.state.lock().unwrap();
             (
@@ -471,6 +1815,23 @@ impl ScreenSnapApp {
                 state_guard.current_image.is_some()
             )
         };
+        let (live_preview, live_processing, installed_models) = {
+            let state_guard = self.state.lock().unwrap();
+            let installed_models = match &state_guard.ollama_health {
+                OllamaHealth::Up(models) => models.clone(),
+                _ => Vec::new(),
+            };
+            (state_guard.live_preview.clone(), state_guard.live_processing, installed_models)
+        };
+        
+        // First run: nothing captured yet. try_lock so a capture in progress just hides the placeholder
+        let show_placeholder = texture_handle_clone.is_none() && self.screenshot_manager.try_lock()
+            .map_or(false, |manager| manager.get_current_image().is_none() && manager.history().is_empty());
+        // The crop of the current capture, and whether it was a window
+        let crop = self.screenshot_manager.try_lock().ok().and_then(|manager| {
+            let is_window = manager.current_capture_source().and_then(|source| source.window_title()).is_some();
+            manager.crop_rect().map(|rect| (rect, is_window))
+        });
         
         let full_sidebar_rect = frame_ui.max_rect(); 
         let top_section_bottom = top_section_response.rect.bottom();
@@ -500,7 +1861,29 @@ impl ScreenSnapApp {
                             let aspect_ratio = texture.size_vec2().x / texture.size_vec2().y;
                             let image_height = if aspect_ratio > 0.0 { available_width / aspect_ratio } else { available_width };
                             let image_size = Vec2::new(available_width, image_height);
-                            inner_scroll_ui.image((texture.id(), image_size));
+                            self.draw_image_viewer(inner_scroll_ui, texture, image_size);
+                            if self.roi.is_some() {
+                                inner_scroll_ui.horizontal(|h_ui| {
+                                    h_ui.label(RichText::new("Analysis focuses on the marked region").small().color(Color32::from_rgb(150, 150, 150)));
+                                    if h_ui.small_button("✂ Crop").on_hover_text("Keep only the marked region, so the model sees nothing else").clicked() {
+                                        self.crop_to_roi();
+                                    }
+                                    if h_ui.small_button("Clear region").clicked() {
+                                        self.roi = None;
+                                    }
+                                });
+                            }
+                            if let Some((rect, is_window)) = crop {
+                                inner_scroll_ui.horizontal(|h_ui| {
+                                    let whole = if is_window { "window" } else { "capture" };
+                                    h_ui.label(RichText::new(format!("Cropped to {}x{} of the {}", rect.width, rect.height, whole))
+                                        .small().color(Color32::from_rgb(150, 150, 150)));
+                                    if h_ui.small_button(format!("⤢ Whole {}", whole)).clicked() {
+                                        self.uncrop_capture();
+                                    }
+                                });
+                            }
+                            self.draw_comparison(inner_scroll_ui, available_width);
                             inner_scroll_ui.horizontal(|h_ui| {
                                 if h_ui.add_sized([h_ui.available_width() * 0.5 - 4.0, 32.0], 
                                     egui::Button::new(RichText::new("💾 Save Image").size(14.0))
@@ -515,6 +1898,46 @@ impl ScreenSnapApp {
                                     self.copy_image_to_clipboard();
                                 }
                             });
+                            inner_scroll_ui.add_space(4.0);
+                            if inner_scroll_ui.add_sized([inner_scroll_ui.available_width(), 32.0],
+                                egui::Button::new(RichText::new("📦 Save Bundle").size(14.0))
+                                .fill(Color32::from_rgb(45, 45, 45)).rounding(6.0))
+                                .on_hover_text("Save the image, the latest AI response and its details into a folder").clicked() {
+                                if let Some(dir) = rfd::FileDialog::new().pick_folder() {
+                                    self.save_bundle(dir);
+                                }
+                            }
+                            inner_scroll_ui.add_space(8.0);
+                        } else if show_placeholder {
+                            inner_scroll_ui.add_space(12.0);
+                            egui::Frame::none()
+                                .fill(Color32::from_rgb(35, 35, 35))
+                                .rounding(8.0)
+                                .inner_margin(16.0)
+                                .show(inner_scroll_ui, |ui| {
+                                    ui.vertical_centered(|ui| {
+                                        ui.label(RichText::new("No screenshot yet").size(16.0));
+                                        ui.add_space(8.0);
+                                        let label = match self.config.default_capture {
+                                            CaptureTarget::Screen => "📷 Capture Screen",
+                                            CaptureTarget::Window => "🪟 Capture Window",
+                                        };
+                                        if ui.add_enabled_ui(!capturing, |ui| ui.add_sized([ui.available_width(), 44.0], egui::Button::new(
+                                            RichText::new(label).size(18.0))
+                                            .fill(Color32::from_rgb(42, 90, 170))
+                                            .rounding(8.0)
+                                        )).inner.clicked() {
+                                            match self.config.default_capture {
+                                                CaptureTarget::Screen => self.capture_full_screen(),
+                                                CaptureTarget::Window => self.open_window_picker(),
+                                            }
+                                        }
+                                        ui.add_space(8.0);
+                                        ui.label(RichText::new("Tip: press Ctrl+N to capture the screen or Ctrl+W to pick a window, then Analyze or ask a question below.")
+                                            .small()
+                                            .color(Color32::from_rgb(150, 150, 150)));
+                                    });
+                                });
                             inner_scroll_ui.add_space(8.0);
                         }
 
@@ -522,8 +1945,14 @@ impl ScreenSnapApp {
                             inner_scroll_ui.add_space(8.0);
                             inner_scroll_ui.heading(RichText::new("Chat History").size(18.0));
                             inner_scroll_ui.add_space(8.0);
+                            let mut pinned = None;
                             for message in &self.chat_history {
-                                self.draw_chat_message(inner_scroll_ui, message);
+                                if self.draw_chat_message(inner_scroll_ui, message) {
+                                    pinned = Some(message.clone());
+                                }
+                            }
+                            if let Some(message) = pinned {
+                                self.pin_message(&message);
                             }
                         }
 
@@ -531,12 +1960,59 @@ impl ScreenSnapApp {
                             let is_new_ai_message = self.chat_history.last().map_or(true, |m| m.text != ai_response_cloned || m.is_user);
                             if is_new_ai_message && self.chat_history.is_empty() { inner_scroll_ui.add_space(8.0); inner_scroll_ui.heading(RichText::new("AI Response").size(18.0)); inner_scroll_ui.add_space(5.0); }
                             else if is_new_ai_message { inner_scroll_ui.add_space(5.0); }
-                            let ai_message_for_display = ChatMessage { text: ai_response_cloned.clone(), is_user: false, timestamp: chrono::Local::now() };
+                            let mut ai_message_for_display = ChatMessage { id: 0, text: ai_response_cloned.clone(), is_user: false, timestamp: chrono::Local::now(), model: None };
                             self.draw_chat_message(inner_scroll_ui, &ai_message_for_display);
                             if !processing_cloned && is_new_ai_message {
-                                self.chat_history.push(ai_message_for_display.clone());
-                                let mut state_guard = self.state.lock().unwrap();
-                                if state_guard.ai_response == ai_response_cloned { state_guard.ai_response.clear(); }
+                                let reserved_id = {
+                                    let mut state_guard = self.state.lock().unwrap();
+                                    if state_guard.ai_response == ai_response_cloned { state_guard.ai_response.clear(); }
+                                    state_guard.response_id.take()
+                                };
+                                ai_message_for_display.id = match reserved_id {
+                                    Some(id) => id,
+                                    None => self.reserve_message_id(),
+                                };
+                                self.push_message(ai_message_for_display);
+                            }
+                        }
+
+                        if self.quick_look_reply.is_some() && !processing_cloned {
+                            if inner_scroll_ui.button("🔬 Full analysis")
+                                .on_hover_text("That was a quick look at a thumbnail; analyze the capture at full resolution")
+                                .clicked() {
+                                self.analyze_image();
+                            }
+                        }
+
+                        if self.last_analysis.is_some() && !processing_cloned && !installed_models.is_empty() {
+                            let mut retry_model = None;
+                            egui::ComboBox::from_id_source("retry_model_selector")
+                                .selected_text("Retry with model…")
+                                .show_ui(inner_scroll_ui, |ui| {
+                                    for model in &installed_models {
+                                        if ui.selectable_label(false, model).clicked() {
+                                            retry_model = Some(model.clone());
+                                        }
+                                    }
+                                })
+                                .response
+                                .on_hover_text("Ask another model about the same image and prompt");
+                            if let Some(model) = retry_model {
+                                self.rerun_with_model(model);
+                            }
+                        }
+
+                        if self.live_prompt && (live_processing || !live_preview.is_empty()) {
+                            inner_scroll_ui.add_space(5.0);
+                            inner_scroll_ui.horizontal(|h_ui| {
+                                h_ui.label(RichText::new("Live preview").small().color(Color32::from_rgb(150, 150, 150)));
+                                if live_processing {
+                                    h_ui.spinner();
+                                }
+                            });
+                            if !live_preview.is_empty() {
+                                let preview_message = ChatMessage { id: 0, text: live_preview, is_user: false, timestamp: chrono::Local::now(), model: None };
+                                self.draw_chat_message(inner_scroll_ui, &preview_message);
                             }
                         }
                     });
@@ -552,21 +2028,91 @@ impl ScreenSnapApp {
                 self.draw_modern_chat_input(input_ui);
             });
         }
+
+        if self.show_intro {
+            self.draw_intro(ctx, full_sidebar_rect);
+        }
     }
 
-    fn draw_chat_message(&self, ui: &mut Ui, message: &ChatMessage) {
-        let (bubble_color, text_color, name_text, name_color) = if message.is_user {
-            (Color32::from_rgb(42, 90, 170), Color32::WHITE, "You", Color32::from_rgb(220, 220, 220))
-        } else {
-            (Color32::from_rgb(50, 50, 50), Color32::WHITE, "AI", Color32::from_rgb(180, 180, 180))
-        };
-        let layout_alignment = if message.is_user { Align::RIGHT } else { Align::LEFT };
-        ui.with_layout(Layout::top_down(layout_alignment), |ui| {
-            let time_str = message.timestamp.format("%H:%M").to_string();
-            ui.horizontal(|ui| {
-                if !message.is_user {
+    /// One-time welcome over the sidebar explaining the two kinds of capture and picking the main one.
+    /// Either button saves the settings, so the config file exists and it isn't shown again
+    fn draw_intro(&mut self, ctx: &egui::Context, sidebar_rect: egui::Rect) {
+        let mut dismissed = false;
+        egui::Area::new("first_run_intro")
+            .order(Order::Foreground)
+            .fixed_pos(sidebar_rect.min + egui::vec2(16.0, 80.0))
+            .show(ctx, |ui| {
+                egui::Frame::none()
+                    .fill(Color32::from_rgb(35, 35, 35))
+                    .stroke(Stroke::new(1.0, Color32::from_rgb(70, 70, 70)))
+                    .rounding(12.0)
+                    .inner_margin(16.0)
+                    .show(ui, |ui| {
+                        ui.set_width(sidebar_rect.width() - 64.0);
+                        ui.heading(RichText::new("Welcome to ScreenSnap").size(20.0));
+                        ui.add_space(8.0);
+                        ui.label(RichText::new("📷 Capture Screen").strong());
+                        ui.label("Takes everything on your screen. Good for questions about what you see as a whole.");
+                        ui.add_space(6.0);
+                        ui.label(RichText::new("🪟 Capture Window").strong());
+                        ui.label("Lets you pick one window, so the model only sees that app.");
+                        ui.add_space(10.0);
+                        ui.horizontal(|ui| {
+                            ui.label("Main capture:");
+                            for target in CaptureTarget::ALL {
+                                ui.selectable_value(&mut self.config.default_capture, target, target.label());
+                            }
+                        });
+                        ui.label(RichText::new("You can change this later in Settings.").small().color(Color32::from_rgb(150, 150, 150)));
+                        ui.add_space(10.0);
+                        ui.horizontal(|ui| {
+                            if ui.add(egui::Button::new("Get started").fill(Color32::from_rgb(42, 90, 170)).rounding(6.0)).clicked() {
+                                dismissed = true;
+                            }
+                            if ui.button("Skip").clicked() {
+                                self.config.default_capture = CaptureTarget::Screen;
+                                dismissed = true;
+                            }
+                        });
+                    });
+            });
+        if dismissed {
+            info!("First-run intro dismissed; main capture: {}", self.config.default_capture.label());
+            self.show_intro = false;
+            self.save_config();
+        }
+    }
+
+    /// Draw one chat bubble. Returns true when the pin button on an analysis reply was clicked
+    fn draw_chat_message(&self, ui: &mut Ui, message: &ChatMessage) -> bool {
+        let mut pin = false;
+        let (bubble_color, text_color, name_text, name_color) = if message.is_user {
+            (Color32::from_rgb(42, 90, 170), Color32::WHITE, "You".to_string(), Color32::from_rgb(220, 220, 220))
+        } else {
+            let name_text = match &message.model {
+                Some(model) => format!("AI · {}", model),
+                None => "AI".to_string(),
+            };
+            (Color32::from_rgb(50, 50, 50), Color32::WHITE, name_text, Color32::from_rgb(180, 180, 180))
+        };
+        let layout_alignment = if message.is_user { Align::RIGHT } else { Align::LEFT };
+        ui.with_layout(Layout::top_down(layout_alignment), |ui| {
+            let time_str = message.timestamp.format("%H:%M").to_string();
+            ui.horizontal(|ui| {
+                if !message.is_user {
                     ui.label(RichText::new(name_text).color(name_color).small());
                     ui.label(RichText::new(time_str).color(Color32::from_rgb(130, 130, 130)).small());
+                    #[cfg(feature = "clipboard")]
+                    if ui.small_button("📋").on_hover_text("Copy the reply exactly as the model wrote it").clicked() {
+                        match Clipboard::new().and_then(|mut clipboard| clipboard.set_text(message.text.clone())) {
+                            Ok(()) => info!("Reply copied to clipboard"),
+                            Err(e) => error!("Failed to copy reply to clipboard: {}", e),
+                        }
+                    }
+                    // Only finished analysis replies carry a model
+                    if message.model.is_some() && ui.small_button("📌").on_hover_text("Pin this answer above the chat; pins stay after /clear").clicked() {
+                        pin = true;
+                    }
                 } else {
                     ui.label(RichText::new(time_str).color(Color32::from_rgb(130, 130, 130)).small());
                     ui.label(RichText::new(name_text).color(name_color).small());
@@ -587,10 +2133,81 @@ impl ScreenSnapApp {
                 .inner_margin(egui::Margin::symmetric(12.0, 8.0))
                 .show(ui, |ui| {
                     ui.set_max_width(SIDEBAR_WIDTH * 0.8); 
-                    ui.label(RichText::new(&message.text).color(text_color)); 
+                    // The stored text stays raw for copying, pins and follow-ups; only model replies are
+                    // post-processed, and only for display, so changing the rules updates them too
+                    let text = if message.model.is_some() {
+                        self.config.post_process(&message.text)
+                    } else {
+                        message.text.clone()
+                    };
+                    if !message.is_user && self.config.render_markdown {
+                        ui.label(markdown::layout(&text, text_color));
+                    } else {
+                        ui.label(RichText::new(&text).color(text_color));
+                    }
                 });
             ui.add_space(6.0);
         });
+        pin
+    }
+
+    /// The pinned answers, each with buttons to copy or unpin it
+    fn draw_pinned_notes(&mut self, ui: &mut Ui) {
+        if self.pinned_notes.notes.is_empty() {
+            return;
+        }
+        let mut unpin = None;
+        egui::CollapsingHeader::new(RichText::new(format!("📌 Pinned ({})", self.pinned_notes.notes.len())).size(14.0))
+            .default_open(true)
+            .show(ui, |ui| {
+                ScrollArea::vertical().id_source("pinned_notes").max_height(160.0).show(ui, |ui| {
+                    for (index, note) in self.pinned_notes.notes.iter().enumerate() {
+                        egui::Frame::none()
+                            .fill(Color32::from_rgb(45, 45, 35))
+                            .rounding(6.0)
+                            .inner_margin(6.0)
+                            .show(ui, |ui| {
+                                ui.horizontal(|ui| {
+                                    let source = note.model.as_deref().unwrap_or("AI");
+                                    ui.label(RichText::new(source).small().color(Color32::from_rgb(150, 150, 150)));
+                                    ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                                        if ui.small_button("✕").on_hover_text("Unpin").clicked() {
+                                            unpin = Some(index);
+                                        }
+                                        #[cfg(feature = "clipboard")]
+                                        if ui.small_button("📋").on_hover_text("Copy this answer").clicked() {
+                                            match Clipboard::new().and_then(|mut clipboard| clipboard.set_text(note.text.clone())) {
+                                                Ok(()) => info!("Pinned note copied to clipboard"),
+                                                Err(e) => error!("Failed to copy pinned note to clipboard: {}", e),
+                                            }
+                                        }
+                                    });
+                                });
+                                ui.label(RichText::new(truncate_chars(&note.text, PINNED_NOTE_PREVIEW_CHARS)).size(13.0))
+                                    .on_hover_text(&note.text);
+                            });
+                        ui.add_space(4.0);
+                    }
+                });
+            });
+        if let Some(index) = unpin {
+            self.pinned_notes.remove(index);
+            self.save_pinned_notes();
+        }
+    }
+
+    /// Pin a reply so it stays visible; pinning the same text twice does nothing
+    fn pin_message(&mut self, message: &ChatMessage) {
+        if self.pinned_notes.pin(&message.text, message.model.as_deref()) {
+            info!("Pinned a reply ({} pinned)", self.pinned_notes.notes.len());
+            self.save_pinned_notes();
+        }
+    }
+
+    fn save_pinned_notes(&self) {
+        if let Err(e) = self.pinned_notes.save() {
+            error!("Failed to save pinned notes: {}", e);
+        }
     }
 
     fn draw_modern_chat_input(&mut self, ui: &mut Ui) -> bool {
@@ -609,6 +2226,9 @@ impl ScreenSnapApp {
                             .margin(egui::vec2(8.0, 6.0))
                             .font(egui::TextStyle::Body);
                         let response = ui.add(text_edit);
+                        if response.changed() && self.live_prompt {
+                            self.live_edited_at = Some(Instant::now());
+                        }
                         ui.add_space(4.0);
                         let send_button = ui.add_sized(
                             [36.0, 36.0], 
@@ -622,13 +2242,16 @@ impl ScreenSnapApp {
                             let user_message_text = self.current_input.trim().to_string();
                             if !user_message_text.is_empty() {
                                 self.current_input.clear();
+                                self.stop_live_prompt();
                                 let user_message = ChatMessage {
+                                    id: self.reserve_message_id(),
                                     text: user_message_text.clone(),
                                     is_user: true,
                                     timestamp: chrono::Local::now(),
+                                    model: None,
                                 };
                                 info!("Adding user message to chat history: '{}'", &user_message.text);
-                                self.chat_history.push(user_message);
+                                self.push_message(user_message);
                                 self.handle_user_input(user_message_text); 
                                 message_sent = true;
                                 response.request_focus();
@@ -642,39 +2265,178 @@ impl ScreenSnapApp {
         message_sent
     }
 
+    /// Minimize the window so it stays out of a screen capture; it is restored once the capture thread
+    /// clears `capturing`. With a delay, a countdown the user can cancel is shown first and the window
+    /// minimizes when it runs out. The capture thread waits for both with `wait_for_capture`
+    fn prepare_capture(&mut self, extra_delay: Duration) {
+        {
+            let mut state_guard = self.state.lock().unwrap();
+            if !extra_delay.is_zero() {
+                state_guard.capture_countdown = Some(CaptureCountdown { deadline: Instant::now() + extra_delay, cancelled: false });
+            }
+        }
+        self.minimize_for_capture = true;
+        self.egui_ctx.request_repaint();
+    }
+
+    /// Claim the capture for a new request. False, ignoring the request, while another capture is in
+    /// flight or right after the last one: overlapping captures race on the manager and can leave an
+    /// older image on screen than the one last asked for. The capture thread clears `capturing`
+    fn begin_capture(&mut self) -> bool {
+        if self.last_capture_request.map_or(false, |at| at.elapsed() < CAPTURE_DEBOUNCE) {
+            info!("Ignoring a capture request right after the last one");
+            return false;
+        }
+        let mut state_guard = self.state.lock().unwrap();
+        if state_guard.capturing {
+            info!("Ignoring a capture request: one is already in progress");
+            return false;
+        }
+        state_guard.capturing = true;
+        self.last_capture_request = Some(Instant::now());
+        true
+    }
+
     fn capture_full_screen(&mut self) {
+        let delay = Duration::from_millis(self.config.capture_delay_ms);
+        self.capture_full_screen_after(delay);
+    }
+
+    /// Capture the full screen without the extra delay
+    fn quick_capture(&mut self) {
+        self.capture_full_screen_after(Duration::ZERO);
+    }
+
+    fn capture_full_screen_after(&mut self, delay: Duration) {
+        if !self.begin_capture() {
+            return;
+        }
+        self.last_capture_window = None;
+        self.prepare_capture(delay);
         let screenshot_manager_clone = Arc::clone(&self.screenshot_manager);
         let state_clone = Arc::clone(&self.state);
+        let repaint_ctx = self.egui_ctx.clone();
         thread::spawn(move || {
-            thread::sleep(Duration::from_millis(300));
+            if !wait_for_capture(&state_clone, &repaint_ctx) {
+                return;
+            }
             if let Ok(mut manager) = screenshot_manager_clone.lock() {
                 if let Err(e) = manager.capture_screen() {
-                    error!("Failed to capture screen: {}", e);
+                    if backend::is_cancelled(&e) {
+                        info!("Screen capture cancelled");
+                    } else {
+                        error!("Failed to capture screen: {}", e);
+                    }
                 } else {
                     if let Ok(image_data_bytes) = manager.get_current_image_data() {
                         let mut state = state_clone.lock().unwrap();
-                        state.image_data = image_data_bytes;
-                        state.current_image = None; 
+                        state.set_capture(image_data_bytes, manager.get_current_image().map_or(false, screenshot::is_probably_blank));
                         info!("Full screen captured, image data updated.");
+                        stats::record_capture("primary");
+                    }
+                }
+            }
+            state_clone.lock().unwrap().capturing = false;
+            repaint_ctx.request_repaint();
+        });
+    }
+
+    fn capture_cursor_monitor(&mut self) {
+        if !self.begin_capture() {
+            return;
+        }
+        self.last_capture_window = None;
+        self.prepare_capture(Duration::from_millis(self.config.capture_delay_ms));
+        let screenshot_manager_clone = Arc::clone(&self.screenshot_manager);
+        let state_clone = Arc::clone(&self.state);
+        let repaint_ctx = self.egui_ctx.clone();
+        thread::spawn(move || {
+            if !wait_for_capture(&state_clone, &repaint_ctx) {
+                return;
+            }
+            if let Ok(mut manager) = screenshot_manager_clone.lock() {
+                match manager.capture_cursor_monitor() {
+                    Ok(monitor) => {
+                        if let Ok(image_data_bytes) = manager.get_current_image_data() {
+                            let mut state = state_clone.lock().unwrap();
+                            state.set_capture(image_data_bytes, manager.get_current_image().map_or(false, screenshot::is_probably_blank));
+                            info!("Monitor {} under the cursor captured, image data updated.", monitor);
+                            stats::record_capture("cursor monitor");
+                        }
+                    }
+                    Err(e) => {
+                        error!("Failed to capture the monitor under the cursor: {}", e);
+                        let mut state = state_clone.lock().unwrap();
+                        state.ai_response = format!("Couldn't capture the monitor under the cursor: {}", e);
                     }
                 }
             }
+            state_clone.lock().unwrap().capturing = false;
+            repaint_ctx.request_repaint();
+        });
+    }
+
+    /// Capture a square around the mouse; with a capture delay there's time to point at the spot
+    fn capture_cursor_area(&mut self) {
+        if !self.begin_capture() {
+            return;
+        }
+        self.last_capture_window = None;
+        self.prepare_capture(Duration::from_millis(self.config.capture_delay_ms));
+        let screenshot_manager_clone = Arc::clone(&self.screenshot_manager);
+        let state_clone = Arc::clone(&self.state);
+        let repaint_ctx = self.egui_ctx.clone();
+        thread::spawn(move || {
+            if !wait_for_capture(&state_clone, &repaint_ctx) {
+                return;
+            }
+            if let Ok(mut manager) = screenshot_manager_clone.lock() {
+                match manager.capture_cursor_area(CURSOR_AREA_RADIUS) {
+                    Ok(area) => {
+                        if let Ok(image_data_bytes) = manager.get_current_image_data() {
+                            let mut state = state_clone.lock().unwrap();
+                            state.set_capture(image_data_bytes, manager.get_current_image().map_or(false, screenshot::is_probably_blank));
+                            info!("{}x{} around the cursor captured, image data updated.", area.width, area.height);
+                            stats::record_capture("cursor area");
+                        }
+                    }
+                    Err(e) => {
+                        error!("Failed to capture the area around the cursor: {}", e);
+                        let mut state = state_clone.lock().unwrap();
+                        state.ai_response = format!("Couldn't capture the area around the cursor: {}", e);
+                    }
+                }
+            }
+            state_clone.lock().unwrap().capturing = false;
+            repaint_ctx.request_repaint();
         });
     }
 
     fn capture_selected_window(&mut self) {
         if let Some(window_title_owned) = self.selected_window.clone() {
+            if !self.begin_capture() {
+                return;
+            }
+            self.last_capture_window = Some(window_title_owned.clone());
             let screenshot_manager_clone = Arc::clone(&self.screenshot_manager);
             let state_clone = Arc::clone(&self.state);
+            let repaint_ctx = self.egui_ctx.clone();
             thread::spawn(move || {
                 if let Ok(mut manager) = screenshot_manager_clone.lock() {
                     if let Err(e) = manager.capture_window(&window_title_owned) {
+                        if backend::is_cancelled(&e) {
+                            info!("Window capture cancelled");
+                            let mut state = state_clone.lock().unwrap();
+                            state.ai_response = "Capture cancelled.".to_string();
+                            state.capturing = false;
+                            repaint_ctx.request_repaint();
+                            return;
+                        }
                         error!("Failed to capture window '{}': {}", window_title_owned, e);
                         if manager.capture_screen().is_ok() { 
                             if let Ok(image_data_bytes) = manager.get_current_image_data() {
                                 let mut state = state_clone.lock().unwrap();
-                                state.image_data = image_data_bytes;
-                                state.current_image = None; 
+                                state.set_capture(image_data_bytes, manager.get_current_image().map_or(false, screenshot::is_probably_blank));
                                 info!("Window capture failed, fell back to full screen. Image data updated.");
                             }
                         } else {
@@ -683,16 +2445,244 @@ impl ScreenSnapApp {
                     } else {
                         if let Ok(image_data_bytes) = manager.get_current_image_data() {
                             let mut state = state_clone.lock().unwrap();
-                            state.image_data = image_data_bytes;
-                            state.current_image = None; 
+                            state.set_capture(image_data_bytes, manager.get_current_image().map_or(false, screenshot::is_probably_blank));
                             info!("Window '{}' captured, image data updated.", window_title_owned);
+                            stats::record_capture("window");
                         }
                     }
                 }
+                state_clone.lock().unwrap().capturing = false;
+                repaint_ctx.request_repaint();
             });
         }
     }
 
+    /// Crop the current capture to the marked region; the whole capture can be brought back with `uncrop_capture`
+    fn crop_to_roi(&self) {
+        let roi = match self.roi_pixels() {
+            Some(roi) => roi,
+            None => return,
+        };
+        let mut manager = self.screenshot_manager.lock().unwrap();
+        let result = manager.crop_current_image(roi.x.max(0) as u32, roi.y.max(0) as u32, roi.width, roi.height);
+        self.show_edited_capture(&manager, result, "crop");
+    }
+
+    /// Go back from a crop to the whole window or screen capture
+    fn uncrop_capture(&self) {
+        let mut manager = self.screenshot_manager.lock().unwrap();
+        let result = manager.uncrop();
+        self.show_edited_capture(&manager, result, "restore the whole capture");
+    }
+
+    /// Hand the manager's current image to the preview after a crop, or explain why it failed
+    fn show_edited_capture(&self, manager: &ScreenshotManager, result: Result<()>, action: &str) {
+        let mut state_guard = self.state.lock().unwrap();
+        let image_data = result.and_then(|()| manager.get_current_image_data());
+        match image_data {
+            Ok(image_data) => {
+                let looks_blank = manager.get_current_image().map_or(false, screenshot::is_probably_blank);
+                state_guard.set_capture(image_data, looks_blank);
+            }
+            Err(e) => {
+                warn!("Failed to {}: {}", action, e);
+                state_guard.ai_response = format!("Couldn't {}: {}.", action, e);
+            }
+        }
+    }
+
+    /// Preview with scroll-wheel zoom, drag to pan and double-click to fit
+    fn draw_image_viewer(&mut self, ui: &mut Ui, texture: &egui::TextureHandle, size: Vec2) {
+        let (view_rect, response) = ui.allocate_exact_size(size, egui::Sense::click_and_drag());
+
+        if response.double_clicked() {
+            self.zoom = 1.0;
+            self.pan = Vec2::ZERO;
+        }
+
+        if response.hovered() {
+            let scroll = ui.input(|i| i.scroll_delta.y);
+            if scroll != 0.0 {
+                let new_zoom = (self.zoom * (scroll * 0.002).exp()).clamp(1.0, MAX_PREVIEW_ZOOM);
+                // Keep the point under the cursor fixed while zooming
+                if let Some(pointer) = response.hover_pos() {
+                    let offset = pointer - view_rect.center();
+                    self.pan = offset - (offset - self.pan) * (new_zoom / self.zoom);
+                }
+                self.zoom = new_zoom;
+                // Don't let the surrounding scroll area scroll as well
+                ui.input_mut(|i| i.scroll_delta = Vec2::ZERO);
+            }
+        }
+
+        // Shift+drag marks a region of interest instead of panning
+        let starts_selection = response.drag_started() && ui.input(|i| i.modifiers.shift);
+        if response.dragged() && self.roi_drag_start.is_none() && !starts_selection {
+            self.pan += response.drag_delta();
+        }
+
+        // The zoomed image must always cover the view, so it can't be lost off-screen
+        let max_pan = view_rect.size() * (self.zoom - 1.0) / 2.0;
+        self.pan = self.pan.clamp(-max_pan, max_pan);
+
+        let image_rect = egui::Rect::from_center_size(view_rect.center() + self.pan, view_rect.size() * self.zoom);
+        let to_image = |pos: egui::Pos2| {
+            let fraction = (pos - image_rect.min) / image_rect.size();
+            egui::pos2(fraction.x.clamp(0.0, 1.0), fraction.y.clamp(0.0, 1.0))
+        };
+        if starts_selection {
+            self.roi_drag_start = response.interact_pointer_pos().map(to_image);
+        }
+        if let Some(start) = self.roi_drag_start {
+            if let Some(pos) = response.interact_pointer_pos() {
+                self.roi = Some(egui::Rect::from_two_pos(start, to_image(pos)));
+            }
+            if response.drag_released() {
+                self.roi_drag_start = None;
+                // A click without much of a drag isn't a region
+                if self.roi.map_or(false, |r| r.width() < 0.01 || r.height() < 0.01) {
+                    self.roi = None;
+                }
+            }
+        }
+
+        let painter = ui.painter_at(view_rect);
+        painter.image(
+            texture.id(),
+            image_rect,
+            egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+            Color32::WHITE,
+        );
+        if let Some(roi) = self.roi {
+            let roi_rect = egui::Rect::from_min_max(
+                image_rect.min + roi.min.to_vec2() * image_rect.size(),
+                image_rect.min + roi.max.to_vec2() * image_rect.size(),
+            );
+            painter.rect_stroke(roi_rect, 0.0, Stroke::new(2.0, Color32::from_rgb(255, 64, 64)));
+        }
+
+        let response = if self.zoom > 1.0 {
+            response.on_hover_text(format!("{:.0}% - double-click to fit", self.zoom * 100.0))
+        } else {
+            response.on_hover_text("Scroll to zoom, drag to pan, Shift+drag to mark a region")
+        };
+        if self.zoom > 1.0 && response.hovered() {
+            ui.ctx().set_cursor_icon(egui::CursorIcon::Grab);
+        }
+    }
+
+    /// The captures that can be compared: the history oldest first, then the current image.
+    /// try_lock so a capture in progress doesn't stall the UI; empty then
+    fn comparison_images(&self) -> Vec<image::DynamicImage> {
+        match self.screenshot_manager.try_lock() {
+            Ok(manager) => manager.history().iter().chain(manager.get_current_image()).cloned().collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// The compare toggle under the preview and, while it's open, the capture pickers and before/after slider
+    fn draw_comparison(&mut self, ui: &mut Ui, width: f32) {
+        // try_lock so a capture in progress doesn't stall the UI
+        let count = self.screenshot_manager.try_lock()
+            .map_or(0, |manager| manager.history().len() + usize::from(manager.get_current_image().is_some()));
+        if count < 2 {
+            return;
+        }
+        if self.comparison.is_none() {
+            if ui.small_button("⇆ Compare captures").on_hover_text("Slide between two captures to spot what changed").clicked() {
+                self.comparison = Some(CaptureComparison { before: count - 2, after: count - 1, divider: 0.5, textures: None });
+            }
+            return;
+        }
+        let comparison = match &mut self.comparison {
+            Some(comparison) => comparison,
+            None => return,
+        };
+        let label = |index: usize| if index + 1 == count { "Current".to_string() } else { format!("Capture {}", index + 1) };
+        let mut close = false;
+        ui.horizontal(|ui| {
+            for (id, side, pick) in [("compare_before", "Left:", &mut comparison.before), ("compare_after", "Right:", &mut comparison.after)] {
+                ui.label(RichText::new(side).small());
+                egui::ComboBox::from_id_source(id)
+                    .selected_text(label(*pick))
+                    .width(90.0)
+                    .show_ui(ui, |ui| {
+                        for index in 0..count {
+                            ui.selectable_value(pick, index, label(index));
+                        }
+                    });
+            }
+            if ui.small_button("✕").on_hover_text("Close the comparison").clicked() {
+                close = true;
+            }
+        });
+        if close {
+            self.comparison = None;
+            return;
+        }
+
+        let pair = (comparison.before.min(count - 1), comparison.after.min(count - 1));
+        let loaded = comparison.textures.as_ref().map(|(loaded, _, _)| *loaded);
+        if loaded != Some(pair) {
+            let images = self.comparison_images();
+            let (before, after) = match (images.get(pair.0), images.get(pair.1)) {
+                (Some(before), Some(after)) => (before, after),
+                _ => return,
+            };
+            let textures = (pair, image_texture(ui.ctx(), "compare_before", before), image_texture(ui.ctx(), "compare_after", after));
+            if let Some(comparison) = &mut self.comparison {
+                comparison.textures = Some(textures);
+            }
+        }
+        let comparison = match &mut self.comparison {
+            Some(comparison) => comparison,
+            None => return,
+        };
+        let (before, after) = match &comparison.textures {
+            Some((_, before, after)) => (before.clone(), after.clone()),
+            None => return,
+        };
+
+        let aspect_ratio = before.size_vec2().x / before.size_vec2().y;
+        let height = if aspect_ratio > 0.0 { width / aspect_ratio } else { width };
+        let (rect, response) = ui.allocate_exact_size(Vec2::new(width, height), egui::Sense::click_and_drag());
+        if response.dragged() || response.clicked() {
+            if let Some(pointer) = response.interact_pointer_pos() {
+                comparison.divider = ((pointer.x - rect.left()) / rect.width()).clamp(0.0, 1.0);
+            }
+        }
+        let split_x = rect.left() + rect.width() * comparison.divider;
+        let uv = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+        // Both images fill the same frame; each is clipped to its side of the divider
+        ui.painter_at(egui::Rect::from_min_max(rect.min, egui::pos2(split_x, rect.bottom())))
+            .image(before.id(), rect, uv, Color32::WHITE);
+        ui.painter_at(egui::Rect::from_min_max(egui::pos2(split_x, rect.top()), rect.max))
+            .image(after.id(), rect, uv, Color32::WHITE);
+        let painter = ui.painter_at(rect);
+        painter.line_segment([egui::pos2(split_x, rect.top()), egui::pos2(split_x, rect.bottom())], Stroke::new(2.0, Color32::WHITE));
+        painter.circle(egui::pos2(split_x, rect.center().y), 9.0, Color32::from_rgb(42, 90, 170), Stroke::new(2.0, Color32::WHITE));
+        painter.text(egui::pos2(split_x, rect.center().y), egui::Align2::CENTER_CENTER, "⇆", egui::FontId::proportional(11.0), Color32::WHITE);
+        if response.hovered() || response.dragged() {
+            ui.ctx().set_cursor_icon(egui::CursorIcon::ResizeHorizontal);
+        }
+        response.on_hover_text(format!(
+            "{} on the left, {} on the right. Drag to move the divider; captures of different sizes are stretched to the same frame",
+            label(pair.0), label(pair.1)
+        ));
+    }
+
+    /// Repeat the last capture, used when it came back blank
+    fn retry_capture(&mut self) {
+        self.state.lock().unwrap().looks_blank = false;
+        match self.last_capture_window.clone() {
+            Some(window_title) => {
+                self.selected_window = Some(window_title);
+                self.capture_selected_window();
+            }
+            None => self.capture_full_screen(),
+        }
+    }
+
     fn analyze_image(&mut self) {
         let image_data_bytes = {
             let mut state_guard = self.state.lock().unwrap(); 
@@ -701,51 +2691,141 @@ impl ScreenSnapApp {
                 state_guard.ai_response = "Please capture an image first.".to_string();
                 return;
             }
+            if let Err(e) = screenshot::ensure_analyzable(&state_guard.image_data) {
+                warn!("Refusing to analyze image: {}", e);
+                state_guard.ai_response = format!("The {}.", e);
+                return;
+            }
+            if state_guard.analyzing_capture == Some(state_guard.capture_count) {
+                info!("This capture is already being analyzed.");
+                return;
+            }
             state_guard.image_data.clone()
         };
+        let max_payload = match self.payload_decision(&image_data_bytes, None) {
+            Some(downscale) => downscale.then_some(self.max_payload_bytes()),
+            None => return,
+        };
+        self.quick_look_reply = None;
         
         let model_name = self.model_name.clone(); 
         let state_clone = Arc::clone(&self.state); 
-        let ollama_host_url_str = get_ollama_url(None); 
+        let ollama_host_url_str = self.ollama_url(); 
+        let system_prompt = self.config.system_prompt.clone();
+        let quality = self.config.quality;
+        let roi = self.roi_pixels();
 
-        {
+        let reply_id = self.reserve_message_id();
+        self.reply_models.insert(reply_id, model_name.clone());
+        self.last_analysis = Some(LastAnalysis { image_data: image_data_bytes.clone(), prompt: None, roi, max_payload });
+        let capture_id = {
             let mut state_guard = self.state.lock().unwrap();
             state_guard.processing = true;
+            state_guard.response_id = Some(reply_id);
+            state_guard.analyzing_capture = Some(state_guard.capture_count);
             state_guard.ai_response = "Processing image...".to_string(); 
-        }
+            state_guard.capture_count
+        };
         info!("Starting AI analysis for image.");
         
+        let repaint_ctx = self.egui_ctx.clone();
         thread::spawn(move || {
             std::env::set_var("OLLAMA_HOST", &ollama_host_url_str); 
-            match LocalModel::new(&model_name) {
+            let reply = match LocalModel::new(&model_name) {
                 Ok(mut ai_model) => {
-                    match ai_model.process_image(&image_data_bytes) {
+                    ai_model.set_system_prompt(system_prompt.as_deref());
+                    quality.apply(&mut ai_model);
+                    let start = Instant::now();
+                    let result = analyze_capture(&mut ai_model, &image_data_bytes, roi, max_payload);
+                    stats::record_analysis(&model_name, start.elapsed(), result.is_ok());
+                    match result {
                         Ok(response) => {
-                            let mut state_guard = state_clone.lock().unwrap();
-                            state_guard.ai_response = response;
                             info!("AI analysis complete.");
+                            response
                         }
                         Err(e) => {
-                            let mut state_guard = state_clone.lock().unwrap();
-                            state_guard.ai_response = format!("AI processing failed: {}", e);
-                            if e.to_string().contains("not found") {
-                                state_guard.ai_response.push_str(&format!("\n\nTo fix: ollama pull {}", model_name));
-                            } else if e.to_string().contains("not available") || e.to_string().contains("connection refused") {
-                                state_guard.ai_response.push_str("\n\nEnsure Ollama is running: ollama serve");
-                            }
                             error!("AI processing error: {}", e);
+                            analysis_failure_reply(&e, &model_name)
                         }
                     }
                 }
                 Err(e) => {
-                    let mut state_guard = state_clone.lock().unwrap();
-                    state_guard.ai_response = format!("Failed to init Ollama model: {}\n\n", e);
-                    state_guard.ai_response.push_str("Is Ollama running? Is model pulled?");
                     error!("Failed to init Ollama model: {}", e);
+                    format!("Failed to init Ollama model: {}\n\nIs Ollama running? Is model pulled?", e)
                 }
-            }
+            };
             let mut state_guard = state_clone.lock().unwrap();
-            state_guard.processing = false;
+            if state_guard.analyzing_capture == Some(capture_id) {
+                state_guard.analyzing_capture = None;
+            }
+            state_guard.finish_reply(reply_id, reply);
+            repaint_ctx.request_repaint();
+        });
+    }
+
+    /// Describe a small thumbnail of the capture for a fast first answer; the full analysis is one click away
+    fn quick_look(&mut self) {
+        let image_data_bytes = {
+            let mut state_guard = self.state.lock().unwrap();
+            if state_guard.image_data.is_empty() {
+                state_guard.ai_response = "Please capture an image first.".to_string();
+                return;
+            }
+            if let Err(e) = screenshot::ensure_analyzable(&state_guard.image_data) {
+                warn!("Refusing to analyze image: {}", e);
+                state_guard.ai_response = format!("The {}.", e);
+                return;
+            }
+            state_guard.image_data.clone()
+        };
+        let model_name = self.model_name.clone();
+        let state_clone = Arc::clone(&self.state);
+        let ollama_host_url_str = self.ollama_url();
+        let system_prompt = self.config.system_prompt.clone();
+        let quality = self.config.quality;
+        let roi = self.roi_pixels();
+
+        let reply_id = self.reserve_message_id();
+        self.reply_models.insert(reply_id, model_name.clone());
+        self.last_analysis = Some(LastAnalysis { image_data: image_data_bytes.clone(), prompt: None, roi, max_payload: None });
+        self.quick_look_reply = Some(reply_id);
+        {
+            let mut state_guard = self.state.lock().unwrap();
+            state_guard.processing = true;
+            state_guard.response_id = Some(reply_id);
+            state_guard.ai_response = "Taking a quick look...".to_string();
+        }
+        info!("Starting a quick look at a {} px thumbnail", local_model::QUICK_LOOK_DIMENSION);
+
+        let repaint_ctx = self.egui_ctx.clone();
+        thread::spawn(move || {
+            std::env::set_var("OLLAMA_HOST", &ollama_host_url_str);
+            let reply = match LocalModel::new(&model_name) {
+                Ok(mut ai_model) => {
+                    ai_model.set_system_prompt(system_prompt.as_deref());
+                    quality.apply(&mut ai_model);
+                    // Same settings as a full analysis, only the image is shrunk
+                    ai_model.set_max_dimension(Some(local_model::QUICK_LOOK_DIMENSION));
+                    // Not recorded in the usage stats: a thumbnail's timing would skew the model's averages
+                    let result = analyze_capture(&mut ai_model, &image_data_bytes, roi, None);
+                    match result {
+                        Ok(response) => {
+                            info!("Quick look complete.");
+                            response
+                        }
+                        Err(e) => {
+                            error!("Quick look error: {}", e);
+                            analysis_failure_reply(&e, &model_name)
+                        }
+                    }
+                }
+                Err(e) => {
+                    error!("Failed to init Ollama model: {}", e);
+                    format!("Failed to init Ollama model: {}\n\nIs Ollama running? Is model pulled?", e)
+                }
+            };
+            state_clone.lock().unwrap().finish_reply(reply_id, reply);
+            repaint_ctx.request_repaint();
         });
     }
 
@@ -757,12 +2837,15 @@ impl ScreenSnapApp {
             let mut response_text = String::new(); 
 
             match command.as_str() {
-                "/capture" => self.capture_full_screen(),
-                "/window" => {
-                    match get_window_titles() {
-                        Ok(list) => self.window_list = list,
-                        Err(e) => error!("Failed to get window list: {}", e),
+                "/capture" => {
+                    if self.state.lock().unwrap().capturing {
+                        response_text = "A capture is already in progress.".to_string();
+                    } else {
+                        self.capture_full_screen();
                     }
+                },
+                "/window" => {
+                    self.refresh_window_list();
                     if parts.len() > 1 {
                         let window_name = parts[1].trim();
                         let matched_window = self.window_list.iter()
@@ -781,9 +2864,17 @@ impl ScreenSnapApp {
                 },
                 "/model" => {
                     if parts.len() > 1 {
-                        let model_name_input = parts[1].trim();
-                        self.model_name = model_name_input.to_string();
-                        response_text = format!("Model set to: {}", self.model_name);
+                        response_text = match self.resolve_model_name(parts[1]) {
+                            Ok((model_name, None)) => {
+                                self.model_name = model_name;
+                                format!("Model set to: {}", self.model_name)
+                            }
+                            Ok((model_name, Some(similar))) => {
+                                self.model_name = model_name;
+                                format!("Model set to: {} (not installed; did you mean {}?)", self.model_name, similar)
+                            }
+                            Err(e) => e.to_string(),
+                        };
                     } else {
                         response_text = format!("Current model: {}. Usage: /model <model_name>", self.model_name);
                     }
@@ -797,13 +2888,50 @@ impl ScreenSnapApp {
                         self.analyze_image();
                     }
                 },
+                "/annotate" => {
+                    let state_guard_check = self.state.lock().unwrap();
+                    if state_guard_check.image_data.is_empty() {
+                        response_text = "Please capture an image first using /capture or /window.".to_string();
+                    } else {
+                        drop(state_guard_check);
+                        self.annotate_image();
+                    }
+                },
+                "/changed" => self.describe_changes(),
+                "/quick" => self.quick_look(),
+                "/followup" => {
+                    match parts.get(1).map(|arg| arg.trim().to_lowercase()).as_deref() {
+                        Some("on") => self.follow_up = true,
+                        Some("off") => self.follow_up = false,
+                        _ => {}
+                    }
+                    response_text = if self.follow_up {
+                        "Follow-up mode is on: messages build on the earlier answers. Use /followup off for fresh analyses.".to_string()
+                    } else {
+                        "Follow-up mode is off: every message is a fresh analysis. Use /followup on to build on earlier answers.".to_string()
+                    };
+                },
+                "/system" => {
+                    let text = parts.get(1).map_or("", |t| t.trim());
+                    response_text = if text.is_empty() {
+                        match &self.config.system_prompt {
+                            Some(system_prompt) => format!("Current system prompt:\n{}\n\nUse /system reset to go back to the model's default.", system_prompt),
+                            None => "No system prompt set (using the model's default). Usage: /system <text>".to_string(),
+                        }
+                    } else if text.eq_ignore_ascii_case("reset") {
+                        self.config.system_prompt = None;
+                        self.save_config();
+                        "System prompt cleared.".to_string()
+                    } else if text.chars().count() > MAX_SYSTEM_PROMPT_CHARS {
+                        format!("System prompt is too long ({} characters, maximum {}).", text.chars().count(), MAX_SYSTEM_PROMPT_CHARS)
+                    } else {
+                        self.config.system_prompt = Some(text.to_string());
+                        self.save_config();
+                        format!("System prompt set to:\n{}", text)
+                    };
+                },
                 "/clear" => {
-                    self.chat_history.clear();
-                    let mut state_guard = self.state.lock().unwrap();
-                    state_guard.current_image = None; 
-                    state_guard.image_data.clear();
-                    state_guard.ai_response.clear();
-                    info!("Chat history and current image cleared.");
+                    self.clear_conversation();
                     response_text = "Chat history and image cleared.".to_string();
                 },
                 "/help" => {
@@ -812,8 +2940,22 @@ impl ScreenSnapApp {
                         /window [name] - Capture a specific window (or part of name)\n\
                         /model [name] - Change AI model (e.g., /model llava:latest)\n\
                         /analyze - Analyze current image with default prompt\n\
+                        /annotate - Draw boxes around UI elements found by the model\n\
+                        /changed - Describe what changed between the last two captures\n\
+                        /quick - Quick, rough look at a thumbnail of the current image\n\
+                        /followup [on|off] - Ask follow-ups that build on earlier answers, or fresh analyses (default)\n\
+                        /system [text] - Show or set the system prompt (/system reset to clear)\n\
                         /clear - Clear chat history and current image\n\
-                        /help - Show this help message".to_string();
+                        /help - Show this help message\n\n\
+                        Shortcuts:\n\
+                        Ctrl+Enter - Analyze current image\n\
+                        Ctrl+N - Capture full screen\n\
+                        Ctrl+Shift+N - Quick capture (no delay)\n\
+                        Ctrl+Shift+A - Capture the area around the mouse\n\
+                        Ctrl+W - Pick a window to capture\n\
+                        Ctrl+L - Clear chat history and image\n\
+                        Ctrl+V - Paste an image from the clipboard\n\
+                        Esc - Collapse the sidebar".to_string();
                 },
                 _ => {
                     response_text = format!("Unknown command: {}. Type /help for available commands.", command);
@@ -829,7 +2971,11 @@ impl ScreenSnapApp {
                 state_guard_check.ai_response = "Please capture an image first before sending a prompt.".to_string();
             } else {
                 drop(state_guard_check); 
-                self.analyze_with_prompt(input);
+                if self.follow_up {
+                    self.continue_conversation(input);
+                } else {
+                    self.analyze_with_prompt(input);
+                }
             }
         }
     }
@@ -842,57 +2988,545 @@ impl ScreenSnapApp {
                 state_guard.ai_response = "Please capture an image for prompt analysis.".to_string();
                 return;
             }
+            if let Err(e) = screenshot::ensure_analyzable(&state_guard.image_data) {
+                warn!("Refusing to analyze image: {}", e);
+                state_guard.ai_response = format!("The {}.", e);
+                return;
+            }
             state_guard.image_data.clone()
         };
         let model_name = self.model_name.clone();
         let state_clone = Arc::clone(&self.state);
-        let prompt_clone = prompt; 
-        let ollama_host_url_str = get_ollama_url(None);
+        let max_payload = match self.payload_decision(&image_data_bytes, Some(&prompt)) {
+            Some(downscale) => downscale.then_some(self.max_payload_bytes()),
+            None => return,
+        };
+        let source = self.screenshot_manager.lock().ok().and_then(|manager| manager.current_capture_source().cloned());
+        let prompt_clone = screenshot::expand_prompt_variables(&prompt, source.as_ref());
+        let ollama_host_url_str = self.ollama_url();
+        let system_prompt = self.config.system_prompt.clone();
+        let quality = self.config.quality;
+        let roi = self.roi_pixels();
 
+        let reply_id = self.reserve_message_id();
+        self.reply_models.insert(reply_id, model_name.clone());
+        self.last_analysis = Some(LastAnalysis { image_data: image_data_bytes.clone(), prompt: Some(prompt_clone.clone()), roi, max_payload });
         {
             let mut state_guard = self.state.lock().unwrap();
             state_guard.processing = true;
+            state_guard.response_id = Some(reply_id);
             state_guard.ai_response = "Processing with your prompt...".to_string();
         }
+        let repaint_ctx = self.egui_ctx.clone();
         thread::spawn(move || {
             std::env::set_var("OLLAMA_HOST", &ollama_host_url_str);
-            match LocalModel::new(&model_name) {
+            let reply = match LocalModel::new(&model_name) {
                 Ok(mut ai_model) => {
                     ai_model.set_prompt(&prompt_clone); 
-                    match ai_model.process_image(&image_data_bytes) {
+                    ai_model.set_system_prompt(system_prompt.as_deref());
+                    quality.apply(&mut ai_model);
+                    let start = Instant::now();
+                    let result = analyze_capture(&mut ai_model, &image_data_bytes, roi, max_payload);
+                    stats::record_analysis(&model_name, start.elapsed(), result.is_ok());
+                    match result {
                         Ok(response) => {
-                            let mut state_guard = state_clone.lock().unwrap();
-                            state_guard.ai_response = response;
                             info!("AI analysis with prompt complete.");
+                            response
                         }
                         Err(e) => {
-                            let mut state_guard = state_clone.lock().unwrap();
-                            state_guard.ai_response = format!("AI processing failed: {}", e);
-                            if e.to_string().contains("not found") {
-                                state_guard.ai_response.push_str(&format!("\n\nTo fix: ollama pull {}", model_name));
-                            } else if e.to_string().contains("not available") || e.to_string().contains("connection refused") {
-                                state_guard.ai_response.push_str("\n\nEnsure Ollama is running: ollama serve");
-                            }
-                             error!("AI processing with prompt error: {}", e);
+                            error!("AI processing with prompt error: {}", e);
+                            analysis_failure_reply(&e, &model_name)
                         }
                     }
                 }
                 Err(e) => {
-                    let mut state_guard = state_clone.lock().unwrap();
-                    state_guard.ai_response = format!("Failed to init Ollama model: {}\n\n", e);
-                    state_guard.ai_response.push_str("Is Ollama running? Is model pulled?");
                     error!("Failed to init Ollama model for prompt analysis: {}", e);
+                    format!("Failed to init Ollama model: {}\n\nIs Ollama running? Is model pulled?", e)
                 }
+            };
+            state_clone.lock().unwrap().finish_reply(reply_id, reply);
+            repaint_ctx.request_repaint();
+        });
+    }
+
+    /// Earlier analysis turns as (question, answer) pairs, oldest first and at most `MAX_FOLLOW_UP_TURNS`.
+    /// Replies to /analyze and other slash commands count as answers to the default prompt
+    fn conversation_turns(&self) -> Vec<(String, String)> {
+        let mut turns = Vec::new();
+        let mut question: Option<&str> = None;
+        for message in &self.chat_history {
+            if message.is_user {
+                question = Some(&message.text);
+            } else if message.model.is_some() {
+                let asked = match question.take() {
+                    Some(text) if !text.starts_with('/') => text,
+                    _ => local_model::DEFAULT_PROMPT,
+                };
+                turns.push((asked.to_string(), message.text.clone()));
+            }
+        }
+        let skip = turns.len().saturating_sub(MAX_FOLLOW_UP_TURNS);
+        turns.split_off(skip)
+    }
+
+    /// Ask a follow-up that builds on the earlier answers; without any yet it is a fresh analysis
+    fn continue_conversation(&mut self, prompt: String) {
+        let history = self.conversation_turns();
+        if history.is_empty() {
+            self.analyze_with_prompt(prompt);
+            return;
+        }
+        info!("Continuing the conversation ({} earlier turns) with: '{}'", history.len(), prompt);
+        let image_data_bytes = {
+            let mut state_guard = self.state.lock().unwrap();
+            if let Err(e) = screenshot::ensure_analyzable(&state_guard.image_data) {
+                warn!("Refusing to analyze image: {}", e);
+                state_guard.ai_response = format!("The {}.", e);
+                return;
             }
+            state_guard.image_data.clone()
+        };
+        let max_payload = match self.payload_decision(&image_data_bytes, Some(&prompt)) {
+            Some(downscale) => downscale.then_some(self.max_payload_bytes()),
+            None => return,
+        };
+        let model_name = self.model_name.clone();
+        let state_clone = Arc::clone(&self.state);
+        let source = self.screenshot_manager.lock().ok().and_then(|manager| manager.current_capture_source().cloned());
+        let prompt_clone = screenshot::expand_prompt_variables(&prompt, source.as_ref());
+        let ollama_host_url_str = self.ollama_url();
+        let system_prompt = self.config.system_prompt.clone();
+        let quality = self.config.quality;
+        let roi = self.roi_pixels();
+
+        let reply_id = self.reserve_message_id();
+        self.reply_models.insert(reply_id, model_name.clone());
+        {
+            let mut state_guard = self.state.lock().unwrap();
+            state_guard.processing = true;
+            state_guard.response_id = Some(reply_id);
+            state_guard.ai_response = "Thinking about your follow-up...".to_string();
+        }
+        let repaint_ctx = self.egui_ctx.clone();
+        thread::spawn(move || {
+            std::env::set_var("OLLAMA_HOST", &ollama_host_url_str);
+            let reply = match LocalModel::new(&model_name) {
+                Ok(mut ai_model) => {
+                    ai_model.set_prompt(&prompt_clone);
+                    ai_model.set_history(history);
+                    ai_model.set_system_prompt(system_prompt.as_deref());
+                    quality.apply(&mut ai_model);
+                    let start = Instant::now();
+                    let result = analyze_capture(&mut ai_model, &image_data_bytes, roi, max_payload);
+                    stats::record_analysis(&model_name, start.elapsed(), result.is_ok());
+                    match result {
+                        Ok(response) => {
+                            info!("Follow-up answered.");
+                            response
+                        }
+                        Err(e) => {
+                            error!("Follow-up error: {}", e);
+                            analysis_failure_reply(&e, &model_name)
+                        }
+                    }
+                }
+                Err(e) => {
+                    error!("Failed to init Ollama model for follow-up: {}", e);
+                    format!("Failed to init Ollama model: {}\n\nIs Ollama running? Is model pulled?", e)
+                }
+            };
+            state_clone.lock().unwrap().finish_reply(reply_id, reply);
+            repaint_ctx.request_repaint();
+        });
+    }
+
+    /// Ask another model the last analysis again: same image, prompt and region of interest
+    fn rerun_with_model(&mut self, model_name: String) {
+        let last = match &self.last_analysis {
+            Some(last) => last.clone(),
+            None => return,
+        };
+        info!("Re-running the last analysis with {}", model_name);
+        let state_clone = Arc::clone(&self.state);
+        let ollama_host_url_str = self.ollama_url();
+        let system_prompt = self.config.system_prompt.clone();
+        let quality = self.config.quality;
+
+        let reply_id = self.reserve_message_id();
+        self.reply_models.insert(reply_id, model_name.clone());
+        {
+            let mut state_guard = self.state.lock().unwrap();
+            state_guard.processing = true;
+            state_guard.response_id = Some(reply_id);
+            state_guard.ai_response = format!("Re-running with {}...", model_name);
+        }
+        let repaint_ctx = self.egui_ctx.clone();
+        thread::spawn(move || {
+            std::env::set_var("OLLAMA_HOST", &ollama_host_url_str);
+            let reply = match LocalModel::new(&model_name) {
+                Ok(mut ai_model) => {
+                    if let Some(prompt) = &last.prompt {
+                        ai_model.set_prompt(prompt);
+                    }
+                    ai_model.set_system_prompt(system_prompt.as_deref());
+                    quality.apply(&mut ai_model);
+                    let start = Instant::now();
+                    let result = analyze_capture(&mut ai_model, &last.image_data, last.roi, last.max_payload);
+                    stats::record_analysis(&model_name, start.elapsed(), result.is_ok());
+                    match result {
+                        Ok(response) => {
+                            info!("Re-run with {} complete.", model_name);
+                            response
+                        }
+                        Err(e) => {
+                            error!("Re-run with {} failed: {}", model_name, e);
+                            analysis_failure_reply(&e, &model_name)
+                        }
+                    }
+                }
+                Err(e) => {
+                    error!("Failed to init Ollama model {}: {}", model_name, e);
+                    format!("Failed to init Ollama model: {}\n\nIs Ollama running? Is model pulled?", e)
+                }
+            };
+            state_clone.lock().unwrap().finish_reply(reply_id, reply);
+            repaint_ctx.request_repaint();
+        });
+    }
+
+    /// Track the window hovered in the picker, reading its bounds on a worker thread once per hover
+    fn flash_window(&mut self, hovered: Option<String>) {
+        let title = match hovered {
+            Some(title) => title,
+            None => {
+                self.window_flash = None;
+                return;
+            }
+        };
+        if self.window_flash.as_ref().map(|flash| &flash.title) == Some(&title) {
+            return;
+        }
+        let bounds = Arc::new(Mutex::new(None));
+        let bounds_clone = Arc::clone(&bounds);
+        let lookup_title = title.clone();
+        let repaint_ctx = self.egui_ctx.clone();
+        thread::spawn(move || {
+            match window_finder::get_window_bounds(&lookup_title, false) {
+                Ok(bounds) => {
+                    *bounds_clone.lock().unwrap() = Some(bounds);
+                    repaint_ctx.request_repaint();
+                }
+                Err(e) => info!("Not outlining '{}': {}", lookup_title, e),
+            }
+        });
+        self.window_flash = Some(WindowFlash { title, bounds, started: Instant::now() });
+    }
+
+    /// Outline the hovered window, fading out. Only the part over this app's own window can be drawn
+    fn draw_window_flash(&self, ctx: &egui::Context) {
+        let (flash, bounds, origin) = match (&self.window_flash, self.window_origin) {
+            (Some(flash), Some(origin)) => match *flash.bounds.lock().unwrap() {
+                Some(bounds) => (flash, bounds, origin),
+                None => return,
+            },
+            _ => return,
+        };
+        let elapsed = flash.started.elapsed();
+        if elapsed >= WINDOW_FLASH_DURATION {
+            return;
+        }
+        let fade = 1.0 - elapsed.as_secs_f32() / WINDOW_FLASH_DURATION.as_secs_f32();
+
+        // Window bounds are physical screen pixels; the painter wants points relative to this window
+        let pixels_per_point = ctx.pixels_per_point();
+        let screen_rect = egui::Rect::from_min_size(
+            egui::pos2(bounds.x as f32, bounds.y as f32) / pixels_per_point,
+            egui::vec2(bounds.width as f32, bounds.height as f32) / pixels_per_point,
+        );
+        let rect = screen_rect.translate(-origin.to_vec2());
+        let painter = ctx.layer_painter(egui::LayerId::new(Order::Foreground, egui::Id::new("window_flash")));
+        let alpha = (fade * 255.0) as u8;
+        painter.rect_filled(rect, 0.0, Color32::from_rgba_unmultiplied(255, 200, 0, alpha / 8));
+        painter.rect_stroke(rect, 0.0, Stroke::new(3.0, Color32::from_rgba_unmultiplied(255, 200, 0, alpha)));
+        ctx.request_repaint();
+    }
+
+    /// Start a live re-analysis once typing has paused for `LIVE_PROMPT_DEBOUNCE`. Only one request runs at
+    /// a time; a prompt settled on meanwhile waits in `live_pending`, replacing any older one
+    fn run_live_prompt(&mut self) {
+        if let Some(edited_at) = self.live_edited_at.filter(|_| self.live_prompt) {
+            let waited = edited_at.elapsed();
+            if waited < LIVE_PROMPT_DEBOUNCE {
+                self.egui_ctx.request_repaint_after(LIVE_PROMPT_DEBOUNCE - waited);
+                return;
+            }
+            self.live_edited_at = None;
+            let prompt = self.current_input.trim().to_string();
+            // Slash commands aren't prompts
+            if !prompt.is_empty() && !prompt.starts_with('/') {
+                let mut state_guard = self.state.lock().unwrap();
+                if state_guard.live_in_flight {
+                    // The running request is for an older prompt; drop its reply when it lands
+                    state_guard.live_generation += 1;
+                    state_guard.live_processing = true;
+                }
+                drop(state_guard);
+                self.live_pending = Some(prompt);
+            }
+        }
+        // The running request wakes the UI when it finishes, which starts the pending one
+        if self.live_pending.is_some() && !self.state.lock().unwrap().live_in_flight {
+            if let Some(prompt) = self.live_pending.take() {
+                self.start_live_analysis(prompt);
+            }
+        }
+    }
+
+    /// Drop any pending or running live analysis and its preview
+    fn stop_live_prompt(&mut self) {
+        self.live_edited_at = None;
+        self.live_pending = None;
+        let mut state_guard = self.state.lock().unwrap();
+        state_guard.live_generation += 1;
+        state_guard.live_processing = false;
+        state_guard.live_preview.clear();
+    }
+
+    /// Analyze the capture with a prompt that is still being edited. The request can't be aborted, but a newer
+    /// prompt supersedes it and its reply is dropped. Oversized captures are downscaled without asking
+    fn start_live_analysis(&mut self, prompt: String) {
+        let (image_data_bytes, generation) = {
+            let mut state_guard = self.state.lock().unwrap();
+            if state_guard.image_data.is_empty() || screenshot::ensure_analyzable(&state_guard.image_data).is_err() {
+                state_guard.live_processing = false;
+                return;
+            }
+            state_guard.live_generation += 1;
+            state_guard.live_processing = true;
+            state_guard.live_in_flight = true;
+            (state_guard.image_data.clone(), state_guard.live_generation)
+        };
+        let max_payload = (screenshot::encoded_payload_size(&image_data_bytes) > self.max_payload_bytes())
+            .then_some(self.max_payload_bytes());
+        let source = self.screenshot_manager.lock().ok().and_then(|manager| manager.current_capture_source().cloned());
+        let prompt = screenshot::expand_prompt_variables(&prompt, source.as_ref());
+        let model_name = self.model_name.clone();
+        let state_clone = Arc::clone(&self.state);
+        let ollama_host_url_str = self.ollama_url();
+        let system_prompt = self.config.system_prompt.clone();
+        let quality = self.config.quality;
+        let roi = self.roi_pixels();
+        info!("Live prompt analysis #{}: '{}'", generation, prompt);
+
+        let repaint_ctx = self.egui_ctx.clone();
+        thread::spawn(move || {
+            std::env::set_var("OLLAMA_HOST", &ollama_host_url_str);
+            let result = LocalModel::new(&model_name).and_then(|mut ai_model| {
+                ai_model.set_prompt(&prompt);
+                ai_model.set_system_prompt(system_prompt.as_deref());
+                quality.apply(&mut ai_model);
+                analyze_capture(&mut ai_model, &image_data_bytes, roi, max_payload)
+            });
+            let reply = match result {
+                Ok(response) => response,
+                Err(e) => {
+                    error!("Live prompt analysis failed: {}", e);
+                    analysis_failure_reply(&e, &model_name)
+                }
+            };
             let mut state_guard = state_clone.lock().unwrap();
-            state_guard.processing = false;
+            state_guard.live_in_flight = false;
+            if state_guard.live_generation == generation {
+                state_guard.live_preview = reply;
+                state_guard.live_processing = false;
+            } else {
+                info!("Dropping superseded live prompt analysis #{}", generation);
+            }
+            drop(state_guard);
+            repaint_ctx.request_repaint();
         });
     }
 
+    fn annotate_image(&mut self) {
+        let source_image = self.screenshot_manager.lock().ok().and_then(|m| m.get_current_image().cloned());
+        let (image_data_bytes, source_image) = {
+            let mut state_guard = self.state.lock().unwrap();
+            match source_image {
+                Some(image) if !state_guard.image_data.is_empty() => {
+                    if let Err(e) = screenshot::ensure_analyzable(&state_guard.image_data) {
+                        warn!("Refusing to annotate image: {}", e);
+                        state_guard.ai_response = format!("The {}.", e);
+                        return;
+                    }
+                    (state_guard.image_data.clone(), image)
+                }
+                _ => {
+                    state_guard.ai_response = "Please capture an image first.".to_string();
+                    return;
+                }
+            }
+        };
+        let model_name = self.model_name.clone();
+        let state_clone = Arc::clone(&self.state);
+        let screenshot_manager_clone = Arc::clone(&self.screenshot_manager);
+        let ollama_host_url_str = self.ollama_url();
+        let system_prompt = self.config.system_prompt.clone();
+        let quality = self.config.quality;
+
+        let reply_id = self.reserve_message_id();
+        {
+            let mut state_guard = self.state.lock().unwrap();
+            state_guard.processing = true;
+            state_guard.response_id = Some(reply_id);
+            state_guard.ai_response = "Locating UI elements...".to_string();
+        }
+        info!("Starting AI annotation for image.");
+
+        let repaint_ctx = self.egui_ctx.clone();
+        thread::spawn(move || {
+            std::env::set_var("OLLAMA_HOST", &ollama_host_url_str);
+            let result = LocalModel::new(&model_name).and_then(|mut ai_model| {
+                ai_model.set_prompt(annotate::ANNOTATION_PROMPT);
+                ai_model.set_system_prompt(system_prompt.as_deref());
+                quality.apply(&mut ai_model);
+                ai_model.process_image(&image_data_bytes)
+            });
+            let reply = match result {
+                Ok(response) => {
+                    let boxes = annotate::parse_bounding_boxes(&response);
+                    if boxes.is_empty() {
+                        format!("The model did not return any usable bounding boxes.\n\nRaw response:\n{}", response)
+                    } else {
+                        let annotated = annotate::draw_bounding_boxes(&source_image, &boxes);
+                        let labels: Vec<&str> = boxes.iter().map(|b| b.label.as_str()).filter(|l| !l.is_empty()).collect();
+                        if let Ok(mut manager) = screenshot_manager_clone.lock() {
+                            let source = manager.current_capture_source().cloned();
+                            manager.set_current_image(annotated, source);
+                            if let Ok(image_data_bytes) = manager.get_current_image_data() {
+                                let mut state_guard = state_clone.lock().unwrap();
+                                state_guard.image_data = image_data_bytes;
+                                state_guard.current_image = None;
+                            }
+                        }
+                        info!("AI annotation complete with {} boxes.", boxes.len());
+                        format!("Annotated {} UI element(s): {}", boxes.len(), labels.join(", "))
+                    }
+                }
+                Err(e) => {
+                    error!("AI annotation error: {}", e);
+                    format!("AI annotation failed: {}", e)
+                }
+            };
+            state_clone.lock().unwrap().finish_reply(reply_id, reply);
+            repaint_ctx.request_repaint();
+        });
+    }
+
+    /// Ask the model what changed between the previous capture and the current one
+    fn describe_changes(&mut self) {
+        let images = self.screenshot_manager.lock().ok().and_then(|manager| {
+            Some((manager.history().back()?.clone(), manager.get_current_image()?.clone()))
+        });
+        let (before, after) = match images {
+            Some(images) => images,
+            None => {
+                let mut state_guard = self.state.lock().unwrap();
+                state_guard.ai_response = "Comparing needs at least two captures. Capture again after something changes, then try /changed.".to_string();
+                return;
+            }
+        };
+        let model_name = self.model_name.clone();
+        let state_clone = Arc::clone(&self.state);
+        let system_prompt = self.config.system_prompt.clone();
+        let quality = self.config.quality;
+        let ollama_host_url_str = self.ollama_url();
+
+        let reply_id = self.reserve_message_id();
+        {
+            let mut state_guard = self.state.lock().unwrap();
+            state_guard.processing = true;
+            state_guard.response_id = Some(reply_id);
+            state_guard.ai_response = "Comparing the last two captures...".to_string();
+        }
+        info!("Starting AI comparison of the last two captures.");
+
+        let repaint_ctx = self.egui_ctx.clone();
+        thread::spawn(move || {
+            std::env::set_var("OLLAMA_HOST", &ollama_host_url_str);
+            let result = (|| {
+                let mut images = vec![screenshot::encode_png(&before)?, screenshot::encode_png(&after)?];
+                let mut prompt = diff::CHANGES_PROMPT.to_string();
+                // The overlay points the model at the changed areas, but only makes sense for same-sized captures
+                match diff::pixel_diff(&before, &after) {
+                    Some(pixel_diff) if pixel_diff.changed_pixels == 0 => {
+                        return Ok("The two captures are pixel-for-pixel identical.".to_string());
+                    }
+                    Some(pixel_diff) => {
+                        images.push(screenshot::encode_png(&pixel_diff.overlay)?);
+                        prompt.push_str(diff::OVERLAY_PROMPT_NOTE);
+                    }
+                    None => {}
+                }
+
+                let mut ai_model = LocalModel::new(&model_name)?;
+                ai_model.set_prompt(&prompt);
+                ai_model.set_system_prompt(system_prompt.as_deref());
+                quality.apply(&mut ai_model);
+                let image_refs: Vec<&[u8]> = images.iter().map(|image| image.as_slice()).collect();
+                ai_model.process_images(&image_refs)
+            })();
+            let reply = match result {
+                Ok(response) => {
+                    info!("AI comparison complete.");
+                    response
+                }
+                Err(e) => {
+                    error!("AI comparison error: {}", e);
+                    format!("AI comparison failed: {}", e)
+                }
+            };
+            state_clone.lock().unwrap().finish_reply(reply_id, reply);
+            repaint_ctx.request_repaint();
+        });
+    }
+
+    #[cfg(feature = "clipboard")]
+    fn paste_image_from_clipboard(&mut self) {
+        let pasted = Clipboard::new().and_then(|mut clipboard| clipboard.get_image());
+        let img_data = match pasted {
+            Ok(img_data) => img_data,
+            Err(e) => {
+                info!("No image available on the clipboard: {}", e);
+                let mut state_guard = self.state.lock().unwrap();
+                state_guard.ai_response = "No image found on the clipboard. Copy an image first, then paste it here.".to_string();
+                return;
+            }
+        };
+
+        let rgba = match image::RgbaImage::from_raw(img_data.width as u32, img_data.height as u32, img_data.bytes.into_owned()) {
+            Some(rgba) => rgba,
+            None => {
+                error!("Clipboard image data did not match its reported size {}x{}", img_data.width, img_data.height);
+                let mut state_guard = self.state.lock().unwrap();
+                state_guard.ai_response = "The clipboard image could not be read.".to_string();
+                return;
+            }
+        };
+
+        if let Ok(mut manager) = self.screenshot_manager.lock() {
+            manager.set_current_image(image::DynamicImage::ImageRgba8(rgba), None);
+            if let Ok(image_data_bytes) = manager.get_current_image_data() {
+                let mut state = self.state.lock().unwrap();
+                state.set_capture(image_data_bytes, false);
+                info!("Image pasted from clipboard, image data updated.");
+            }
+        }
+    }
+
     fn save_image(&self, path: PathBuf) {
         if let Ok(manager) = self.screenshot_manager.lock() {
             if let Some(image) = manager.get_current_image() {
-                if let Err(e) = image.save_with_format(&path, ImageFormat::Png) {
+                if let Err(e) = screenshot::save_image(image, &path, self.config.png_compression) {
                     error!("Failed to save image: {}", e);
                 } else {
                     info!("Image saved to: {}", path.display());
@@ -901,30 +3535,115 @@ impl ScreenSnapApp {
         }
     }
 
+    /// Save the image with the latest AI reply and the prompt that produced it
+    fn save_bundle(&self, dir: PathBuf) {
+        let reply_index = match self.chat_history.iter().rposition(|m| !m.is_user) {
+            Some(index) => index,
+            None => {
+                warn!("Nothing to bundle yet: analyze the image first");
+                return;
+            }
+        };
+        let response = &self.chat_history[reply_index].text;
+        // Slash commands aren't prompts; the default prompt was used for those
+        let prompt = reply_index.checked_sub(1)
+            .map(|i| &self.chat_history[i])
+            .filter(|m| m.is_user && !m.text.starts_with('/'))
+            .map(|m| m.text.as_str());
+
+        let result = match self.screenshot_manager.lock() {
+            Ok(manager) => match manager.get_current_image() {
+                Some(image) => {
+                    let meta = bundle::BundleMeta::new(&self.model_name, prompt, image, manager.current_capture_source());
+                    bundle::save_bundle(&dir, image, response, &meta)
+                }
+                None => Err(anyhow::anyhow!("there is no image to save")),
+            },
+            Err(_) => Err(anyhow::anyhow!("the screenshot manager is unavailable")),
+        };
+        if let Err(e) = result {
+            error!("Failed to save bundle: {}", e);
+        }
+    }
+
+    /// OCR the current capture on a worker thread and put the text on the clipboard
+    #[cfg(all(feature = "ocr", feature = "clipboard"))]
+    fn copy_text_from_capture(&mut self) {
+        let image = match self.screenshot_manager.lock().ok().and_then(|manager| manager.get_current_image().cloned()) {
+            Some(image) => image,
+            None => return,
+        };
+        self.state.lock().unwrap().ocr_toast = Some(("Reading text...".to_string(), None));
+        info!("Starting OCR of the current capture.");
+
+        let state_clone = Arc::clone(&self.state);
+        let repaint_ctx = self.egui_ctx.clone();
+        thread::spawn(move || {
+            let message = match crate::ocr::recognize_text(&image) {
+                Ok(text) if text.is_empty() => "No text found in the capture.".to_string(),
+                Ok(text) => match Clipboard::new().and_then(|mut clipboard| clipboard.set_text(text.clone())) {
+                    Ok(()) => {
+                        info!("Copied {} characters of recognized text", text.len());
+                        text
+                    }
+                    Err(e) => {
+                        error!("Failed to copy text to clipboard: {}", e);
+                        format!("Couldn't copy the text: {}\n\n{}", e, text)
+                    }
+                },
+                Err(e) => {
+                    error!("OCR failed: {}", e);
+                    format!("Text recognition failed: {}", e)
+                }
+            };
+            state_clone.lock().unwrap().ocr_toast = Some((message, Some(Instant::now())));
+            repaint_ctx.request_repaint();
+        });
+    }
+
+    /// The text from "Copy Text", shown for a few seconds and kept out of the chat history
+    #[cfg(all(feature = "ocr", feature = "clipboard"))]
+    fn draw_ocr_toast(&self, ui: &mut Ui) {
+        let mut state_guard = self.state.lock().unwrap();
+        let (text, shown_at) = match &state_guard.ocr_toast {
+            Some(toast) => toast.clone(),
+            None => return,
+        };
+        if let Some(shown_at) = shown_at {
+            let elapsed = shown_at.elapsed();
+            if elapsed >= OCR_TOAST_DURATION {
+                state_guard.ocr_toast = None;
+                return;
+            }
+            self.egui_ctx.request_repaint_after(OCR_TOAST_DURATION - elapsed);
+        }
+        drop(state_guard);
+
+        ui.add_space(4.0);
+        egui::Frame::none()
+            .fill(Color32::from_rgb(40, 60, 45))
+            .rounding(8.0)
+            .inner_margin(8.0)
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new("Copy Text").small().color(Color32::from_rgb(150, 200, 160)));
+                    if shown_at.is_none() {
+                        ui.spinner();
+                    }
+                });
+                ScrollArea::vertical().id_source("ocr_toast").max_height(120.0).show(ui, |ui| {
+                    ui.label(RichText::new(text).size(13.0));
+                });
+            });
+    }
+
     fn copy_image_to_clipboard(&self) {
         #[cfg(feature = "clipboard")]
         {
             if let Ok(manager) = self.screenshot_manager.lock() {
                 if let Some(image) = manager.get_current_image() {
-                    let width = image.width() as usize;
-                    let height = image.height() as usize;
-                    let rgba8 = image.to_rgba8();
-                    match Clipboard::new() {
-                        Ok(mut clipboard) => {
-                            let img_data = ImageData {
-                                width,
-                                height,
-                                bytes: rgba8.as_raw().into(),
-                            };
-                            if let Err(e) = clipboard.set_image(img_data) {
-                                error!("Failed to copy image to clipboard: {}", e);
-                            } else {
-                                info!("Image copied to clipboard");
-                            }
-                        }
-                        Err(e) => {
-                            error!("Failed to access clipboard: {}", e);
-                        }
+                    if let Err(e) = clipboard::copy_image(image, self.config.clipboard_format) {
+                        error!("{}", e);
                     }
                 }
             }
@@ -938,39 +3657,82 @@ impl ScreenSnapApp {
     }
 }
 
-pub fn run_gui() -> Result<()> {
+pub fn run_gui(mut options: GuiOptions) -> Result<()> {
     info!("ScreenSnap GUI starting up...");
 
+    if options.transparent && !transparency_supported() {
+        warn!("run_gui: Transparent windows are not supported (no compositor?). Falling back to an opaque, decorated window.");
+        options.transparent = false;
+        options.decorated = true;
+    }
+
+    let result = match options.renderer {
+        Some(renderer) => run_with_renderer(&options, renderer)?,
+        None => match run_with_renderer(&options, GuiRenderer::Glow)? {
+            Err(e) if is_graphics_init_error(&e) => {
+                warn!("run_gui: Could not create a GPU context ({}). Retrying with software rendering.", e);
+                run_with_renderer(&options, GuiRenderer::Software)?
+            }
+            other => other,
+        },
+    };
+    result.map_err(|e| anyhow::anyhow!("Failed to start GUI: {}", e))?;
+
+    Ok(())
+}
+
+/// Start the GUI with one renderer. The outer error means the renderer isn't available in this build
+fn run_with_renderer(options: &GuiOptions, renderer: GuiRenderer) -> Result<Result<(), eframe::Error>> {
     let (mon_abs_x, mon_abs_y, mon_width, mon_height) = get_primary_monitor_info();
 
     let initial_window_width = HANDLE_WIDTH;
     let initial_window_height = CLOSED_WINDOW_HEIGHT; 
 
     let desired_x = mon_abs_x + mon_width - initial_window_width;
-    let desired_y = mon_abs_y + mon_height - initial_window_height - TASKBAR_BUFFER; 
+    let desired_y = get_primary_work_area_bottom(mon_abs_y, mon_height) - initial_window_height;
 
     info!("run_gui: Calculated initial window state: pos=({},{}), size=({},{})",
            desired_x, desired_y, initial_window_width, initial_window_height);
 
-    let native_options = eframe::NativeOptions {
+    let mut native_options = eframe::NativeOptions {
         initial_window_pos: Some(egui::pos2(desired_x.max(0.0), desired_y.max(0.0))),
         initial_window_size: Some(egui::vec2(initial_window_width, initial_window_height)), 
-        transparent: true,
-        decorated: false,
-        always_on_top: true,
+        transparent: options.transparent,
+        decorated: options.decorated,
+        always_on_top: options.always_on_top,
         fullscreen: false,
         resizable: false, 
         ..eframe::NativeOptions::default()
     };
+    match renderer {
+        GuiRenderer::Glow => native_options.renderer = eframe::Renderer::Glow,
+        #[cfg(feature = "wgpu")]
+        GuiRenderer::Wgpu => native_options.renderer = eframe::Renderer::Wgpu,
+        #[cfg(not(feature = "wgpu"))]
+        GuiRenderer::Wgpu => {
+            return Err(anyhow::anyhow!("This build has no wgpu renderer; rebuild with --features wgpu"));
+        }
+        GuiRenderer::Software => {
+            // Mesa picks llvmpipe when asked for software GL; other drivers ignore this
+            std::env::set_var("LIBGL_ALWAYS_SOFTWARE", "1");
+            native_options.renderer = eframe::Renderer::Glow;
+            native_options.hardware_acceleration = eframe::HardwareAcceleration::Off;
+            native_options.multisampling = 0;
+        }
+    }
+    info!("run_gui: Using the {:?} renderer", renderer);
 
-    eframe::run_native(
+    let options = options.clone();
+    Ok(eframe::run_native(
         "ScreenSnap",
         native_options,
-        Box::new(|_cc| {
-            Box::new(ScreenSnapApp::default())
+        Box::new(move |cc| {
+            Box::new(ScreenSnapApp::new(&options, cc.egui_ctx.clone()))
         }),
-    )
-    .map_err(|e| anyhow::anyhow!("Failed to start GUI: {}", e))?;
+    ))
+}
 
-    Ok(())
+/// Whether eframe failed while setting up OpenGL, as happens on VMs and remote desktops without a GPU
+fn is_graphics_init_error(e: &eframe::Error) -> bool {
+    matches!(e, eframe::Error::Glutin(_) | eframe::Error::NoGlutinConfigs(..))
 }
\ No newline at end of file
@@ -1,14 +1,30 @@
 // src/main.rs
 use anyhow::Result;
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use log::{info, error, warn};
-use image::ImageFormat;
 use std::path::PathBuf;
 use std::io::BufRead;
-use crate::ai::connector::AiConnector;
+use crate::ai::connector::{AiBackend, AiConnector};
+use crate::ai::local_model::ResponseFormat;
+use crate::ai::preprocess::PreprocessOp;
+use crate::capture::backend::CaptureBackendKind;
+use crate::capture::screenshot::PngCompression;
 
 mod capture;
 mod ai;
+mod batch;
+mod bundle;
+mod clipboard;
+mod config;
+mod countdown;
+mod markdown;
+mod naming;
+mod notes;
+#[cfg(feature = "ocr")]
+mod ocr;
+mod prompts;
+mod rerun;
+mod stats;
 mod gui; // GUI module
 
 #[derive(Parser)]
@@ -17,32 +33,383 @@ mod gui; // GUI module
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    
+    /// Screen capture backend
+    #[arg(long, global = true, value_enum, default_value_t = CaptureBackendKind::Auto)]
+    capture_backend: CaptureBackendKind,
+    
+    /// Use this profile from the config file instead of the active one
+    #[arg(long, global = true)]
+    profile: Option<String>,
+    
+    /// Number of worker threads for converting large captures (default: one per core)
+    #[cfg(feature = "parallel")]
+    #[arg(long, global = true)]
+    conversion_threads: Option<usize>,
+}
+
+#[derive(Args, Clone)]
+struct CaptureArgs {
+    /// Ollama model name (e.g., "llava:latest")
+    #[arg(long, short = 'm')]
+    model: Option<String>,
+    
+    /// Ollama server URL (default: http://localhost:11434)
+    #[arg(long)]
+    ollama_url: Option<String>,
+    
+    /// Save screenshot to file; repeat to save several copies, each in the format of its extension (e.g. --save a.png --save a.jpg)
+    #[arg(long, group = "output")]
+    save: Vec<PathBuf>,
+    
+    /// Create missing folders for --save, --save-dir and --name-template paths instead of stopping with an error
+    #[arg(long)]
+    mkdir: bool,
+    
+    /// Save into this directory with a name built from --name-template
+    #[arg(long, group = "output")]
+    save_dir: Option<PathBuf>,
+    
+    /// File name template for --save-dir: {date}, {time}, {window}, {monitor} and {n} (e.g. "{date}-{window}-{n}.png")
+    #[arg(long)]
+    name_template: Option<String>,
+    
+    /// PNG compression: fast (~30x quicker, ~2x larger), default, or best (~3x slower, slightly smaller)
+    #[arg(long, value_enum, default_value_t = PngCompression::Default)]
+    png_compression: PngCompression,
+    
+    /// Copy the capture to the clipboard
+    #[cfg(feature = "clipboard")]
+    #[arg(long)]
+    clipboard: bool,
+    
+    /// Clipboard image format (default: the clipboard_format setting, raw RGBA unless changed)
+    #[cfg(feature = "clipboard")]
+    #[arg(long, value_enum, requires = "clipboard")]
+    clipboard_format: Option<clipboard::ClipboardFormat>,
+    
+    /// Open the saved screenshot with this program (the system default when no program is given)
+    #[arg(long, num_args = 0..=1, default_missing_value = "", value_name = "CMD", requires = "output")]
+    open_with: Option<String>,
+    
+    /// Window title to capture (optional)
+    #[arg(long, group = "window_target", conflicts_with_all = ["monitor", "region"])]
+    window: Option<String>,
+    
+    /// Capture the first window whose title matches this regex (e.g. "^Editor - .* \(pid \d+\)$").
+    /// Use either this or --window, not both: --window matches part of a title, this matches a pattern
+    #[arg(long, value_name = "PATTERN", group = "window_target", value_parser = parse_regex, conflicts_with_all = ["monitor", "region"])]
+    window_regex: Option<regex::Regex>,
+    
+    /// With --window-regex, capture every matching window one after another instead of the first
+    #[arg(long, requires = "window_regex", conflicts_with_all = ["window", "save", "rerun_with"])]
+    all_matches: bool,
+    
+    /// Capture only the window's content, without title bar and borders
+    #[arg(long, requires = "window_target")]
+    client_only: bool,
+    
+    /// Capture the window's whole monitor and draw a border around the window
+    #[arg(long, requires = "window_target")]
+    highlight_window: bool,
+    
+    /// Wait this many seconds before capturing, with an on-screen countdown that Esc cancels
+    #[arg(long, value_name = "SECS")]
+    delay: Option<f32>,
+    
+    /// Experimental: scroll the window down N times and stitch the captures into one tall image
+    #[arg(long, value_name = "N", requires = "window_target", conflicts_with = "highlight_window")]
+    scroll: Option<u32>,
+    
+    /// Rotate the capture clockwise before saving or analyzing it
+    #[arg(long, value_enum)]
+    rotate: Option<capture::screenshot::Rotation>,
+    
+    /// Mirror the capture horizontally (h) or vertically (v), after any --rotate
+    #[arg(long, value_enum)]
+    flip: Option<capture::screenshot::Flip>,
+    
+    /// Resize every capture to exactly this size before saving or analyzing it, e.g. 1280x720
+    #[arg(long, value_name = "WxH", value_parser = parse_size)]
+    output_size: Option<(u32, u32)>,
+    
+    /// How --output-size fits the capture: letterbox (contain), crop (cover) or distort (stretch)
+    #[arg(long, value_enum, default_value_t = capture::screenshot::Fit::Contain, requires = "output_size")]
+    fit: capture::screenshot::Fit,
+    
+    /// Don't correct captures that come back sideways from a rotated monitor
+    #[arg(long)]
+    no_auto_orient: bool,
+    
+    /// Trade analysis detail for speed: fast downscales and caps the reply, accurate keeps full resolution
+    /// and allows longer replies (default: the quality setting, balanced)
+    #[arg(long, value_enum)]
+    quality: Option<ai::local_model::Quality>,
+    
+    /// Offer to downscale captures whose request would exceed this many KiB (default: the max_payload_kb setting, 4096)
+    #[arg(long, value_name = "KIB")]
+    max_payload_kb: Option<u64>,
+    
+    /// Monitor index to capture, starting at 0 (optional)
+    #[arg(long)]
+    monitor: Option<usize>,
+    
+    /// Region to capture as x,y,w,h - relative to --monitor when given, otherwise absolute
+    #[arg(long, value_parser = parse_region)]
+    region: Option<Region>,
+    
+    /// Region of interest in the captured image as x,y,w,h: sent cropped, with the full capture for context
+    #[arg(long, value_parser = parse_region)]
+    roi: Option<Region>,
+    
+    /// Find this text on the capture with OCR and analyze only the area around it
+    #[cfg(feature = "ocr")]
+    #[arg(long, value_name = "TEXT", conflicts_with_all = ["roi", "no_ai"])]
+    find: Option<String>,
+    
+    /// Pick the region in the terminal: move the mouse and press Enter at two corners
+    #[arg(long, conflicts_with_all = ["region", "window_target", "monitor"])]
+    pick_region: bool,
+    
+    /// Capture the whole monitor the mouse cursor is on
+    #[arg(long, conflicts_with_all = ["region", "window_target", "monitor", "pick_region"])]
+    cursor_monitor: bool,
+    
+    /// Capture a square of 2 x PX pixels centered on the mouse cursor, kept inside its monitor near the edges
+    #[arg(long, value_name = "PX", conflicts_with_all = ["region", "window_target", "monitor", "pick_region", "cursor_monitor"])]
+    cursor_area: Option<u32>,
+    
+    /// Leave out the taskbar, dock and panels: capture only the usable area of --monitor (default: the primary one)
+    #[arg(long, conflicts_with_all = ["region", "window_target", "cursor_monitor", "cursor_area", "pick_region"])]
+    work_area: bool,
+    
+    /// Write the monitor's pixels to FILE unconverted, as BGRA (4 bytes per pixel: blue, green, red, alpha;
+    /// rows top to bottom, no padding), and do nothing else. Captures --monitor, or the primary one
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["region", "window_target", "cursor_monitor", "cursor_area", "work_area", "pick_region", "rerun_with", "output"])]
+    raw_bgra: Option<PathBuf>,
+    
+    /// Skip AI analysis - just capture and save
+    #[arg(long)]
+    no_ai: bool,
+    
+    /// Print the resolved target, model, prompt and payload estimate, then exit without capturing
+    #[arg(long)]
+    dry_run: bool,
+    
+    /// Analyze even if the model doesn't look like it supports images
+    #[arg(long)]
+    force: bool,
+    
+    /// Print the prompt (and system prompt) to stderr before sending it
+    #[arg(short, long)]
+    verbose: bool,
+    
+    /// Store the model, prompt, time and capture source in saved PNGs (read them back with `screensnap inspect`)
+    #[arg(long)]
+    embed_metadata: bool,
+    
+    /// Also write image.png, response.txt and meta.json into a timestamped folder in this directory
+    #[arg(long, value_name = "DIR", conflicts_with = "no_ai")]
+    bundle: Option<PathBuf>,
+    
+    /// Ask for the analysis prompt on stdin after capturing
+    #[arg(long)]
+    interactive_prompt: bool,
+    
+    /// Use a prompt saved with `screensnap prompts add`
+    #[arg(long, value_name = "NAME", conflicts_with = "interactive_prompt")]
+    prompt_name: Option<String>,
+    
+    /// Skip capturing and ask this Ollama model about the last analyzed image and prompt again
+    #[arg(long, value_name = "MODEL", conflicts_with_all = ["no_ai", "model", "backend", "region", "window_target", "monitor", "cursor_monitor", "cursor_area", "work_area", "pick_region", "delay"])]
+    rerun_with: Option<String>,
+    
+    /// Filters applied before analysis, comma-separated (e.g. grayscale,contrast)
+    #[arg(long, value_enum, value_delimiter = ',')]
+    preprocess: Vec<PreprocessOp>,
+    
+    /// Extra HTTP header sent to Ollama as "Key: Value" (repeatable)
+    #[arg(long = "header", value_name = "KEY: VALUE", value_parser = parse_header)]
+    headers: Vec<(String, String)>,
+    
+    /// AI backend used for analysis; give several (e.g. ollama,command) to fall back
+    /// to the next one when a backend can't be reached
+    #[arg(long, value_enum, value_delimiter = ',', default_value = "ollama")]
+    backend: Vec<AiBackend>,
+    
+    /// Command run by `--backend command`; {image} is the screenshot path and {prompt} the prompt,
+    /// e.g. "llama-mtmd-cli -m model.gguf --mmproj mmproj.gguf --image {image} -p {prompt}"
+    #[arg(long, value_name = "COMMAND")]
+    cmd: Option<String>,
+    
+    /// Seconds to wait for `--backend command` before killing it
+    #[arg(long, value_name = "SECS", default_value_t = ai::subprocess_model::DEFAULT_TIMEOUT_SECS)]
+    cmd_timeout: u64,
+    
+    /// Response format to request from the model
+    #[arg(long, value_enum, default_value_t = ResponseFormat::Text)]
+    format: ResponseFormat,
+    
+    /// Print only the model's reply, exactly as sent, without the surrounding banner
+    #[arg(long)]
+    raw: bool,
+    
+    /// Recapture up to N times if the screenshot comes back blank (e.g. all black)
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    retry_if_blank: u32,
+}
+
+#[derive(Args)]
+struct AssertArgs {
+    /// Window title to capture instead of the full screen
+    #[arg(long)]
+    window: Option<String>,
+    
+    /// Reference image the capture must match
+    #[arg(long)]
+    expect: PathBuf,
+    
+    /// Lowest similarity that passes, from 0 to 1 (1 = identical)
+    #[arg(long, default_value_t = 0.98)]
+    threshold: f64,
+    
+    /// On failure, write an image highlighting the differing pixels here
+    #[arg(long)]
+    diff_out: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct WatchArgs {
+    /// Seconds between captures (minimum 1)
+    #[arg(long, default_value_t = 10.0)]
+    interval: f32,
+    
+    /// Stop after this many captures (default: run until Ctrl+C)
+    #[arg(long)]
+    count: Option<u32>,
+    
+    /// Directory the captures are written to
+    #[arg(long)]
+    out_dir: PathBuf,
+    
+    /// Window title to capture instead of the full screen
+    #[arg(long)]
+    window: Option<String>,
+    
+    /// Reuse the window's position for up to 30 seconds instead of looking it up every capture
+    #[arg(long, requires = "window")]
+    cache_bounds: bool,
+    
+    /// File name template: {date}, {time}, {window}, {monitor} and {n}
+    #[arg(long)]
+    name_template: Option<String>,
+    
+    /// PNG compression: fast (~30x quicker, ~2x larger), default, or best (~3x slower, slightly smaller)
+    #[arg(long, value_enum, default_value_t = PngCompression::Fast)]
+    png_compression: PngCompression,
+    
+    /// Analyze each capture and write the response next to the image
+    #[arg(long)]
+    analyze: bool,
+    
+    /// Ollama model name used with --analyze (e.g., "llava:latest")
+    #[arg(long, short = 'm')]
+    model: Option<String>,
+    
+    /// Ollama server URL (default: http://localhost:11434)
+    #[arg(long)]
+    ollama_url: Option<String>,
+    
+    /// Print one JSON object per saved capture on stdout (status messages move to stderr)
+    #[arg(long)]
+    jsonl: bool,
+    
+    /// Print a record per saved capture on stdout in this format (status messages move to stderr);
+    /// csv writes a header row, then filename, timestamp, model, prompt, response and error columns
+    #[arg(long, value_enum, conflicts_with = "jsonl")]
+    out_format: Option<batch::OutFormat>,
+}
+
+#[derive(Args)]
+struct BatchArgs {
+    /// Directory containing the images to analyze
+    dir: PathBuf,
+    
+    /// Only analyze files whose name matches this pattern (e.g. "*.png")
+    #[arg(long)]
+    glob: Option<String>,
+    
+    /// Results file: JSON lines, or CSV when the name ends in .csv
+    #[arg(long)]
+    out: PathBuf,
+    
+    /// Results format, overriding the one picked from the --out file name
+    #[arg(long, value_enum)]
+    out_format: Option<batch::OutFormat>,
+    
+    /// Prompt sent with every image (default: a general description)
+    #[arg(long)]
+    prompt: Option<String>,
+    
+    /// Ollama model name (e.g., "llava:latest")
+    #[arg(long, short = 'm')]
+    model: Option<String>,
+    
+    /// Ollama server URL (default: http://localhost:11434)
+    #[arg(long)]
+    ollama_url: Option<String>,
+    
+    /// How long Ollama keeps the model loaded between files
+    #[arg(long, default_value = "30m")]
+    keep_alive: String,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Region {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+}
+
+fn parse_region(value: &str) -> std::result::Result<Region, String> {
+    let parts: Vec<&str> = value.split(',').map(|p| p.trim()).collect();
+    if parts.len() != 4 {
+        return Err(format!("expected x,y,w,h but got '{}'", value));
+    }
+    let x = parts[0].parse::<i32>().map_err(|e| format!("invalid x '{}': {}", parts[0], e))?;
+    let y = parts[1].parse::<i32>().map_err(|e| format!("invalid y '{}': {}", parts[1], e))?;
+    let width = parts[2].parse::<u32>().map_err(|e| format!("invalid width '{}': {}", parts[2], e))?;
+    let height = parts[3].parse::<u32>().map_err(|e| format!("invalid height '{}': {}", parts[3], e))?;
+    Ok(Region { x, y, width, height })
+}
+
+fn parse_size(value: &str) -> std::result::Result<(u32, u32), String> {
+    let (width, height) = value.split_once(['x', 'X'])
+        .ok_or_else(|| format!("expected WxH but got '{}'", value))?;
+    let width = width.trim().parse::<u32>().map_err(|e| format!("invalid width '{}': {}", width, e))?;
+    let height = height.trim().parse::<u32>().map_err(|e| format!("invalid height '{}': {}", height, e))?;
+    if width == 0 || height == 0 {
+        return Err(format!("width and height must be positive, got '{}'", value));
+    }
+    Ok((width, height))
+}
+
+fn parse_regex(value: &str) -> std::result::Result<regex::Regex, String> {
+    regex::Regex::new(value).map_err(|e| format!("invalid regex: {}", e))
+}
+
+fn parse_header(value: &str) -> std::result::Result<(String, String), String> {
+    match value.split_once(':') {
+        Some((key, header_value)) if !key.trim().is_empty() => Ok((key.trim().to_string(), header_value.trim().to_string())),
+        _ => Err(format!("expected \"Key: Value\" but got '{}'", value)),
+    }
 }
 
 #[derive(Subcommand)]
 enum Commands {
     /// Capture and analyze a screenshot with local Ollama
-    Capture {
-        /// Ollama model name (e.g., "llava:latest")
-        #[arg(long, short = 'm')]
-        model: Option<String>,
-        
-        /// Ollama server URL (default: http://localhost:11434)
-        #[arg(long)]
-        ollama_url: Option<String>,
-        
-        /// Save screenshot to file
-        #[arg(long)]
-        save: Option<PathBuf>,
-        
-        /// Window title to capture (optional)
-        #[arg(long)]
-        window: Option<String>,
-        
-        /// Skip AI analysis - just capture and save
-        #[arg(long)]
-        no_ai: bool,
-    },
+    Capture(CaptureArgs),
     /// List available windows
     ListWindows,
     /// List available Ollama models
@@ -66,10 +433,171 @@ enum Commands {
         #[arg(long)]
         ollama_url: Option<String>,
     },
+    /// Look for a running Ollama server on the usual ports
+    Discover,
     /// Run simple interactive mode
-    Interactive,
+    Interactive {
+        /// Analyze every capture right away instead of asking (default: the auto_analyze setting)
+        #[arg(long, overrides_with = "no_auto_analyze")]
+        auto_analyze: bool,
+        
+        /// Ask before analyzing each capture, even when the auto_analyze setting is on
+        #[arg(long, overrides_with = "auto_analyze")]
+        no_auto_analyze: bool,
+    },
+    /// Run several models on the same capture and compare them
+    Bench {
+        /// Model names to compare (e.g., llava:7b llava:13b)
+        #[arg(required = true)]
+        models: Vec<String>,
+        
+        /// Ollama server URL (default: http://localhost:11434)
+        #[arg(long)]
+        ollama_url: Option<String>,
+    },
+    /// Capture every few seconds into a directory (timelapse)
+    Watch(WatchArgs),
+    /// Analyze every image in a directory and write the responses to a file
+    BatchAnalyze(BatchArgs),
+    /// Capture and compare against a reference image; exits with 1 if they differ too much, 2 on errors
+    Assert(AssertArgs),
+    /// Print the metadata a capture saved with --embed-metadata carries
+    Inspect {
+        /// PNG file to read
+        file: PathBuf,
+    },
+    /// Print an image's embedding vector as a JSON array, for similarity search
+    Embed {
+        /// Image file to embed
+        image: PathBuf,
+        
+        /// Ollama model name (e.g., "llava:latest")
+        #[arg(long, short = 'm')]
+        model: Option<String>,
+        
+        /// Ollama server URL (default: http://localhost:11434)
+        #[arg(long)]
+        ollama_url: Option<String>,
+        
+        /// Write the vector to this file instead of stdout
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Show local usage statistics (enable with `stats enable`)
+    Stats {
+        #[command(subcommand)]
+        action: Option<StatsAction>,
+    },
+    /// Manage saved prompts
+    Prompts {
+        #[command(subcommand)]
+        action: PromptsAction,
+    },
+    /// Inspect the configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Manage named settings profiles (e.g. work and personal)
+    Profile {
+        #[command(subcommand)]
+        action: ProfileAction,
+    },
     /// Run graphical user interface
-    Gui,
+    Gui {
+        /// Don't keep the window above other windows
+        #[arg(long)]
+        no_always_on_top: bool,
+        
+        /// Use the window manager's title bar and borders
+        #[arg(long)]
+        decorated: bool,
+        
+        /// Use an opaque window instead of a transparent one
+        #[arg(long)]
+        opaque: bool,
+        
+        /// Stop the handle animation so the GUI stays idle when unused
+        #[arg(long)]
+        power_save: bool,
+        
+        /// Force a renderer (default: glow, retrying with software rendering if the GPU fails)
+        #[arg(long, value_enum)]
+        renderer: Option<gui::GuiRenderer>,
+    },
+}
+
+#[derive(Subcommand)]
+enum StatsAction {
+    /// Start recording usage statistics locally
+    Enable,
+    /// Stop recording usage statistics (existing stats are kept)
+    Disable,
+    /// Delete the recorded statistics
+    Clear,
+}
+
+#[derive(Subcommand)]
+enum PromptsAction {
+    /// Save a prompt under a name, replacing any prompt already saved with it
+    Add {
+        name: String,
+        prompt: String,
+    },
+    /// List the saved prompts
+    List,
+    /// Delete a saved prompt
+    Remove {
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProfileAction {
+    /// List the profiles; the active one is marked with *
+    List,
+    /// Create a profile overriding the given settings
+    Create {
+        name: String,
+        
+        /// Ollama model name (e.g., "llava:latest")
+        #[arg(long, short = 'm')]
+        model: Option<String>,
+        
+        /// Ollama server URL
+        #[arg(long)]
+        ollama_url: Option<String>,
+        
+        /// System prompt sent with every analysis
+        #[arg(long)]
+        system_prompt: Option<String>,
+    },
+    /// Apply a profile whenever --profile isn't given; omit the name to go back to the base settings
+    Use {
+        name: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Print the settings in effect and where each one comes from
+    Show {
+        /// Ollama model name, as it would be passed to other commands
+        #[arg(long, short = 'm')]
+        model: Option<String>,
+        
+        /// Ollama server URL, as it would be passed to other commands
+        #[arg(long)]
+        ollama_url: Option<String>,
+        
+        /// Response format, as it would be passed to `capture`
+        #[arg(long, value_enum)]
+        format: Option<ResponseFormat>,
+        
+        /// Command backend timeout in seconds, as it would be passed to `capture`
+        #[arg(long)]
+        cmd_timeout: Option<u64>,
+    },
 }
 
 fn main() -> Result<()> {
@@ -80,9 +608,25 @@ fn main() -> Result<()> {
 
     let cli = Cli::parse();
     
+    if let Some(profile) = &cli.profile {
+        if !config::Config::load_file().profiles.contains_key(profile) {
+            return Err(anyhow::anyhow!("No profile named '{}' (see: screensnap profile list)", profile));
+        }
+        // Every later Config::load picks this up
+        std::env::set_var(config::PROFILE_ENV, profile);
+    }
+    
+    // Window rectangles and captures must both be in physical pixels on scaled displays
+    capture::window_finder::enable_dpi_awareness();
+    
+    #[cfg(feature = "parallel")]
+    if let Some(threads) = cli.conversion_threads {
+        rayon::ThreadPoolBuilder::new().num_threads(threads).build_global()?;
+    }
+    
     match cli.command {
-        Commands::Capture { model, ollama_url, save, window, no_ai } => {
-            run_capture_cli(model, ollama_url, save, window, no_ai)
+        Commands::Capture(args) => {
+            run_capture_cli(args, cli.capture_backend)
         }
         Commands::ListWindows => {
             list_windows()
@@ -96,96 +640,881 @@ fn main() -> Result<()> {
         Commands::CheckOllama { ollama_url } => {
             check_ollama_status(ollama_url)
         }
-        Commands::Interactive => {
-            run_interactive_mode()
+        Commands::Discover => {
+            discover_ollama_server()
+        }
+        Commands::Interactive { auto_analyze, no_auto_analyze } => {
+            let auto_analyze = !no_auto_analyze && (auto_analyze || config::Config::load().auto_analyze);
+            run_interactive_mode(cli.capture_backend, auto_analyze)
+        }
+        Commands::Bench { models, ollama_url } => {
+            run_bench(models, ollama_url, cli.capture_backend)
+        }
+        Commands::Watch(args) => {
+            run_watch(args, cli.capture_backend)
+        }
+        Commands::BatchAnalyze(args) => {
+            run_batch_analyze(args)
+        }
+        Commands::Assert(args) => {
+            // A capture or read error must not look like a mismatch to CI
+            if let Err(e) = run_assert(args, cli.capture_backend) {
+                eprintln!("Error: {:?}", e);
+                std::process::exit(ASSERT_ERROR_EXIT_CODE);
+            }
+            Ok(())
+        }
+        Commands::Inspect { file } => {
+            run_inspect(&file)
+        }
+        Commands::Embed { image, model, ollama_url, out } => {
+            run_embed(&image, model, ollama_url, out)
+        }
+        Commands::Stats { action } => {
+            run_stats(action)
+        }
+        Commands::Prompts { action } => {
+            run_prompts(action)
+        }
+        Commands::Config { action } => {
+            run_config(action)
         }
-        Commands::Gui => {
+        Commands::Profile { action } => {
+            run_profile(action)
+        }
+        Commands::Gui { no_always_on_top, decorated, opaque, power_save, renderer } => {
             // Run the new GUI mode
-            gui::run_gui()
+            gui::run_gui(gui::GuiOptions {
+                always_on_top: !no_always_on_top,
+                decorated,
+                transparent: !opaque,
+                capture_backend: cli.capture_backend,
+                power_save,
+                renderer,
+            })
+        }
+    }
+}
+
+/// Capture the screen, window, monitor or region selected by the capture arguments
+fn capture_from_args(screenshot_manager: &mut capture::screenshot::ScreenshotManager, args: &CaptureArgs) -> Result<()> {
+    if let Some(window_title) = &args.window {
+        info!("Capturing window: {}", window_title);
+        let captured = if args.highlight_window {
+            screenshot_manager.capture_window_highlighted(window_title)
+        } else if let Some(steps) = args.scroll {
+            warn!("Scroll capture is best effort: it moves the mouse over the window and sends wheel events, \
+                and falls back to a single capture if the frames can't be lined up");
+            screenshot_manager.capture_scrolling_window(window_title, steps)
+        } else {
+            screenshot_manager.capture_window(window_title)
+        };
+        match captured {
+            Ok(_) => info!("Window captured successfully"),
+            Err(e) if capture::backend::is_cancelled(&e) => return Err(e),
+            Err(e) => {
+                error!("Failed to capture window '{}': {}", window_title, e);
+                warn!("Falling back to full screen capture...");
+                screenshot_manager.capture_screen()?;
+            }
+        }
+    } else if let Some(region) = args.region {
+        match args.monitor {
+            Some(monitor) => {
+                if region.x < 0 || region.y < 0 {
+                    return Err(anyhow::anyhow!("Monitor-relative region must not have negative coordinates"));
+                }
+                screenshot_manager.capture_monitor_region(monitor, region.x as u32, region.y as u32, region.width, region.height)?;
+            }
+            None => {
+                screenshot_manager.capture_region(region.x, region.y, region.width, region.height)?;
+            }
+        }
+    } else if args.work_area {
+        screenshot_manager.capture_work_area(args.monitor.unwrap_or_else(primary_monitor_index))?;
+    } else if let Some(monitor) = args.monitor {
+        screenshot_manager.capture_monitor(monitor)?;
+    } else if let Some(radius) = args.cursor_area {
+        let area = screenshot_manager.capture_cursor_area(radius)?;
+        info!("Captured {}x{} around the cursor", area.width, area.height);
+    } else if args.cursor_monitor {
+        match screenshot_manager.capture_cursor_monitor() {
+            Ok(monitor) => info!("Captured monitor {} under the cursor", monitor),
+            Err(e) => {
+                warn!("Failed to capture the monitor under the cursor: {}", e);
+                warn!("Falling back to full screen capture...");
+                screenshot_manager.capture_screen()?;
+            }
+        }
+    } else {
+        info!("Capturing full screen");
+        screenshot_manager.capture_screen()?;
+    }
+    Ok(())
+}
+
+/// Titles of the open windows matching --window-regex, in window list order; an error when none do
+fn matching_window_titles(regex: &regex::Regex) -> Result<Vec<String>> {
+    let titles: Vec<String> = capture::window_finder::get_window_titles()?
+        .into_iter()
+        .filter(|title| regex.is_match(title))
+        .collect();
+    if titles.is_empty() {
+        return Err(anyhow::anyhow!(
+            "No window title matches /{}/. Run `screensnap list-windows` to see the titles",
+            regex.as_str()
+        ));
+    }
+    info!("{} window(s) match /{}/", titles.len(), regex.as_str());
+    Ok(titles)
+}
+
+/// Index of the monitor a plain capture would use, or 0 when no screen reports a size
+fn primary_monitor_index() -> usize {
+    screenshots::Screen::all().ok()
+        .and_then(|screens| capture::backend::select_usable_screen(&screens))
+        .unwrap_or(0)
+}
+
+/// Pixels of context kept around text found with --find
+#[cfg(feature = "ocr")]
+const FIND_PADDING: i32 = 150;
+
+/// Crop the current capture to the area around `query`, leaving it whole if OCR doesn't find the text
+#[cfg(feature = "ocr")]
+fn crop_to_text(screenshot_manager: &mut capture::screenshot::ScreenshotManager, query: &str) -> Result<()> {
+    let image = screenshot_manager.get_current_image()
+        .ok_or_else(|| anyhow::anyhow!("No image captured"))?
+        .clone();
+    let words = ocr::recognize_words(&image)?;
+    let found = match ocr::locate_text(&words, query) {
+        Some(found) => found,
+        None => {
+            warn!("'{}' wasn't found on the capture ({} words read), analyzing all of it", query, words.len());
+            return Ok(());
+        }
+    };
+    let padded = capture::roi::Roi {
+        x: found.x - FIND_PADDING,
+        y: found.y - FIND_PADDING,
+        width: found.width + 2 * FIND_PADDING as u32,
+        height: found.height + 2 * FIND_PADDING as u32,
+    };
+    let area = match padded.clamp_to(image.width(), image.height()) {
+        Some(area) => area,
+        None => return Ok(()),
+    };
+    info!("Found '{}' at {}x{}+{}+{}, analyzing {}x{}+{}+{}", query,
+        found.width, found.height, found.x, found.y, area.width, area.height, area.x, area.y);
+    let cropped = image.crop_imm(area.x as u32, area.y as u32, area.width, area.height);
+    let source = screenshot_manager.current_capture_source().cloned();
+    screenshot_manager.set_current_image(cropped, source);
+    Ok(())
+}
+
+/// What the capture arguments target and its size in pixels where that can be found without capturing
+fn describe_capture_target(args: &CaptureArgs) -> (String, Option<(u32, u32)>) {
+    let screens = screenshots::Screen::all().unwrap_or_default();
+    let monitor_size = |index: usize| screens.get(index).map(|s| (s.display_info.width, s.display_info.height));
+    if let Some(window_title) = &args.window {
+        return match capture::window_finder::get_window_bounds(window_title, args.client_only) {
+            Ok(bounds) => (
+                format!("window '{}' at ({}, {})", window_title, bounds.x, bounds.y),
+                Some((bounds.width.max(0) as u32, bounds.height.max(0) as u32)),
+            ),
+            Err(e) => (format!("window '{}' (not found now: {}; would fall back to the full screen)", window_title, e), None),
+        };
+    }
+    if let Some(region) = args.region {
+        let origin = match args.monitor {
+            Some(monitor) => format!("of monitor {}", monitor),
+            None => "of the desktop".to_string(),
+        };
+        return (format!("region at ({}, {}) {}", region.x, region.y, origin), Some((region.width, region.height)));
+    }
+    if args.work_area {
+        let monitor = args.monitor.unwrap_or_else(primary_monitor_index);
+        return match capture::screenshot::work_area(monitor) {
+            Ok(area) => (format!("work area of monitor {} at ({}, {})", monitor, area.x, area.y), Some((area.width, area.height))),
+            Err(e) => (format!("work area of monitor {} ({})", monitor, e), None),
+        };
+    }
+    if let Some(monitor) = args.monitor {
+        return (format!("monitor {}", monitor), monitor_size(monitor));
+    }
+    if let Some(radius) = args.cursor_area {
+        let side = radius.saturating_mul(2);
+        return (format!("{}x{} square around the cursor (smaller if the monitor is)", side, side), Some((side, side)));
+    }
+    if args.cursor_monitor {
+        let monitor = capture::window_finder::get_cursor_position().ok()
+            .and_then(|(x, y)| capture::screenshot::screen_containing_point(x, y));
+        return match monitor {
+            Some(monitor) => (format!("monitor {} (under the cursor now)", monitor), monitor_size(monitor)),
+            None => ("monitor under the cursor (unknown now; would fall back to the full screen)".to_string(), None),
+        };
+    }
+    match capture::backend::select_usable_screen(&screens) {
+        Some(monitor) => (format!("full screen (monitor {})", monitor), monitor_size(monitor)),
+        None => ("full screen (no usable display found)".to_string(), None),
+    }
+}
+
+/// `capture --dry-run`: show what the flags and config resolve to without capturing or calling a model
+fn print_dry_run(args: &CaptureArgs, last_analysis: Option<&rerun::LastAnalysis>) -> Result<()> {
+    let config = config::Config::load();
+    let (target, size) = match last_analysis {
+        Some(last) => ("the last analyzed capture (--rerun-with)".to_string(), Some((last.image.width(), last.image.height()))),
+        None => describe_capture_target(args),
+    };
+    println!("Target:      {}", target);
+    if let Some(delay) = args.delay {
+        println!("Delay:       {}s, with an on-screen countdown", delay);
+    }
+    match size {
+        Some((width, height)) => println!("Size:        {}x{} px", width, height),
+        None => println!("Size:        unknown"),
+    }
+    if let Some((width, height)) = args.output_size {
+        let fit = clap::ValueEnum::to_possible_value(&args.fit).map(|value| value.get_name().to_string()).unwrap_or_default();
+        println!("Resized to:  {}x{} px ({})", width, height, fit);
+    }
+    let size = args.output_size.or(size);
+    
+    if !args.save.is_empty() {
+        let paths: Vec<String> = args.save.iter().map(|path| path.display().to_string()).collect();
+        println!("Save to:     {}", paths.join(", "));
+    } else if let Some(dir) = args.save_dir.clone().or(config.save_dir.clone()) {
+        let template = args.name_template.clone()
+            .or(config.name_template.clone())
+            .unwrap_or_else(|| naming::DEFAULT_NAME_TEMPLATE.to_string());
+        println!("Save to:     {} as {}", dir.display(), template);
+    } else {
+        println!("Save to:     not saved");
+    }
+    
+    if args.no_ai {
+        println!("Analysis:    skipped (--no-ai)");
+        return Ok(());
+    }
+    let backends: Vec<String> = args.backend.iter()
+        .filter_map(|backend| clap::ValueEnum::to_possible_value(backend).map(|value| value.get_name().to_string()))
+        .collect();
+    println!("Backend:     {}", backends.join(" -> "));
+    if args.backend.contains(&AiBackend::Ollama) {
+        let model = config.resolve_model(args.model.as_deref());
+        let ollama_url = config.resolve_ollama_url(args.ollama_url.as_deref());
+        println!("Model:       {} ({})", model.value, model.source.label());
+        println!("Ollama URL:  {} ({})", ollama_url.value, ollama_url.source.label());
+        let quality = args.quality.unwrap_or(config.quality);
+        println!("Quality:     {}", quality.label());
+    }
+    let prompt = if args.interactive_prompt {
+        "(asked after capturing)".to_string()
+    } else if let Some(name) = &args.prompt_name {
+        prompts::PromptLibrary::load().require(name)?.to_string()
+    } else if let Some(prompt) = last_analysis.and_then(|last| last.prompt.clone()) {
+        prompt
+    } else {
+        ai::local_model::DEFAULT_PROMPT.to_string()
+    };
+    println!("Prompt:      {}", prompt);
+    if let Some(system_prompt) = &config.system_prompt {
+        println!("System:      {}", system_prompt);
+    }
+    
+    // The PNG is usually several times smaller than the raw pixels, so this is an upper bound
+    let max_payload_kb = args.max_payload_kb.unwrap_or(config.max_payload_kb);
+    match size {
+        Some((width, height)) => {
+            let raw_kb = width as u64 * height as u64 * 4 / 1024;
+            println!("Payload:     at most ~{} KiB before PNG compression (limit {} KiB)", raw_kb * 4 / 3, max_payload_kb);
+        }
+        None => println!("Payload:     unknown (limit {} KiB)", max_payload_kb),
+    }
+    Ok(())
+}
+
+/// Text chunks for --embed-metadata: the time, the capture source and, when it is known before analyzing,
+/// the Ollama model and prompt. An --interactive-prompt isn't asked for until after saving, so it is left out
+fn png_metadata(
+    args: &CaptureArgs,
+    source: Option<&capture::screenshot::CaptureSource>,
+    last_analysis: Option<&rerun::LastAnalysis>,
+) -> Vec<(String, String)> {
+    let mut metadata = vec![
+        ("Software".to_string(), format!("screensnap {}", env!("CARGO_PKG_VERSION"))),
+        ("Creation Time".to_string(), chrono::Local::now().to_rfc3339()),
+    ];
+    if let Some(source) = source.and_then(|source| serde_json::to_string(source).ok()) {
+        metadata.push(("Source".to_string(), source));
+    }
+    if args.no_ai || args.backend.as_slice() != [AiBackend::Ollama] {
+        return metadata;
+    }
+    metadata.push(("Model".to_string(), get_model_name(args.model.as_deref())));
+    let prompt = if let Some(name) = &args.prompt_name {
+        prompts::PromptLibrary::load().require(name).ok().map(str::to_string)
+    } else if let Some(last) = last_analysis {
+        Some(last.prompt.clone().unwrap_or_else(|| ai::local_model::DEFAULT_PROMPT.to_string()))
+    } else if !args.interactive_prompt {
+        Some(ai::local_model::DEFAULT_PROMPT.to_string())
+    } else {
+        None
+    };
+    if let Some(prompt) = prompt {
+        metadata.push(("Prompt".to_string(), capture::screenshot::expand_prompt_variables(&prompt, source)));
+    }
+    metadata
+}
+
+/// Name of what the capture arguments target, used as the usage stats key
+fn capture_target_label(args: &CaptureArgs) -> String {
+    if args.window.is_some() {
+        "window".to_string()
+    } else if args.region.is_some() {
+        "region".to_string()
+    } else if args.work_area {
+        "work area".to_string()
+    } else if let Some(monitor) = args.monitor {
+        format!("monitor {}", monitor)
+    } else if args.cursor_monitor {
+        "cursor monitor".to_string()
+    } else if args.cursor_area.is_some() {
+        "cursor area".to_string()
+    } else {
+        "primary".to_string()
+    }
+}
+
+fn run_capture_cli(mut args: CaptureArgs, capture_backend: CaptureBackendKind) -> Result<()> {
+    info!("Starting headless capture mode");
+    
+    if args.pick_region {
+        args.region = Some(pick_region()?);
+    }
+    if let Some(regex) = args.window_regex.take() {
+        let titles = matching_window_titles(&regex)?;
+        if args.all_matches {
+            let count = titles.len();
+            for (i, title) in titles.into_iter().enumerate() {
+                eprintln!("[{}/{}] {}", i + 1, count, title);
+                let mut window_args = args.clone();
+                window_args.window = Some(title);
+                run_capture_cli(window_args, capture_backend)?;
+            }
+            return Ok(());
+        }
+        args.window = titles.into_iter().next();
+    }
+    let last_analysis = match &args.rerun_with {
+        Some(model) => {
+            let last = rerun::load()?;
+            info!("Re-running the last analysis (by {}) with {}", last.model, model);
+            args.model = Some(model.clone());
+            Some(last)
+        }
+        None => None,
+    };
+    if args.dry_run {
+        return print_dry_run(&args, last_analysis.as_ref());
+    }
+    if let Some(delay) = args.delay {
+        if !delay.is_finite() || delay < 0.0 {
+            return Err(anyhow::anyhow!("--delay must be a positive number of seconds"));
+        }
+        if !countdown::run_overlay(std::time::Duration::from_secs_f32(delay))? {
+            eprintln!("Capture cancelled");
+            std::process::exit(CANCELLED_EXIT_CODE);
+        }
+    }
+    
+    // Initialize screenshot manager
+    let mut screenshot_manager = capture::screenshot::ScreenshotManager::with_backend(capture_backend)?;
+    screenshot_manager.set_client_area_only(args.client_only);
+    screenshot_manager.set_auto_orient(!args.no_auto_orient);
+    
+    if let Some(path) = &args.raw_bgra {
+        screenshot_manager.capture_monitor_raw_bgra(args.monitor.unwrap_or_else(primary_monitor_index))?;
+        let (data, width, height) = screenshot_manager.get_current_image_raw_bgra()
+            .ok_or_else(|| anyhow::anyhow!("The raw capture returned no frame"))?;
+        std::fs::write(path, data)?;
+        println!("Saved {}x{} BGRA pixels ({} bytes per row) to {}", width, height, width * 4, path.display());
+        return Ok(());
+    }
+    
+    // Capture screenshot, or reuse the last analyzed one
+    match &last_analysis {
+        Some(last) => screenshot_manager.set_current_image(last.image.clone(), None),
+        None => {
+            match capture_from_args(&mut screenshot_manager, &args) {
+                Err(e) if capture::backend::is_cancelled(&e) => {
+                    println!("Capture cancelled.");
+                    return Ok(());
+                }
+                result => result?,
+            }
+            stats::record_capture(&capture_target_label(&args));
+            let looks_blank = |manager: &capture::screenshot::ScreenshotManager| {
+                manager.get_current_image().map_or(false, capture::screenshot::is_probably_blank)
+            };
+            for attempt in 1..=args.retry_if_blank {
+                if !looks_blank(&screenshot_manager) {
+                    break;
+                }
+                warn!("Capture looks blank, retrying ({}/{})", attempt, args.retry_if_blank);
+                std::thread::sleep(std::time::Duration::from_millis(500));
+                capture_from_args(&mut screenshot_manager, &args)?;
+            }
+            if looks_blank(&screenshot_manager) {
+                if args.retry_if_blank == 0 {
+                    warn!("The capture looks blank. Use --retry-if-blank N to recapture automatically.");
+                } else {
+                    warn!("The capture still looks blank after {} retries", args.retry_if_blank);
+                }
+            }
+        }
+    }
+    if let Some(rotation) = args.rotate {
+        screenshot_manager.rotate_current_image(rotation)?;
+    }
+    if let Some(flip) = args.flip {
+        screenshot_manager.flip_current_image(flip)?;
+    }
+    if let Some((width, height)) = args.output_size {
+        screenshot_manager.resize_current_image(width, height, args.fit)?;
+    }
+    
+    // Save if requested
+    let save_paths = resolve_save_paths(&args, screenshot_manager.current_capture_source())?;
+    let metadata = if args.embed_metadata {
+        png_metadata(&args, screenshot_manager.current_capture_source(), last_analysis.as_ref())
+    } else {
+        Vec::new()
+    };
+    for save_path in &save_paths {
+        if let Err(e) = capture::screenshot::prepare_output_dir(save_path, args.mkdir) {
+            return Err(anyhow::anyhow!("{}. Create it first or pass --mkdir", e));
+        }
+    }
+    if let Some(image) = screenshot_manager.get_current_image() {
+        for save_path in &save_paths {
+            capture::screenshot::save_image_with_metadata(image, save_path, args.png_compression, &metadata)?;
+            info!("Screenshot saved to: {}", save_path.display());
+        }
+        #[cfg(feature = "clipboard")]
+        if args.clipboard {
+            let format = args.clipboard_format.unwrap_or_else(|| config::Config::load().clipboard_format);
+            clipboard::copy_image(image, format)?;
+        }
+        // Open the first copy only
+        if let (Some(program), Some(save_path)) = (&args.open_with, save_paths.first()) {
+            if let Err(e) = open_saved_file(save_path, program) {
+                error!("{}", e);
+                println!("The screenshot is still saved at: {}", save_path.display());
+            }
+        }
+    }
+    
+    // Process with AI if requested
+    if !args.no_ai {
+        #[cfg(feature = "ocr")]
+        if let Some(query) = &args.find {
+            crop_to_text(&mut screenshot_manager, query)?;
+        }
+        let custom_prompt = if args.interactive_prompt {
+            read_prompt_interactively(&screenshot_manager, save_paths.first().map(PathBuf::as_path))?
+        } else if let Some(name) = &args.prompt_name {
+            Some(prompts::PromptLibrary::load().require(name)?.to_string())
+        } else {
+            last_analysis.as_ref().and_then(|last| last.prompt.clone())
+        };
+        let custom_prompt = custom_prompt.map(|prompt| {
+            capture::screenshot::expand_prompt_variables(&prompt, screenshot_manager.current_capture_source())
+        });
+        
+        analyze_current_capture(&screenshot_manager, &args, &config::Config::load(), custom_prompt.as_deref())?;
+    }
+    
+    Ok(())
+}
+
+/// Analyze the current capture with the --backend chain and print the response. A single backend goes
+/// through the same setup as a chain of several, so every capture option applies to both
+fn analyze_current_capture(
+    screenshot_manager: &capture::screenshot::ScreenshotManager,
+    args: &CaptureArgs,
+    config: &config::Config,
+    custom_prompt: Option<&str>,
+) -> Result<()> {
+    if args.roi.is_some() && args.backend.iter().any(|kind| *kind != AiBackend::Ollama) {
+        warn!("--roi is only understood by the Ollama backend; other backends get the capture with the region outlined");
+    }
+    let mut connectors = Vec::new();
+    for kind in &args.backend {
+        connectors.push(build_connector(*kind, args, config, custom_prompt)?);
+    }
+    let mut ai_model = ai::fallback::FallbackConnector::new(connectors);
+    
+    let image_data = screenshot_manager.get_current_image_data()?;
+    capture::screenshot::ensure_analyzable(&image_data)?;
+    if let Err(e) = rerun::save(&image_data, custom_prompt, &ai_model.model()) {
+        warn!("Failed to keep this analysis for --rerun-with: {}", e);
+    }
+    let max_payload_bytes = args.max_payload_kb.unwrap_or(config.max_payload_kb) as usize * 1024;
+    let (image_data, scale) = fit_payload(image_data, max_payload_bytes)?;
+    let roi = args.roi.map(|r| capture::roi::Roi { x: r.x, y: r.y, width: r.width, height: r.height }.scaled(scale));
+    
+    // Process with AI, with a spinner on stderr so stdout stays clean for piping
+    let spinner = analysis_spinner(&ai_model.name());
+    let start = std::time::Instant::now();
+    let result = capture::roi::analyze(&mut ai_model, &image_data, roi);
+    spinner.finish_and_clear();
+    let elapsed = start.elapsed();
+    let model = ai_model.model();
+    stats::record_analysis(&model, elapsed, result.is_ok());
+    if args.verbose {
+        eprintln!("Analysis took {:.1}s", elapsed.as_secs_f32());
+    }
+    let response = match result {
+        Ok(response) => response,
+        Err(e) => {
+            if e.to_string().contains("not found") {
+                eprintln!("\nTo fix this, run:");
+                eprintln!("  ollama pull {}", model);
+            } else if e.to_string().contains("not available") {
+                eprintln!("\nTo fix this, run:");
+                eprintln!("  ollama serve");
+            }
+            return Err(e);
+        }
+    };
+    let served_by = ai_model.last_served_by().unwrap_or_else(|| ai_model.name());
+    print_analysis(config, &served_by, &response, args.raw);
+    save_bundle_if_requested(args.bundle.as_deref(), screenshot_manager, &model, custom_prompt, &response)
+}
+
+/// Print a capture's analysis. With `raw` it is printed exactly as the model wrote it; otherwise the
+/// configured post-processing is applied and it is framed with the name of what produced it
+fn print_analysis(config: &config::Config, served_by: &str, response: &str, raw: bool) {
+    if raw {
+        println!("{}", response);
+        return;
+    }
+    let response = config.post_process(response);
+    println!("\n=== AI Analysis ({}) ===", served_by);
+    println!("{}", response);
+    println!("===========================================\n");
+}
+
+/// Set up one --backend with the capture options, alone or as part of a fallback chain
+fn build_connector(kind: AiBackend, args: &CaptureArgs, config: &config::Config, custom_prompt: Option<&str>) -> Result<Box<dyn AiConnector>> {
+    Ok(match kind {
+        AiBackend::Ollama => {
+            let url = config.resolve_ollama_url(args.ollama_url.as_deref()).value;
+            let model_name = config.resolve_model(args.model.as_deref()).value;
+            info!("Processing with Ollama model: {} at {}", model_name, url);
+            std::env::set_var("OLLAMA_HOST", &url);
+            let mut ai_model = ai::local_model::LocalModel::new(&model_name)?;
+            offer_model_pull(&ai_model, &model_name)?;
+            // Catch text-only models before they fail with an opaque error
+            match ai_model.is_vision_model() {
+                Ok(true) => {}
+                Ok(false) if args.force => {
+                    warn!("Model {} doesn't appear to support images; analyzing anyway (--force)", model_name);
+                }
+                Ok(false) => {
+                    return Err(anyhow::anyhow!(
+                        "Model {} doesn't appear to support images; try llava (or pass --force to try anyway)",
+                        model_name
+                    ));
+                }
+                Err(e) => warn!("Could not check whether {} supports images: {}", model_name, e),
+            }
+            args.quality.unwrap_or(config.quality).apply(&mut ai_model);
+            ai_model.set_preprocess(args.preprocess.clone());
+            ai_model.set_response_format(args.format);
+            ai_model.set_headers(args.headers.clone());
+            if let Some(prompt) = custom_prompt {
+                ai_model.set_prompt(prompt);
+            }
+            if args.roi.is_some() {
+                let prompt = capture::roi::roi_prompt(ai_model.prompt());
+                ai_model.set_prompt(&prompt);
+            }
+            // Stream into a buffer so Ctrl+C can still show what the model said so far
+            ai_model.set_stream_sink(Some(cancel_partial_output()?));
+            if args.verbose {
+                if let Some(system_prompt) = ai_model.system_prompt() {
+                    eprintln!("System prompt: {}", system_prompt);
+                }
+                eprintln!("Prompt: {}", ai_model.prompt());
+            }
+            Box::new(ai_model)
+        }
+        AiBackend::Command => {
+            let command = args.cmd.as_deref()
+                .ok_or_else(|| anyhow::anyhow!("--backend command needs --cmd"))?;
+            let mut ai_model = ai::subprocess_model::SubprocessModel::new(
+                command,
+                std::time::Duration::from_secs(args.cmd_timeout),
+            )?;
+            if let Some(prompt) = custom_prompt {
+                ai_model.set_prompt(prompt);
+            }
+            if args.verbose {
+                eprintln!("Prompt: {}", ai_model.prompt());
+            }
+            Box::new(ai_model)
+        }
+        AiBackend::Mock => {
+            let mut ai_model = ai::mock_model::MockModel::new();
+            if let Some(prompt) = custom_prompt {
+                ai_model.set_prompt(prompt);
+            }
+            Box::new(ai_model)
+        }
+    })
+}
+
+/// Offer to downscale a capture whose request would be larger than `max_bytes`, which risks a timeout.
+/// Without a terminal to ask on it is downscaled automatically. Returns the PNG to send and the scale applied
+fn fit_payload(image_data: Vec<u8>, max_bytes: usize) -> Result<(Vec<u8>, f32)> {
+    use std::io::{self, IsTerminal, Write};
+    
+    let size = capture::screenshot::encoded_payload_size(&image_data);
+    if size <= max_bytes {
+        return Ok((image_data, 1.0));
+    }
+    let (size_mb, limit_mb) = (size as f64 / 1048576.0, max_bytes as f64 / 1048576.0);
+    if io::stdin().is_terminal() {
+        // Ask on stderr so a piped stdout only gets the analysis
+        eprint!("The capture is {:.1} MB once encoded (limit {:.1} MB) and may time out. Downscale it? [Y/n]: ", size_mb, limit_mb);
+        io::stderr().flush()?;
+        if read_stdin_line().trim().eq_ignore_ascii_case("n") {
+            return Ok((image_data, 1.0));
+        }
+    } else {
+        warn!("The capture is {:.1} MB once encoded (limit {:.1} MB); downscaling it", size_mb, limit_mb);
+    }
+    capture::screenshot::downscale_to_fit(&image_data, max_bytes, None)
+}
+
+/// Write the capture and its analysis into a bundle folder when --bundle was given
+fn save_bundle_if_requested(
+    bundle_dir: Option<&std::path::Path>,
+    screenshot_manager: &capture::screenshot::ScreenshotManager,
+    model: &str,
+    prompt: Option<&str>,
+    response: &str,
+) -> Result<()> {
+    let (dir, image) = match (bundle_dir, screenshot_manager.get_current_image()) {
+        (Some(dir), Some(image)) => (dir, image),
+        _ => return Ok(()),
+    };
+    let meta = bundle::BundleMeta::new(model, prompt, image, screenshot_manager.current_capture_source());
+    let path = bundle::save_bundle(dir, image, response, &meta)?;
+    println!("Bundle saved to: {}", path.display());
+    Ok(())
+}
+
+/// Exit code when the user cancels a capture's analysis with Ctrl+C (128 + SIGINT)
+const CANCELLED_EXIT_CODE: i32 = 130;
+
+/// Response streamed so far by the running analysis, shared with the Ctrl+C handler
+static PARTIAL_RESPONSE: std::sync::OnceLock<std::sync::Arc<std::sync::Mutex<String>>> = std::sync::OnceLock::new();
+
+/// An emptied buffer to stream the next analysis into. On Ctrl+C its contents are printed and the process
+/// exits with `CANCELLED_EXIT_CODE`. The handler is installed on the first call only, since a process can
+/// have just one; later analyses (e.g. each window of --all-matches) reuse it
+fn cancel_partial_output() -> Result<std::sync::Arc<std::sync::Mutex<String>>> {
+    if let Some(partial) = PARTIAL_RESPONSE.get() {
+        if let Ok(mut text) = partial.lock() {
+            text.clear();
+        }
+        return Ok(std::sync::Arc::clone(partial));
+    }
+    let partial = std::sync::Arc::new(std::sync::Mutex::new(String::new()));
+    let handler_partial = std::sync::Arc::clone(&partial);
+    ctrlc::set_handler(move || {
+        eprintln!("\nCancelled.");
+        let partial = handler_partial.lock().map(|p| p.clone()).unwrap_or_default();
+        if !partial.is_empty() {
+            println!("\n=== Partial AI Analysis (cancelled) ===");
+            println!("{}", partial);
+            println!("===========================================\n");
         }
+        std::process::exit(CANCELLED_EXIT_CODE);
+    })?;
+    let _ = PARTIAL_RESPONSE.set(std::sync::Arc::clone(&partial));
+    Ok(partial)
+}
+
+/// Spinner shown on stderr while a model runs; hidden when stderr isn't a terminal
+fn analysis_spinner(model_name: &str) -> indicatif::ProgressBar {
+    use std::io::IsTerminal;
+    
+    if !std::io::stderr().is_terminal() {
+        return indicatif::ProgressBar::hidden();
     }
+    let spinner = indicatif::ProgressBar::new_spinner();
+    spinner.set_style(
+        indicatif::ProgressStyle::with_template("{spinner} Analyzing with {msg}... {elapsed}")
+            .unwrap_or_else(|_| indicatif::ProgressStyle::default_spinner()),
+    );
+    spinner.set_message(model_name.to_string());
+    spinner.enable_steady_tick(std::time::Duration::from_millis(100));
+    spinner
 }
 
-fn run_capture_cli(model: Option<String>, ollama_url: Option<String>, save: Option<PathBuf>, window: Option<String>, no_ai: bool) -> Result<()> {
-    info!("Starting headless capture mode");
+/// Where to save the capture: every --save, or a templated name in --save-dir (falling back to the config file).
+/// {window} and {monitor} come from what was actually captured. Missing folders are left to `prepare_output_dir` and --mkdir
+fn resolve_save_paths(args: &CaptureArgs, source: Option<&capture::screenshot::CaptureSource>) -> Result<Vec<PathBuf>> {
+    if !args.save.is_empty() {
+        return Ok(args.save.clone());
+    }
     
-    // Initialize screenshot manager
-    let mut screenshot_manager = capture::screenshot::ScreenshotManager::new()?;
+    let config = config::Config::load();
+    let save_dir = match args.save_dir.clone().or(config.save_dir) {
+        Some(dir) => dir,
+        None if args.name_template.is_some() => PathBuf::from("."),
+        None => return Ok(Vec::new()),
+    };
     
-    // Capture screenshot
-    if let Some(window_title) = window {
-        info!("Capturing window: {}", window_title);
-        match screenshot_manager.capture_window(&window_title) {
-            Ok(_) => info!("Window captured successfully"),
-            Err(e) => {
-                error!("Failed to capture window '{}': {}", window_title, e);
-                warn!("Falling back to full screen capture...");
-                screenshot_manager.capture_screen()?;
+    let template = args.name_template.clone()
+        .or(config.name_template)
+        .unwrap_or_else(|| naming::DEFAULT_NAME_TEMPLATE.to_string());
+    let ctx = naming::FilenameContext {
+        window: source.and_then(|s| s.window_title()),
+        monitor: source.and_then(|s| s.monitor()),
+        timestamp: chrono::Local::now(),
+    };
+    naming::render_filename(&template, &save_dir, &ctx).map(|path| vec![path])
+}
+
+/// Lines typed on stdin, read by one background thread. Prompts in the capture path read from here rather
+/// than from stdin directly, so nothing typed after --pick-region is swallowed by its reader
+static STDIN_LINES: std::sync::OnceLock<std::sync::Mutex<std::sync::mpsc::Receiver<String>>> = std::sync::OnceLock::new();
+
+/// The shared stdin reader, started on first use
+fn stdin_lines() -> std::sync::MutexGuard<'static, std::sync::mpsc::Receiver<String>> {
+    STDIN_LINES.get_or_init(|| {
+        let (tx, rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            for line in std::io::stdin().lock().lines() {
+                match line {
+                    Ok(line) if tx.send(line).is_ok() => {}
+                    _ => break,
+                }
             }
-        }
-    } else {
-        info!("Capturing full screen");
-        screenshot_manager.capture_screen()?;
-    }
+        });
+        std::sync::Mutex::new(rx)
+    }).lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Wait for the next line from `stdin_lines`; empty once stdin is closed, like `read_line` at the end of input
+fn read_stdin_line() -> String {
+    stdin_lines().recv().unwrap_or_default()
+}
+
+/// Let the user mark two corners with the mouse, pressing Enter in the terminal for each
+fn pick_region() -> Result<Region> {
+    use std::io::{self, IsTerminal, Write};
+    use std::sync::mpsc;
+    use std::time::Duration;
     
-    // Save if requested
-    if let Some(save_path) = &save {
-        if let Some(image) = screenshot_manager.get_current_image() {
-            image.save_with_format(save_path, ImageFormat::Png)?;
-            info!("Screenshot saved to: {}", save_path.display());
-        }
+    if !io::stdin().is_terminal() {
+        return Err(anyhow::anyhow!("--pick-region needs an interactive terminal"));
     }
+    // Fail early with a clear message if the cursor can't be read here
+    capture::window_finder::get_cursor_position()
+        .map_err(|e| anyhow::anyhow!("--pick-region is unavailable: {}. Use --region x,y,w,h instead.", e))?;
     
-    // Process with AI if requested
-    if !no_ai {
-        let model_name = model.unwrap_or_else(|| "llava:latest".to_string());
-        let url = get_ollama_url(ollama_url);
-        
-        info!("Processing with Ollama model: {} at {}", model_name, url);
-        
-        // Set Ollama URL as environment variable
-        std::env::set_var("OLLAMA_HOST", &url);
-        
-        // Initialize Ollama model
-        match ai::local_model::LocalModel::new(&model_name) {
-            Ok(mut ai_model) => {
-                // Get image data
-                match screenshot_manager.get_current_image_data() {
-                    Ok(image_data) => {
-                        // Process with AI
-                        match ai_model.process_image(&image_data) {
-                            Ok(response) => {
-                                println!("\n=== AI Analysis (Ollama: {}) ===", model_name);
-                                println!("{}", response);
-                                println!("===========================================\n");
-                            }
-                            Err(e) => {
-                                error!("AI processing failed: {}", e);
-                                
-                                if e.to_string().contains("not found") {
-                                    println!("\nTo fix this, run:");
-                                    println!("  ollama pull {}", model_name);
-                                } else if e.to_string().contains("not available") {
-                                    println!("\nTo fix this, run:");
-                                    println!("  ollama serve");
-                                }
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        error!("Failed to get image data: {}", e);
-                    }
+    // Enter presses arrive from the shared reader thread, so the position keeps updating meanwhile
+    let lines = stdin_lines();
+    let mut corners = Vec::new();
+    for label in ["first corner", "opposite corner"] {
+        loop {
+            let (x, y) = capture::window_finder::get_cursor_position()?;
+            eprint!("\rMove the mouse to the {} and press Enter: ({}, {})      ", label, x, y);
+            io::stderr().flush()?;
+            match lines.recv_timeout(Duration::from_millis(100)) {
+                Ok(_) => {
+                    corners.push((x, y));
+                    break;
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    return Err(anyhow::anyhow!("stdin closed before the region was picked"));
                 }
-            }
-            Err(e) => {
-                error!("Failed to initialize Ollama model: {}", e);
-                println!("\nMake sure Ollama is running: ollama serve");
-                println!("And that the model is available: ollama pull {}", model_name);
             }
         }
     }
+    eprintln!();
     
-    Ok(())
+    let ((x1, y1), (x2, y2)) = (corners[0], corners[1]);
+    let region = Region {
+        x: x1.min(x2),
+        y: y1.min(y2),
+        width: x1.abs_diff(x2),
+        height: y1.abs_diff(y2),
+    };
+    if region.width == 0 || region.height == 0 {
+        return Err(anyhow::anyhow!("The picked corners don't form a rectangle"));
+    }
+    info!("Picked region {}x{}+{}+{}", region.width, region.height, region.x, region.y);
+    Ok(region)
+}
+
+/// Show what was captured and read a prompt from stdin; None means use the default prompt
+fn read_prompt_interactively(screenshot_manager: &capture::screenshot::ScreenshotManager, save_path: Option<&std::path::Path>) -> Result<Option<String>> {
+    use std::io::{self, IsTerminal, Write};
+    
+    if !io::stdin().is_terminal() {
+        warn!("stdin is not a terminal, using the default prompt");
+        return Ok(None);
+    }
+    
+    println!();
+    if let Some(image) = screenshot_manager.get_current_image() {
+        println!("Captured {}x{} image", image.width(), image.height());
+    }
+    if let Some(path) = save_path {
+        println!("Saved to: {}", path.display());
+    }
+    print!("Enter a prompt for the AI (leave empty for the default description): ");
+    io::stdout().flush()?;
+    
+    let input = read_stdin_line();
+    let prompt = input.trim();
+    Ok(if prompt.is_empty() { None } else { Some(prompt.to_string()) })
+}
+
+/// Launch `program` (or the OS default handler when empty) on a saved file
+fn open_saved_file(path: &std::path::Path, program: &str) -> Result<()> {
+    let mut parts = program.split_whitespace();
+    let executable = match parts.next() {
+        Some(executable) => executable,
+        None => {
+            info!("Opening {} with the default application", path.display());
+            return open::that(path)
+                .map_err(|e| anyhow::anyhow!("Failed to open {} with the default application: {}", path.display(), e));
+        }
+    };
+    
+    info!("Opening {} with {}", path.display(), program);
+    std::process::Command::new(executable)
+        .args(parts)
+        .arg(path)
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                anyhow::anyhow!("Program '{}' not found. Check that it is installed and on your PATH.", executable)
+            } else {
+                anyhow::anyhow!("Failed to launch '{}': {}", executable, e)
+            }
+        })
 }
 
 fn list_windows() -> Result<()> {
@@ -208,11 +1537,44 @@ fn list_windows() -> Result<()> {
 }
 
 fn get_ollama_url(url_arg: Option<String>) -> String {
-    url_arg.unwrap_or_else(|| {
-        std::env::var("OLLAMA_HOST").unwrap_or_else(|_| "http://localhost:11434".to_string())
+    config::Config::load().resolve_ollama_url(url_arg.as_deref()).value
+}
+
+/// The model to use: --model, then the config file, then the built-in default
+fn get_model_name(model_arg: Option<&str>) -> String {
+    config::Config::load().resolve_model(model_arg).value
+}
+
+/// A model entry from Ollama's /api/tags
+struct ModelInfo {
+    name: String,
+    size: Option<u64>,
+}
+
+/// The "models" array, either at the top level or inside a proxy's wrapper object
+fn find_models_array(value: &serde_json::Value) -> Option<&Vec<serde_json::Value>> {
+    value["models"].as_array().or_else(|| {
+        value.as_object()?.values().find_map(|inner| inner["models"].as_array())
     })
 }
 
+/// Read the model list, skipping entries without a name and tolerating missing or string sizes
+fn parse_model_list(value: &serde_json::Value) -> Vec<ModelInfo> {
+    let models = match find_models_array(value) {
+        Some(models) => models,
+        None => return Vec::new(),
+    };
+    models.iter().filter_map(|model| {
+        let name = model["name"].as_str().or_else(|| model["model"].as_str())?;
+        let size = match &model["size"] {
+            serde_json::Value::Number(n) => n.as_u64(),
+            serde_json::Value::String(s) => s.trim().parse().ok(),
+            _ => None,
+        };
+        Some(ModelInfo { name: name.to_string(), size })
+    }).collect()
+}
+
 fn list_ollama_models(ollama_url: Option<String>) -> Result<()> {
     let url = get_ollama_url(ollama_url);
     info!("Listing Ollama models at {}...", url);
@@ -226,16 +1588,20 @@ fn list_ollama_models(ollama_url: Option<String>) -> Result<()> {
                 let data: serde_json::Value = response.json()?;
                 
                 println!("\nAvailable models:");
-                if let Some(models) = data["models"].as_array() {
+                if find_models_array(&data).is_none() {
+                    warn!("Unexpected response from {}: no \"models\" list in {}", api_url, data);
+                    println!("  Could not read the model list (is {} an Ollama server?)", url);
+                } else {
+                    let models = parse_model_list(&data);
+                    if models.is_empty() {
+                        println!("  No models found");
+                    }
                     for model in models {
-                        if let Some(name) = model["name"].as_str() {
-                            let size = model["size"].as_i64().unwrap_or(0);
-                            let size_gb = size as f64 / (1024.0 * 1024.0 * 1024.0);
-                            println!("  - {} ({:.1} GB)", name, size_gb);
+                        match model.size {
+                            Some(size) => println!("  - {} ({:.1} GB)", model.name, size as f64 / (1024.0 * 1024.0 * 1024.0)),
+                            None => println!("  - {}", model.name),
                         }
                     }
-                } else {
-                    println!("  No models found");
                 }
                 println!();
                 
@@ -257,36 +1623,70 @@ fn list_ollama_models(ollama_url: Option<String>) -> Result<()> {
 }
 
 fn pull_ollama_model(model: String, ollama_url: Option<String>) -> Result<()> {
-    let url = get_ollama_url(ollama_url);
-    info!("Pulling model {} from {}...", model, url);
-    
-    let client = reqwest::blocking::Client::new();
-    let api_url = format!("{}/api/pull", url);
-    
-    let request = serde_json::json!({
-        "name": model,
-        "stream": true
-    });
+    std::env::set_var("OLLAMA_HOST", get_ollama_url(ollama_url));
+    let ai_model = ai::local_model::LocalModel::new(&model)?;
+    pull_with_progress(&ai_model, &model).map_err(|e| anyhow::anyhow!("Failed to pull model: {}", e))
+}
+
+/// Pull a model, redrawing one progress line on stderr per download step
+fn pull_with_progress(ai_model: &ai::local_model::LocalModel, model: &str) -> Result<()> {
+    use std::io::Write;
     
     println!("Pulling model {}...", model);
     println!("This may take a while depending on the model size and your internet connection.");
-    
-    match client.post(&api_url).json(&request).send() {
-        Ok(response) => {
-            if response.status().is_success() {
-                println!("Model {} pulled successfully!", model);
-            } else {
-                error!("Failed to pull model: {}", response.text()?);
-            }
+    let mut last_status = String::new();
+    // Ctrl+C ends the whole process, so the pull never needs cancelling from here
+    let result = ai_model.pull(&std::sync::atomic::AtomicBool::new(false), |update| {
+        if update.status != last_status && !last_status.is_empty() {
+            eprintln!();
         }
-        Err(e) => {
-            error!("Failed to connect to Ollama: {}", e);
+        match update.fraction() {
+            Some(fraction) => eprint!("\r  {} {:>3.0}%", update.status, fraction * 100.0),
+            None => eprint!("\r  {}", update.status),
         }
+        let _ = std::io::stderr().flush();
+        last_status = update.status.clone();
+    });
+    if !last_status.is_empty() {
+        eprintln!();
     }
-    
+    result?;
+    println!("Model {} pulled successfully!", model);
     Ok(())
 }
 
+/// When the model isn't installed, say what vision models are and offer to pull it (asking on a terminal,
+/// otherwise printing the command). A server that can't be reached is left for the analysis to report
+fn offer_model_pull(ai_model: &ai::local_model::LocalModel, model: &str) -> Result<()> {
+    use std::io::{self, IsTerminal, Write};
+    
+    match ai_model.is_installed() {
+        Ok(false) => {}
+        _ => return Ok(()),
+    }
+    match ai_model.installed_vision_models() {
+        Ok(vision_models) if vision_models.is_empty() => {
+            eprintln!("No vision model is installed, and screenshots need one to be analyzed.");
+        }
+        Ok(vision_models) => {
+            eprintln!("Model '{}' isn't installed. Installed vision models: {}", model, vision_models.join(", "));
+            eprintln!("Use one of them with --model, or pull '{}'.", model);
+        }
+        Err(_) => eprintln!("Model '{}' isn't installed.", model),
+    }
+    if !io::stdin().is_terminal() {
+        eprintln!("Pull it with: ollama pull {}  (or: screensnap pull-model {})", model, model);
+        return Ok(());
+    }
+    eprint!("Pull {} now? [Y/n]: ", model);
+    io::stderr().flush()?;
+    if read_stdin_line().trim().eq_ignore_ascii_case("n") {
+        eprintln!("Skipped. Pull it later with: ollama pull {}", model);
+        return Ok(());
+    }
+    pull_with_progress(ai_model, model)
+}
+
 fn check_ollama_status(ollama_url: Option<String>) -> Result<()> {
     let url = get_ollama_url(ollama_url);
     info!("Checking Ollama status at {}...", url);
@@ -303,6 +1703,17 @@ fn check_ollama_status(ollama_url: Option<String>) -> Result<()> {
                 if let Some(models) = data["models"].as_array() {
                     println!("✓ {} model(s) available", models.len());
                 }
+                
+                std::env::set_var("OLLAMA_HOST", &url);
+                let ai_model = ai::local_model::LocalModel::new(ai::local_model::RECOMMENDED_VISION_MODEL)?;
+                match ai_model.installed_vision_models() {
+                    Ok(vision_models) if vision_models.is_empty() => {
+                        println!("✗ No vision model installed");
+                        offer_model_pull(&ai_model, ai::local_model::RECOMMENDED_VISION_MODEL)?;
+                    }
+                    Ok(vision_models) => println!("✓ Vision model(s): {}", vision_models.join(", ")),
+                    Err(e) => println!("? Could not check for vision models: {}", e),
+                }
             } else {
                 println!("✗ Ollama server error: {}", response.status());
             }
@@ -320,7 +1731,24 @@ fn check_ollama_status(ollama_url: Option<String>) -> Result<()> {
     Ok(())
 }
 
-fn run_interactive_mode() -> Result<()> {
+/// Print the Ollama server discovery would pick, or every address it tried
+fn discover_ollama_server() -> Result<()> {
+    match ai::local_model::discover_ollama() {
+        Some(url) => {
+            println!("✓ Found Ollama at {}", url);
+            println!("  Used when neither --ollama-url, OLLAMA_HOST nor the config file names a server");
+        }
+        None => {
+            println!("✗ No running Ollama server found. Tried:");
+            for url in ai::local_model::discovery_candidates() {
+                println!("  {}", url);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn run_interactive_mode(capture_backend: CaptureBackendKind, auto_analyze: bool) -> Result<()> {
     use std::io::{self, Write};
     
     println!("🖼️  ScreenSnap Interactive Mode");
@@ -328,10 +1756,11 @@ fn run_interactive_mode() -> Result<()> {
     println!();
     
     // Initialize the application
-    let model_name = "llava:latest".to_string();
+    let config = config::Config::load();
+    let model_name = config.resolve_model(None).value;
     
     // Initialize screenshot manager
-    let mut screenshot_manager = capture::screenshot::ScreenshotManager::new()?;
+    let mut screenshot_manager = capture::screenshot::ScreenshotManager::with_backend(capture_backend)?;
     
     let stdin = io::stdin();
     let mut input = String::new();
@@ -355,7 +1784,7 @@ fn run_interactive_mode() -> Result<()> {
                 match screenshot_manager.capture_screen() {
                     Ok(_) => {
                         println!("✓ Screen captured successfully");
-                        process_screenshot(&mut screenshot_manager, &model_name)?;
+                        process_screenshot(&mut screenshot_manager, &config, &model_name, auto_analyze)?;
                     },
                     Err(e) => {
                         println!("✗ Failed to capture screen: {}", e);
@@ -394,7 +1823,7 @@ fn run_interactive_mode() -> Result<()> {
                                 match screenshot_manager.capture_window(&title) {
                                     Ok(_) => {
                                         println!("✓ Window captured successfully");
-                                        process_screenshot(&mut screenshot_manager, &model_name)?;
+                                        process_screenshot(&mut screenshot_manager, &config, &model_name, auto_analyze)?;
                                     },
                                     Err(e) => {
                                         println!("✗ Failed to capture window: {}", e);
@@ -404,7 +1833,7 @@ fn run_interactive_mode() -> Result<()> {
                                             println!("✗ Full screen capture also failed: {}", e);
                                         } else {
                                             println!("✓ Full screen captured instead");
-                                            process_screenshot(&mut screenshot_manager, &model_name)?;
+                                            process_screenshot(&mut screenshot_manager, &config, &model_name, auto_analyze)?;
                                         }
                                     }
                                 }
@@ -434,34 +1863,468 @@ fn run_interactive_mode() -> Result<()> {
     Ok(())
 }
 
-fn process_screenshot(screenshot_manager: &mut capture::screenshot::ScreenshotManager, model_name: &str) -> Result<()> {
+fn run_stats(action: Option<StatsAction>) -> Result<()> {
+    let mut config = config::Config::load();
+    match action {
+        Some(StatsAction::Enable) | Some(StatsAction::Disable) => {
+            config.usage_stats = matches!(action, Some(StatsAction::Enable));
+            config.save()?;
+            println!("Usage statistics {}", if config.usage_stats { "enabled" } else { "disabled" });
+        }
+        Some(StatsAction::Clear) => {
+            stats::UsageStats::clear()?;
+            println!("Usage statistics cleared");
+        }
+        None => {
+            if !config.usage_stats {
+                println!("Usage statistics are off. Turn them on with: screensnap stats enable");
+                println!("(They are stored only on this machine and never sent anywhere.)\n");
+            }
+            let usage = stats::UsageStats::load();
+            if usage.captures.is_empty() && usage.models.is_empty() {
+                println!("No usage recorded yet.");
+                return Ok(());
+            }
+            
+            println!("Captures:");
+            for (target, count) in &usage.captures {
+                println!("  {:<12} {}", target, count);
+            }
+            println!("\nAnalyses:");
+            for (model_name, model_usage) in &usage.models {
+                println!(
+                    "  {:<20} {} ok, {} failed, {:.1}s average",
+                    model_name, model_usage.analyses, model_usage.failures, model_usage.average_seconds()
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+fn run_prompts(action: PromptsAction) -> Result<()> {
+    let mut library = prompts::PromptLibrary::load();
+    match action {
+        PromptsAction::Add { name, prompt } => {
+            let replaced = library.add(&name, &prompt)?;
+            library.save()?;
+            println!("{} prompt '{}'", if replaced.is_some() { "Updated" } else { "Saved" }, name.trim());
+        }
+        PromptsAction::List => {
+            if library.prompts.is_empty() {
+                println!("No saved prompts. Add one with: screensnap prompts add <name> <prompt>");
+            }
+            for (name, prompt) in &library.prompts {
+                println!("  {:<16} {}", name, prompt);
+            }
+        }
+        PromptsAction::Remove { name } => {
+            if !library.remove(&name) {
+                return Err(anyhow::anyhow!("No saved prompt named '{}'", name));
+            }
+            library.save()?;
+            println!("Removed prompt '{}'", name);
+        }
+    }
+    Ok(())
+}
+
+fn run_profile(action: ProfileAction) -> Result<()> {
+    let mut config = config::Config::load_file();
+    match action {
+        ProfileAction::List => {
+            if config.profiles.is_empty() {
+                println!("No profiles. Create one with: screensnap profile create <name> --model <model>");
+            }
+            let mut names: Vec<&String> = config.profiles.keys().collect();
+            names.sort();
+            for name in names {
+                let marker = if config.active_profile.as_ref() == Some(name) { "*" } else { " " };
+                println!("{} {:<16} {}", marker, name, serde_json::to_string(&config.profiles[name])?);
+            }
+        }
+        ProfileAction::Create { name, model, ollama_url, system_prompt } => {
+            let name = name.trim().to_string();
+            if name.is_empty() {
+                return Err(anyhow::anyhow!("Profile name can't be empty"));
+            }
+            if config.profiles.contains_key(&name) {
+                return Err(anyhow::anyhow!("A profile named '{}' already exists", name));
+            }
+            let profile = config::PartialConfig { model, ollama_url, system_prompt, ..Default::default() };
+            config.profiles.insert(name.clone(), profile);
+            config.save()?;
+            println!("Created profile '{}'. Switch to it with: screensnap profile use {}", name, name);
+        }
+        ProfileAction::Use { name } => {
+            if let Some(name) = &name {
+                if !config.profiles.contains_key(name) {
+                    return Err(anyhow::anyhow!("No profile named '{}' (see: screensnap profile list)", name));
+                }
+            }
+            config.active_profile = name;
+            config.save()?;
+            match &config.active_profile {
+                Some(name) => println!("Using profile '{}'", name),
+                None => println!("Using the base settings"),
+            }
+        }
+    }
+    Ok(())
+}
+
+fn run_config(action: ConfigAction) -> Result<()> {
+    use clap::ValueEnum;
+    
+    let ConfigAction::Show { model, ollama_url, format, cmd_timeout } = action;
+    let flags = config::FlagOverrides { model, ollama_url, format, cmd_timeout_secs: cmd_timeout };
+    let config = config::Config::load();
+    let resolved = config.resolved(&flags);
+    
+    match config::Config::path() {
+        Some(path) if path.exists() => println!("Config file: {}", path.display()),
+        Some(path) => println!("Config file: {} (not created yet)", path.display()),
+        None => println!("Config file: unavailable"),
+    }
+    match &config.applied_profile {
+        Some(name) => println!("Profile:     {}\n", name),
+        None => println!("Profile:     none\n"),
+    }
+    let format_name = resolved.format.value.to_possible_value().map_or_else(String::new, |v| v.get_name().to_string());
+    let rows = [
+        ("model", resolved.model.value, resolved.model.source),
+        ("ollama_url", resolved.ollama_url.value, resolved.ollama_url.source),
+        ("prompt", resolved.prompt.value, resolved.prompt.source),
+        ("system_prompt", resolved.system_prompt.value.unwrap_or_else(|| "(none)".to_string()), resolved.system_prompt.source),
+        ("format", format_name, resolved.format.source),
+        ("request_timeout", format!("{}s", resolved.request_timeout_secs.value), resolved.request_timeout_secs.source),
+        ("cmd_timeout", format!("{}s", resolved.cmd_timeout_secs.value), resolved.cmd_timeout_secs.source),
+    ];
+    for (name, value, source) in rows {
+        println!("{:<16} {:<8} {}", name, source.label(), value);
+    }
+    Ok(())
+}
+
+fn run_bench(models: Vec<String>, ollama_url: Option<String>, capture_backend: CaptureBackendKind) -> Result<()> {
+    std::env::set_var("OLLAMA_HOST", get_ollama_url(ollama_url));
+    
+    let mut screenshot_manager = capture::screenshot::ScreenshotManager::with_backend(capture_backend)?;
+    screenshot_manager.capture_screen()?;
+    let image_data = screenshot_manager.get_current_image_data()?;
+    
+    // One model at a time so only one is loaded into memory
+    let mut rows = Vec::new();
+    for model_name in &models {
+        println!("Running {}...", model_name);
+        let start = std::time::Instant::now();
+        let result = ai::local_model::LocalModel::new(model_name).and_then(|mut ai_model| {
+            let response = ai_model.process_image(&image_data)?;
+            Ok((response, ai_model.last_stats()))
+        });
+        let elapsed = start.elapsed();
+        
+        let (tokens_per_sec, summary) = match result {
+            Ok((response, stats)) => (
+                stats.map_or_else(|| "-".to_string(), |s| format!("{:.1}", s.tokens_per_second())),
+                response.lines().find(|line| !line.trim().is_empty()).unwrap_or("").trim().to_string(),
+            ),
+            Err(e) => {
+                error!("{} failed: {}", model_name, e);
+                ("-".to_string(), format!("error: {}", e))
+            }
+        };
+        rows.push((model_name.clone(), elapsed, tokens_per_sec, summary));
+    }
+    
+    let model_width = rows.iter().map(|row| row.0.len()).max().unwrap_or(0).max("Model".len());
+    println!("\n{:<mw$}  {:>9}  {:>9}  First line", "Model", "Time (s)", "Tokens/s", mw = model_width);
+    for (model_name, elapsed, tokens_per_sec, summary) in &rows {
+        let summary: String = summary.chars().take(80).collect();
+        println!("{:<mw$}  {:>9.1}  {:>9}  {}", model_name, elapsed.as_secs_f32(), tokens_per_sec, summary, mw = model_width);
+    }
+    Ok(())
+}
+
+fn run_batch_analyze(args: BatchArgs) -> Result<()> {
+    let files = batch::collect_images(&args.dir, args.glob.as_deref())?;
+    if files.is_empty() {
+        return Err(anyhow::anyhow!("No image files found in {}", args.dir.display()));
+    }
+    
+    let model_name = get_model_name(args.model.as_deref());
+    std::env::set_var("OLLAMA_HOST", get_ollama_url(args.ollama_url.clone()));
+    // One model for the whole batch, kept loaded between files
+    let mut ai_model = ai::local_model::LocalModel::new(&model_name)?;
+    ai_model.set_keep_alive(Some(&args.keep_alive));
+    if let Some(prompt) = &args.prompt {
+        ai_model.set_prompt(prompt);
+    }
+    let out_format = args.out_format.unwrap_or_else(|| batch::OutFormat::from_path(&args.out));
+    let mut writer = batch::AnalysisWriter::create(&args.out, out_format, "file")?;
+    
+    let mut failed = 0;
+    for (index, path) in files.iter().enumerate() {
+        let file = path.file_name().map_or_else(|| path.display().to_string(), |n| n.to_string_lossy().into_owned());
+        println!("[{}/{}] {}", index + 1, files.len(), file);
+        let start = std::time::Instant::now();
+        let result = std::fs::read(path)
+            .map_err(anyhow::Error::from)
+            .and_then(|image_data| {
+                capture::screenshot::ensure_analyzable(&image_data)?;
+                ai_model.process_image(&image_data)
+            });
+        stats::record_analysis(&model_name, start.elapsed(), result.is_ok());
+        
+        let result = result.map_err(|e| {
+            error!("{} failed: {}", file, e);
+            failed += 1;
+            e.to_string()
+        });
+        writer.write(&batch::AnalysisRecord::new(file, Some(&model_name), Some(ai_model.prompt()), Some(result)))?;
+    }
+    
+    println!("Analyzed {} file(s), {} failed. Results in {}", files.len(), failed, args.out.display());
+    Ok(())
+}
+
+fn run_embed(image: &std::path::Path, model: Option<String>, ollama_url: Option<String>, out: Option<PathBuf>) -> Result<()> {
+    let image_data = std::fs::read(image)
+        .map_err(|e| anyhow::anyhow!("Could not read {}: {}", image.display(), e))?;
+    capture::screenshot::ensure_analyzable(&image_data)?;
+    
+    let model_name = get_model_name(model.as_deref());
+    std::env::set_var("OLLAMA_HOST", get_ollama_url(ollama_url));
+    let mut ai_model = ai::local_model::LocalModel::new(&model_name)?;
+    let embedding = ai_model.embed_image(&image_data)?;
+    
+    let json = serde_json::to_string(&embedding)?;
+    match out {
+        Some(path) => {
+            std::fs::write(&path, json)?;
+            println!("Saved a {}-dimensional embedding to {}", embedding.len(), path.display());
+        }
+        None => println!("{}", json),
+    }
+    Ok(())
+}
+
+fn run_inspect(file: &std::path::Path) -> Result<()> {
+    if !matches!(image::ImageFormat::from_path(file), Ok(image::ImageFormat::Png)) {
+        println!("{} isn't a PNG, so it has no embedded metadata", file.display());
+        return Ok(());
+    }
+    let metadata = capture::screenshot::read_png_metadata(file)?;
+    if metadata.is_empty() {
+        println!("No metadata embedded in {}", file.display());
+        return Ok(());
+    }
+    let width = metadata.iter().map(|(keyword, _)| keyword.chars().count()).max().unwrap_or(0);
+    for (keyword, text) in &metadata {
+        println!("{:<width$}  {}", format!("{}:", keyword), text, width = width + 1);
+    }
+    Ok(())
+}
+
+/// Shortest allowed interval for `watch`, so a typo can't turn into a capture loop
+const MIN_WATCH_INTERVAL_SECS: f32 = 1.0;
+
+/// How long `watch --cache-bounds` trusts a window's position before looking it up again
+const WATCH_BOUNDS_CACHE_TTL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Exit code when a capture doesn't match its reference image
+const ASSERT_FAILED_EXIT_CODE: i32 = 1;
+
+/// Exit code when the comparison couldn't be made, e.g. the capture or the reference image failed
+const ASSERT_ERROR_EXIT_CODE: i32 = 2;
+
+fn run_assert(args: AssertArgs, capture_backend: CaptureBackendKind) -> Result<()> {
+    if !(0.0..=1.0).contains(&args.threshold) {
+        return Err(anyhow::anyhow!("--threshold must be between 0 and 1"));
+    }
+    let expected = image::open(&args.expect)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", args.expect.display(), e))?;
+    
+    let mut screenshot_manager = capture::screenshot::ScreenshotManager::with_backend(capture_backend)?;
+    match &args.window {
+        Some(window_title) => screenshot_manager.capture_window(window_title)?,
+        None => screenshot_manager.capture_screen()?,
+    }
+    let actual = screenshot_manager.get_current_image()
+        .ok_or_else(|| anyhow::anyhow!("No image captured"))?;
+    
+    let score = match capture::diff::similarity(&expected, actual) {
+        Some(score) => score,
+        None => {
+            println!(
+                "FAIL: capture is {}x{} but {} is {}x{}",
+                actual.width(), actual.height(), args.expect.display(), expected.width(), expected.height()
+            );
+            std::process::exit(ASSERT_FAILED_EXIT_CODE);
+        }
+    };
+    if score >= args.threshold {
+        println!("PASS: similarity {:.4} (threshold {})", score, args.threshold);
+        return Ok(());
+    }
+    
+    println!("FAIL: similarity {:.4} is below the threshold {}", score, args.threshold);
+    if let Some(diff_out) = &args.diff_out {
+        if let Some(pixel_diff) = capture::diff::pixel_diff(&expected, actual) {
+            pixel_diff.overlay.save(diff_out)?;
+            println!("{} pixel(s) differ, see {}", pixel_diff.changed_pixels, diff_out.display());
+        }
+    }
+    std::process::exit(ASSERT_FAILED_EXIT_CODE);
+}
+
+fn run_watch(args: WatchArgs, capture_backend: CaptureBackendKind) -> Result<()> {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+    
+    let (interval, count, out_dir, window) = (args.interval, args.count, args.out_dir.as_path(), args.window.as_deref());
+    if !interval.is_finite() || interval < MIN_WATCH_INTERVAL_SECS {
+        return Err(anyhow::anyhow!("Interval must be at least {} second(s)", MIN_WATCH_INTERVAL_SECS));
+    }
+    std::fs::create_dir_all(out_dir)?;
+    
+    let stop = Arc::new(AtomicBool::new(false));
+    let stop_handler = Arc::clone(&stop);
+    ctrlc::set_handler(move || stop_handler.store(true, Ordering::SeqCst))?;
+    
+    let mut screenshot_manager = capture::screenshot::ScreenshotManager::with_backend(capture_backend)?;
+    if args.cache_bounds {
+        screenshot_manager.set_bounds_cache(Some(WATCH_BOUNDS_CACHE_TTL));
+    }
+    let model_name = get_model_name(args.model.as_deref());
+    let mut ai_model = if args.analyze {
+        std::env::set_var("OLLAMA_HOST", get_ollama_url(args.ollama_url.clone()));
+        Some(ai::local_model::LocalModel::new(&model_name)?)
+    } else {
+        None
+    };
+    let prefix = window.map_or_else(|| "screen".to_string(), naming::sanitize_filename);
+    let filename_context = || naming::FilenameContext { window, monitor: None, timestamp: chrono::Local::now() };
+    if let Some(template) = &args.name_template {
+        // Reject a bad template up front rather than failing every frame
+        naming::render_filename(template, out_dir, &filename_context())?;
+    }
+    
+    // Records go to stdout, so status messages move to stderr
+    let mut records = args.out_format
+        .or(args.jsonl.then_some(batch::OutFormat::Jsonl))
+        .map(|format| batch::AnalysisWriter::new(std::io::stdout(), format, "image"));
+    let machine_output = records.is_some();
+    
+    let status = format!("Capturing every {}s into {} (Ctrl+C to stop)", interval, out_dir.display());
+    if machine_output { eprintln!("{}", status) } else { println!("{}", status) }
+    let mut saved = 0u32;
+    let mut failed = 0u32;
+    // Full-screen frames land in one buffer reused every tick instead of a new image each time
+    let mut frame = image::DynamicImage::ImageRgba8(image::RgbaImage::new(0, 0));
+    while !stop.load(Ordering::SeqCst) {
+        let tick = Instant::now();
+        
+        let captured = match (window, frame.as_mut_rgba8()) {
+            (Some(title), _) => screenshot_manager.capture_window(title),
+            (None, Some(buffer)) => screenshot_manager.capture_screen_into(buffer),
+            (None, None) => Err(anyhow::anyhow!("The frame buffer isn't RGBA")),
+        };
+        let result = captured.and_then(|_| {
+            let image = match window {
+                Some(_) => screenshot_manager.get_current_image().ok_or_else(|| anyhow::anyhow!("No image captured"))?,
+                None => &frame,
+            };
+            let image_path = match &args.name_template {
+                Some(template) => naming::render_filename(template, out_dir, &filename_context())?,
+                None => {
+                    let timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S%.3f");
+                    out_dir.join(format!("{}-{}.png", prefix, timestamp))
+                }
+            };
+            capture::screenshot::save_png(image, &image_path, args.png_compression)?;
+            info!("Saved {}", image_path.display());
+            
+            let mut analysis = None;
+            if let Some(ai_model) = ai_model.as_mut() {
+                let image_data = capture::screenshot::encode_png(image)?;
+                analysis = Some(match ai_model.process_image(&image_data) {
+                    Ok(response) => {
+                        std::fs::write(image_path.with_extension("txt"), &response)?;
+                        Ok(response)
+                    }
+                    Err(e) => {
+                        warn!("Analysis of {} failed: {}", image_path.display(), e);
+                        Err(e.to_string())
+                    }
+                });
+            }
+            if let Some(records) = records.as_mut() {
+                let model = ai_model.as_ref().map(|_| model_name.as_str());
+                let prompt = ai_model.as_ref().map(|ai_model| ai_model.prompt());
+                records.write(&batch::AnalysisRecord::new(image_path.display().to_string(), model, prompt, analysis))?;
+            }
+            Ok(())
+        });
+        match result {
+            Ok(()) => saved += 1,
+            Err(e) => {
+                // Skip the frame and keep watching
+                failed += 1;
+                error!("Capture failed, skipping frame: {}", e);
+            }
+        }
+        if count.map_or(false, |count| saved + failed >= count) {
+            break;
+        }
+        
+        // Sleep in small steps so Ctrl+C stops promptly
+        let interval = Duration::from_secs_f32(interval);
+        while !stop.load(Ordering::SeqCst) && tick.elapsed() < interval {
+            std::thread::sleep(Duration::from_millis(100).min(interval.saturating_sub(tick.elapsed())));
+        }
+    }
+    
+    let status = format!("Stopped watching: {} capture(s) saved, {} skipped", saved, failed);
+    if machine_output { eprintln!("{}", status) } else { println!("{}", status) }
+    Ok(())
+}
+
+fn process_screenshot(screenshot_manager: &mut capture::screenshot::ScreenshotManager, config: &config::Config, model_name: &str, auto_analyze: bool) -> Result<()> {
     use std::io::{self, Write};
     
     // Get the image data
     match screenshot_manager.get_current_image_data() {
         Ok(image_data) => {
-            // Save options
-            println!("\nScreenshot Options:");
-            println!("1. Analyze with AI ({})", model_name);
-            println!("2. Save to file");
-            println!("3. Both analyze and save");
-            println!("4. Return to main menu");
-            print!("\nEnter your choice (1-4): ");
-            io::stdout().flush()?;
-            
             let mut input = String::new();
-            io::stdin().lock().read_line(&mut input)?;
-            let choice = input.trim();
-            
-            let analyze = matches!(choice, "1" | "3");
-            let save = matches!(choice, "2" | "3");
+            let (analyze, save) = if auto_analyze {
+                // Analysis always happens, so only saving is left to ask about
+                print!("\nSave to file as well? (y/N): ");
+                io::stdout().flush()?;
+                io::stdin().lock().read_line(&mut input)?;
+                (true, input.trim().eq_ignore_ascii_case("y"))
+            } else {
+                // Save options
+                println!("\nScreenshot Options:");
+                println!("1. Analyze with AI ({})", model_name);
+                println!("2. Save to file");
+                println!("3. Both analyze and save");
+                println!("4. Return to main menu");
+                print!("\nEnter your choice (1-4): ");
+                io::stdout().flush()?;
+                
+                io::stdin().lock().read_line(&mut input)?;
+                let choice = input.trim();
+                (matches!(choice, "1" | "3"), matches!(choice, "2" | "3"))
+            };
             
             // Process with AI if requested
             if analyze {
                 println!("\nAnalyzing screenshot with {}...", model_name);
                 
                 // Set Ollama URL as environment variable
-                std::env::set_var("OLLAMA_HOST", &get_ollama_url(None));
+                std::env::set_var("OLLAMA_HOST", config.resolve_ollama_url(None).value);
                 
                 // Initialize Ollama model
                 match ai::local_model::LocalModel::new(model_name) {
@@ -472,7 +2335,7 @@ fn process_screenshot(screenshot_manager: &mut capture::screenshot::ScreenshotMa
                         match ai_model.process_image(&image_data) {
                             Ok(response) => {
                                 println!("\n=== AI Analysis ({}) ===", model_name);
-                                println!("{}", response);
+                                println!("{}", config.post_process(&response));
                                 println!("===========================================\n");
                             }
                             Err(e) => {
@@ -508,7 +2371,7 @@ fn process_screenshot(screenshot_manager: &mut capture::screenshot::ScreenshotMa
                 if !filename.is_empty() {
                     if let Some(image) = screenshot_manager.get_current_image() {
                         let path = std::path::Path::new(filename);
-                        image.save_with_format(path, ImageFormat::Png)?;
+                        capture::screenshot::save_image(image, path, PngCompression::Default)?;
                         println!("✓ Screenshot saved to: {}", filename);
                     }
                 }
@@ -520,4 +2383,35 @@ fn process_screenshot(screenshot_manager: &mut capture::screenshot::ScreenshotMa
     }
     
     Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn model_list_reads_names_and_sizes() {
+        let tags = serde_json::json!({
+            "models": [
+                { "name": "llava:latest", "model": "llava:latest", "size": 4733363377u64, "digest": "8dd30f6b0cb1" },
+                { "name": "llama3:8b", "size": "4661224676" },
+                { "model": "moondream:latest" },
+                { "size": 12 }
+            ]
+        });
+        let models = parse_model_list(&tags);
+        let names: Vec<&str> = models.iter().map(|model| model.name.as_str()).collect();
+        assert_eq!(names, ["llava:latest", "llama3:8b", "moondream:latest"]);
+        let sizes: Vec<Option<u64>> = models.iter().map(|model| model.size).collect();
+        assert_eq!(sizes, [Some(4733363377), Some(4661224676), None]);
+    }
+
+    #[test]
+    fn model_list_finds_a_wrapped_array() {
+        let tags = serde_json::json!({ "data": { "models": [{ "name": "llava:7b" }] } });
+        let models = parse_model_list(&tags);
+        assert_eq!(models.len(), 1);
+        assert_eq!(models[0].name, "llava:7b");
+        assert!(parse_model_list(&serde_json::json!({ "error": "unauthorized" })).is_empty());
+    }
 }
\ No newline at end of file
@@ -0,0 +1,95 @@
+// src/markdown.rs
+use eframe::egui;
+use egui::text::{LayoutJob, TextFormat};
+use egui::{Color32, FontFamily, FontId};
+
+const BODY_SIZE: f32 = 14.0;
+const CODE_BACKGROUND: Color32 = Color32::from_rgb(30, 30, 30);
+
+/// Lay out the markdown models usually emit: headings, bullet lists, **bold**, `inline code` and fenced
+/// code blocks. Anything else is shown as written
+pub fn layout(text: &str, color: Color32) -> LayoutJob {
+    let body = TextFormat {
+        font_id: FontId::new(BODY_SIZE, FontFamily::Proportional),
+        // Bold text is drawn in the full color, so the rest is toned down a little
+        color: color.gamma_multiply(0.85),
+        ..Default::default()
+    };
+    let code = TextFormat {
+        font_id: FontId::new(BODY_SIZE - 1.0, FontFamily::Monospace),
+        color,
+        background: CODE_BACKGROUND,
+        ..Default::default()
+    };
+
+    let mut job = LayoutJob::default();
+    let mut in_code_block = false;
+    let mut first_line = true;
+    for line in text.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if !first_line {
+            job.append("\n", 0.0, body.clone());
+        }
+        first_line = false;
+
+        if in_code_block {
+            job.append(line, 0.0, code.clone());
+            continue;
+        }
+        let trimmed = line.trim_start();
+        let level = trimmed.chars().take_while(|&c| c == '#').count();
+        if (1..=6).contains(&level) && trimmed[level..].starts_with(' ') {
+            let heading = TextFormat {
+                font_id: FontId::new(BODY_SIZE + (4 - level.min(3)) as f32 * 2.0, FontFamily::Proportional),
+                color,
+                ..Default::default()
+            };
+            append_inline(&mut job, trimmed[level..].trim(), &heading, &code, color);
+        } else if let Some(item) = ["- ", "* ", "+ "].iter().find_map(|marker| trimmed.strip_prefix(marker)) {
+            let indent = &line[..line.len() - trimmed.len()];
+            job.append(&format!("{}• ", indent), 0.0, body.clone());
+            append_inline(&mut job, item, &body, &code, color);
+        } else {
+            append_inline(&mut job, line, &body, &code, color);
+        }
+    }
+    job
+}
+
+/// Append a line's text, handling `code` spans and **bold**. Unpaired markers are shown as written
+fn append_inline(job: &mut LayoutJob, text: &str, format: &TextFormat, code: &TextFormat, strong_color: Color32) {
+    let code_spans = text.matches('`').count() >= 2;
+    let mut rest = text;
+    while !rest.is_empty() {
+        let next_code = if code_spans { rest.find('`') } else { None };
+        let next_bold = rest.find("**").filter(|&start| rest[start + 2..].contains("**"));
+        match (next_code, next_bold) {
+            (Some(start), bold) if bold.map_or(true, |bold| start < bold) => {
+                let end = match rest[start + 1..].find('`') {
+                    Some(offset) => start + 1 + offset,
+                    None => {
+                        job.append(rest, 0.0, format.clone());
+                        return;
+                    }
+                };
+                job.append(&rest[..start], 0.0, format.clone());
+                job.append(&rest[start + 1..end], 0.0, code.clone());
+                rest = &rest[end + 1..];
+            }
+            (_, Some(start)) => {
+                let end = start + 2 + rest[start + 2..].find("**").unwrap_or(0);
+                job.append(&rest[..start], 0.0, format.clone());
+                let strong = TextFormat { color: strong_color, ..format.clone() };
+                job.append(&rest[start + 2..end], 0.0, strong);
+                rest = &rest[end + 2..];
+            }
+            _ => {
+                job.append(rest, 0.0, format.clone());
+                return;
+            }
+        }
+    }
+}
\ No newline at end of file
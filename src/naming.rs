@@ -0,0 +1,100 @@
+// src/naming.rs
+use anyhow::{Result, anyhow};
+use std::path::{Path, PathBuf};
+
+/// Default template for captures saved with --save-dir
+pub const DEFAULT_NAME_TEMPLATE: &str = "{date}-{time}-{n}.png";
+
+/// Values available to filename templates
+pub struct FilenameContext<'a> {
+    pub window: Option<&'a str>,
+    pub monitor: Option<usize>,
+    pub timestamp: chrono::DateTime<chrono::Local>,
+}
+
+/// Replace characters that aren't safe in file names
+pub fn sanitize_filename(name: &str) -> String {
+    let sanitized: String = name.trim()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    if sanitized.is_empty() { "capture".to_string() } else { sanitized }
+}
+
+enum Segment {
+    Literal(String),
+    Value(String),
+    Counter,
+}
+
+/// Resolve a template such as `{date}-{window}-{n}.png` to a path in `dir`.
+/// `{n}` counts up from 1 until the name is unused. Without `{n}`, a name that is already taken gets
+/// "-2", "-3", ... before its extension rather than overwriting the file. Unknown placeholders are an error
+pub fn render_filename(template: &str, dir: &Path, ctx: &FilenameContext) -> Result<PathBuf> {
+    let mut segments = Vec::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        segments.push(Segment::Literal(rest[..open].to_string()));
+        let close = rest[open..].find('}')
+            .map(|offset| open + offset)
+            .ok_or_else(|| anyhow!("Unclosed '{{' in name template '{}'", template))?;
+        let segment = match &rest[open + 1..close] {
+            "date" => Segment::Value(ctx.timestamp.format("%Y-%m-%d").to_string()),
+            "time" => Segment::Value(ctx.timestamp.format("%H%M%S").to_string()),
+            "window" => Segment::Value(ctx.window.map_or_else(|| "screen".to_string(), sanitize_filename)),
+            "monitor" => Segment::Value(ctx.monitor.unwrap_or(0).to_string()),
+            "n" => Segment::Counter,
+            other => return Err(anyhow!(
+                "Unknown placeholder '{{{}}}' in name template (use {{date}}, {{time}}, {{window}}, {{monitor}} or {{n}})",
+                other
+            )),
+        };
+        segments.push(segment);
+        rest = &rest[close + 1..];
+    }
+    segments.push(Segment::Literal(rest.to_string()));
+
+    let has_counter = segments.iter().any(|s| matches!(s, Segment::Counter));
+    let mut n = 1u32;
+    loop {
+        let mut name: String = segments.iter().map(|segment| match segment {
+            Segment::Literal(text) | Segment::Value(text) => text.clone(),
+            Segment::Counter => n.to_string(),
+        }).collect();
+        if Path::new(&name).extension().is_none() {
+            name.push_str(".png");
+        }
+
+        let mut path = dir.join(&name);
+        if !has_counter && n > 1 {
+            let stem = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+            let extension = path.extension().map(|ext| ext.to_string_lossy().into_owned()).unwrap_or_default();
+            path.set_file_name(format!("{}-{}.{}", stem, n, extension));
+        }
+        if !path.exists() {
+            return Ok(path);
+        }
+        n += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn template_without_counter_does_not_overwrite() {
+        let dir = std::env::temp_dir().join(format!("screensnap-naming-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let ctx = FilenameContext { window: Some("My Editor"), monitor: None, timestamp: chrono::Local::now() };
+
+        let first = render_filename("{window}.png", &dir, &ctx).unwrap();
+        assert_eq!(first, dir.join("My_Editor.png"));
+        std::fs::write(&first, b"taken").unwrap();
+        let second = render_filename("{window}.png", &dir, &ctx).unwrap();
+        assert_eq!(second, dir.join("My_Editor-2.png"));
+        std::fs::write(&second, b"taken").unwrap();
+        assert_eq!(render_filename("{window}", &dir, &ctx).unwrap(), dir.join("My_Editor-3.png"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
\ No newline at end of file
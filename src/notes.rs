@@ -0,0 +1,57 @@
+// src/notes.rs
+use anyhow::Result;
+use serde::{Serialize, Deserialize};
+use std::path::PathBuf;
+
+use crate::config;
+
+/// An answer the user pinned to keep in view
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PinnedNote {
+    pub text: String,
+    /// Model that wrote it
+    pub model: Option<String>,
+    /// When it was pinned, as RFC 3339
+    pub pinned_at: String,
+}
+
+/// Pinned answers, oldest first, kept next to the config file so they outlive /clear and restarts
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct PinnedNotes {
+    pub notes: Vec<PinnedNote>,
+}
+
+impl PinnedNotes {
+    /// Location of the notes file, e.g. ~/.config/screensnap/pinned_notes.json
+    pub fn path() -> Option<PathBuf> {
+        config::sibling_path("pinned_notes.json")
+    }
+
+    /// The saved file, or an empty one. An unreadable file is backed up first (see `config::load_json_file`)
+    pub fn load() -> Self {
+        config::load_json_file(Self::path())
+    }
+
+    pub fn save(&self) -> Result<()> {
+        config::save_json_file(Self::path(), self)
+    }
+
+    /// Pin an answer; returns false if the same text is already pinned
+    pub fn pin(&mut self, text: &str, model: Option<&str>) -> bool {
+        if self.notes.iter().any(|note| note.text == text) {
+            return false;
+        }
+        self.notes.push(PinnedNote {
+            text: text.to_string(),
+            model: model.map(str::to_string),
+            pinned_at: chrono::Local::now().to_rfc3339(),
+        });
+        true
+    }
+
+    /// Unpin the note at `index`, if there is one
+    pub fn remove(&mut self, index: usize) -> Option<PinnedNote> {
+        (index < self.notes.len()).then(|| self.notes.remove(index))
+    }
+}
\ No newline at end of file
@@ -0,0 +1,159 @@
+// src/ocr.rs
+use anyhow::{Result, anyhow};
+use image::DynamicImage;
+use std::process::Command;
+
+use crate::capture::roi::Roi;
+
+/// A word tesseract found, with its box in image pixels
+pub struct OcrWord {
+    pub text: String,
+    pub rect: Roi,
+    /// Recognition confidence, 0 to 100
+    pub confidence: f32,
+    /// Words on the same line share this (block, paragraph, line) key
+    line: (u32, u32, u32),
+}
+
+/// Read the text in an image with the tesseract CLI, which must be on PATH. Only the GUI's
+/// "Copy Text" uses it, which also needs the clipboard
+#[cfg(feature = "clipboard")]
+pub fn recognize_text(image: &DynamicImage) -> Result<String> {
+    run_tesseract(image, &[])
+}
+
+/// Every word in an image with its bounding box, in reading order
+pub fn recognize_words(image: &DynamicImage) -> Result<Vec<OcrWord>> {
+    let tsv = run_tesseract(image, &["tsv"])?;
+    // Columns: level page block paragraph line word left top width height confidence text; level 5 is a word
+    Ok(tsv.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() < 12 || fields[0] != "5" || fields[11].trim().is_empty() {
+                return None;
+            }
+            let number = |index: usize| fields[index].parse::<i64>().ok();
+            Some(OcrWord {
+                text: fields[11].trim().to_string(),
+                rect: Roi {
+                    x: number(6)? as i32,
+                    y: number(7)? as i32,
+                    width: number(8)? as u32,
+                    height: number(9)? as u32,
+                },
+                confidence: fields[10].parse().unwrap_or(0.0),
+                line: (number(2)? as u32, number(3)? as u32, number(4)? as u32),
+            })
+        })
+        .collect())
+}
+
+/// Lowercase letters and digits only, so "Submit," matches "submit"
+fn normalize(text: &str) -> String {
+    text.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+/// The box around the words matching `query`, which may span several words on one line.
+/// An exact match beats one that only contains the query; ties go to the most confident words
+pub fn locate_text(words: &[OcrWord], query: &str) -> Option<Roi> {
+    let query = normalize(query);
+    if query.is_empty() {
+        return None;
+    }
+    let mut best: Option<(bool, f32, Roi)> = None;
+    for start in 0..words.len() {
+        let mut joined = String::new();
+        for end in start..words.len() {
+            if words[end].line != words[start].line {
+                break;
+            }
+            joined.push_str(&normalize(&words[end].text));
+            if !joined.contains(&query) {
+                continue;
+            }
+            let matched = &words[start..=end];
+            let exact = joined == query;
+            let confidence = matched.iter().map(|word| word.confidence).sum::<f32>() / matched.len() as f32;
+            let rect = matched.iter().skip(1).fold(matched[0].rect, |bounds, word| union(bounds, word.rect));
+            if best.map_or(true, |(best_exact, best_confidence, _)| (exact, confidence) > (best_exact, best_confidence)) {
+                best = Some((exact, confidence, rect));
+            }
+            break;
+        }
+    }
+    best.map(|(_, _, rect)| rect)
+}
+
+fn union(a: Roi, b: Roi) -> Roi {
+    let left = a.x.min(b.x);
+    let top = a.y.min(b.y);
+    let right = (a.x + a.width as i32).max(b.x + b.width as i32);
+    let bottom = (a.y + a.height as i32).max(b.y + b.height as i32);
+    Roi { x: left, y: top, width: (right - left) as u32, height: (bottom - top) as u32 }
+}
+
+/// Run tesseract on an image with extra config arguments (e.g. "tsv") and return what it printed
+fn run_tesseract(image: &DynamicImage, config: &[&str]) -> Result<String> {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let input_path = std::env::temp_dir().join(format!("screensnap-ocr-{}-{}.png", std::process::id(), nanos));
+    image.save(&input_path)?;
+
+    // "stdout" as the output base makes tesseract print the text instead of writing a file
+    let output = Command::new("tesseract")
+        .arg(&input_path)
+        .arg("stdout")
+        .args(config)
+        .output();
+    let _ = std::fs::remove_file(&input_path);
+    let output = output.map_err(|e| anyhow!("Text recognition needs tesseract ({})", e))?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "tesseract exited with {}: {}",
+            output.status, String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(text: &str, x: i32, line: u32, confidence: f32) -> OcrWord {
+        OcrWord {
+            text: text.to_string(),
+            rect: Roi { x, y: line as i32 * 20, width: 40, height: 12 },
+            confidence,
+            line: (1, 1, line),
+        }
+    }
+
+    #[test]
+    fn words_on_one_line_are_joined() {
+        let words = [word("Save", 10, 1, 90.0), word("as", 60, 1, 90.0), word("PDF", 110, 1, 90.0)];
+        assert_eq!(locate_text(&words, "save as"), Some(Roi { x: 10, y: 20, width: 90, height: 12 }));
+    }
+
+    #[test]
+    fn exact_match_beats_a_partial_one() {
+        let words = [word("Submitted", 10, 1, 95.0), word("Submit,", 10, 2, 60.0)];
+        assert_eq!(locate_text(&words, "submit"), Some(words[1].rect));
+    }
+
+    #[test]
+    fn ties_go_to_the_most_confident_word() {
+        let words = [word("OK", 10, 1, 50.0), word("OK", 10, 2, 90.0), word("OK", 10, 3, 70.0)];
+        assert_eq!(locate_text(&words, "ok"), Some(words[1].rect));
+    }
+
+    #[test]
+    fn matches_stop_at_line_breaks() {
+        let words = [word("Save", 10, 1, 90.0), word("as", 10, 2, 90.0)];
+        assert_eq!(locate_text(&words, "save as"), None);
+        assert_eq!(locate_text(&words, ""), None);
+    }
+}
\ No newline at end of file
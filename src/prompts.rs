@@ -0,0 +1,54 @@
+// src/prompts.rs
+use anyhow::{Result, anyhow};
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::config;
+
+/// The user's saved prompts by name, kept next to the config file
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct PromptLibrary {
+    pub prompts: BTreeMap<String, String>,
+}
+
+impl PromptLibrary {
+    /// Location of the library file, e.g. ~/.config/screensnap/prompts.json
+    pub fn path() -> Option<PathBuf> {
+        config::sibling_path("prompts.json")
+    }
+
+    /// The saved file, or an empty one. An unreadable file is backed up first (see `config::load_json_file`)
+    pub fn load() -> Self {
+        config::load_json_file(Self::path())
+    }
+
+    pub fn save(&self) -> Result<()> {
+        config::save_json_file(Self::path(), self)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.prompts.get(name).map(String::as_str)
+    }
+
+    /// The prompt saved under `name`, with an error naming the command that lists them
+    pub fn require(&self, name: &str) -> Result<&str> {
+        self.get(name)
+            .ok_or_else(|| anyhow!("No saved prompt named '{}' (see `screensnap prompts list`)", name))
+    }
+
+    /// Save a prompt under `name`, returning the one it replaced
+    pub fn add(&mut self, name: &str, prompt: &str) -> Result<Option<String>> {
+        let name = name.trim();
+        if name.is_empty() || prompt.trim().is_empty() {
+            return Err(anyhow!("A saved prompt needs a name and some text"));
+        }
+        Ok(self.prompts.insert(name.to_string(), prompt.to_string()))
+    }
+
+    /// Delete a prompt, returning false if there was none by that name
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.prompts.remove(name).is_some()
+    }
+}
\ No newline at end of file
@@ -0,0 +1,55 @@
+// src/rerun.rs
+use anyhow::{Result, anyhow};
+use image::DynamicImage;
+use serde::{Serialize, Deserialize};
+use std::path::PathBuf;
+
+use crate::config;
+
+/// The text half of the last analysis; the capture itself is stored next to it as a PNG
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+struct LastRequest {
+    prompt: Option<String>,
+    model: String,
+}
+
+/// Input of the last Ollama analysis, for --rerun-with
+pub struct LastAnalysis {
+    pub image: DynamicImage,
+    /// The expanded custom prompt, or None for the default one
+    pub prompt: Option<String>,
+    /// Model that answered it
+    pub model: String,
+}
+
+/// Where the last analysis is kept, e.g. ~/.config/screensnap/last_analysis
+fn dir() -> Option<PathBuf> {
+    config::sibling_path("last_analysis")
+}
+
+/// Remember an analysis' PNG image data and prompt, replacing the previous one
+pub fn save(image_data: &[u8], prompt: Option<&str>, model: &str) -> Result<()> {
+    let dir = dir().ok_or_else(|| anyhow!("No config directory available"))?;
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(dir.join("capture.png"), image_data)?;
+    let request = LastRequest { prompt: prompt.map(str::to_string), model: model.to_string() };
+    std::fs::write(dir.join("request.json"), serde_json::to_string_pretty(&request)?)?;
+    Ok(())
+}
+
+/// Load the last analysis saved by `save`
+pub fn load() -> Result<LastAnalysis> {
+    let dir = dir().ok_or_else(|| anyhow!("No config directory available"))?;
+    let image_path = dir.join("capture.png");
+    if !image_path.exists() {
+        return Err(anyhow!("No previous analysis to re-run; analyze a capture first"));
+    }
+    let image = image::open(&image_path)
+        .map_err(|e| anyhow!("Failed to read the last capture from {}: {}", image_path.display(), e))?;
+    let request: LastRequest = match std::fs::read_to_string(dir.join("request.json")) {
+        Ok(contents) => serde_json::from_str(&contents).map_err(|e| anyhow!("The last analysis' prompt file is corrupt: {}", e))?,
+        Err(_) => LastRequest::default(),
+    };
+    Ok(LastAnalysis { image, prompt: request.prompt, model: request.model })
+}
\ No newline at end of file
@@ -0,0 +1,88 @@
+// src/stats.rs
+use anyhow::Result;
+use log::warn;
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::config::{self, Config};
+
+/// Analysis counters for one model
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct ModelUsage {
+    pub analyses: u64,
+    pub failures: u64,
+    /// Total time spent in successful analyses, in seconds
+    pub total_seconds: f64,
+}
+
+impl ModelUsage {
+    pub fn average_seconds(&self) -> f64 {
+        if self.analyses > 0 { self.total_seconds / self.analyses as f64 } else { 0.0 }
+    }
+}
+
+/// Local-only usage counters, kept next to the config file. Nothing here leaves the machine
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct UsageStats {
+    /// Captures keyed by target, e.g. "primary", "monitor 1", "window", "region"
+    pub captures: BTreeMap<String, u64>,
+    pub models: BTreeMap<String, ModelUsage>,
+}
+
+impl UsageStats {
+    /// Location of the stats file, e.g. ~/.config/screensnap/usage_stats.json
+    pub fn path() -> Option<PathBuf> {
+        config::sibling_path("usage_stats.json")
+    }
+
+    /// The saved counters, or none yet. An unreadable file is backed up first (see `config::load_json_file`)
+    pub fn load() -> Self {
+        config::load_json_file(Self::path())
+    }
+
+    pub fn save(&self) -> Result<()> {
+        config::save_json_file(Self::path(), self)
+    }
+
+    /// Delete the stats file
+    pub fn clear() -> Result<()> {
+        match Self::path() {
+            Some(path) if path.exists() => Ok(std::fs::remove_file(path)?),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Apply `update` to the stored stats, but only if the user opted in
+fn record(update: impl FnOnce(&mut UsageStats)) {
+    if !Config::load().usage_stats {
+        return;
+    }
+    let mut stats = UsageStats::load();
+    update(&mut stats);
+    if let Err(e) = stats.save() {
+        warn!("Failed to save usage stats: {}", e);
+    }
+}
+
+/// Count a capture of the given target
+pub fn record_capture(target: &str) {
+    record(|stats| *stats.captures.entry(target.to_string()).or_default() += 1);
+}
+
+/// Count an analysis and, when it succeeded, how long it took
+pub fn record_analysis(model_name: &str, duration: Duration, success: bool) {
+    record(|stats| {
+        let usage = stats.models.entry(model_name.to_string()).or_default();
+        if success {
+            usage.analyses += 1;
+            usage.total_seconds += duration.as_secs_f64();
+        } else {
+            usage.failures += 1;
+        }
+    });
+}
\ No newline at end of file